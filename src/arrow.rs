@@ -0,0 +1,453 @@
+//! Columnar output of `FlatMftEntryWithName` rows, as Arrow record batches or an Arrow IPC stream,
+//! and as a Parquet file with the `parquet` feature.
+//!
+//! The column names match the CSV header, flags and enums are written as strings like in the CSV output.
+use crate::csv::FlatMftEntryWithName;
+
+use arrow_array::{
+    ArrayRef, BooleanArray, Int64Array, RecordBatch, StringArray, TimestampNanosecondArray,
+    UInt16Array, UInt32Array, UInt64Array,
+};
+use arrow_ipc::writer::StreamWriter;
+use arrow_schema::{ArrowError, DataType, Field, Schema, SchemaRef, TimeUnit};
+use chrono::{DateTime, Utc};
+#[cfg(feature = "parquet")]
+use parquet::{
+    arrow::ArrowWriter, basic::Compression, errors::ParquetError,
+    file::properties::WriterProperties,
+};
+
+use std::io::Write;
+use std::sync::Arc;
+
+/// The number of rows buffered by `FlatMftEntryStreamWriter` before writing a record batch.
+const DEFAULT_BATCH_SIZE: usize = 8192;
+
+/// Returns the schema of the record batches built by `to_record_batch`.
+pub fn schema() -> Schema {
+    let timestamp = || DataType::Timestamp(TimeUnit::Nanosecond, Some("UTC".into()));
+
+    Schema::new(vec![
+        Field::new("Signature", DataType::Utf8, false),
+        Field::new("EntryId", DataType::UInt64, false),
+        Field::new("Sequence", DataType::UInt16, false),
+        Field::new("BaseEntryId", DataType::UInt64, false),
+        Field::new("BaseEntrySequence", DataType::UInt16, false),
+        Field::new("HardLinkCount", DataType::UInt16, false),
+        Field::new("Flags", DataType::Utf8, false),
+        Field::new("UsedEntrySize", DataType::UInt32, false),
+        Field::new("TotalEntrySize", DataType::UInt32, false),
+        Field::new("FirstAttributeRecordOffset", DataType::UInt16, false),
+        Field::new("FirstAttributeId", DataType::UInt16, false),
+        Field::new("LogfileSequenceNumber", DataType::UInt64, false),
+        Field::new("FileSize", DataType::UInt64, false),
+        Field::new("IsADirectory", DataType::Boolean, false),
+        Field::new("IsDeleted", DataType::Boolean, false),
+        Field::new("HasAlternateDataStreams", DataType::Boolean, false),
+        Field::new("HasSuspiciousName", DataType::Boolean, false),
+        Field::new("StandardInfoFlags", DataType::Utf8, true),
+        Field::new("StandardInfoLastModified", timestamp(), true),
+        Field::new("StandardInfoLastAccess", timestamp(), true),
+        Field::new("StandardInfoCreated", timestamp(), true),
+        Field::new("Usn", DataType::UInt64, false),
+        Field::new("FileNameFlags", DataType::Utf8, true),
+        Field::new("FileNameLastModified", timestamp(), true),
+        Field::new("FileNameLastAccess", timestamp(), true),
+        Field::new("FileNameCreated", timestamp(), true),
+        Field::new("CreatedDeltaSecs", DataType::Int64, true),
+        Field::new("LastModifiedDeltaSecs", DataType::Int64, true),
+        Field::new("LastAccessDeltaSecs", DataType::Int64, true),
+        Field::new("ObjectIdTimestamp", timestamp(), true),
+        Field::new("ObjectId", DataType::Utf8, true),
+        Field::new("BirthVolumeId", DataType::Utf8, true),
+        Field::new("BirthObjectId", DataType::Utf8, true),
+        Field::new("DomainId", DataType::Utf8, true),
+        Field::new("ReparseTag", DataType::Utf8, true),
+        Field::new("ReparseTarget", DataType::Utf8, true),
+        Field::new("ParentReferenceStatus", DataType::Utf8, true),
+        Field::new("IsOrphan", DataType::Boolean, false),
+        Field::new("DataSizeConsistency", DataType::Utf8, false),
+        Field::new("TimestompIndicators", DataType::Utf8, true),
+        Field::new("FullPath", DataType::Utf8, false),
+    ])
+}
+
+/// Builds a record batch (with the columns of `schema`) from the given rows.
+pub fn to_record_batch(rows: &[FlatMftEntryWithName]) -> Result<RecordBatch, ArrowError> {
+    to_record_batch_with_schema(rows, Arc::new(schema()))
+}
+
+fn to_record_batch_with_schema(
+    rows: &[FlatMftEntryWithName],
+    schema: SchemaRef,
+) -> Result<RecordBatch, ArrowError> {
+    // Timestamps which do not fit in 64 bits of nanoseconds (years before 1677 or after 2262) are null.
+    let timestamps = |f: fn(&FlatMftEntryWithName) -> Option<DateTime<Utc>>| -> ArrayRef {
+        Arc::new(
+            TimestampNanosecondArray::from_iter(
+                rows.iter().map(|row| f(row)?.timestamp_nanos_opt()),
+            )
+            .with_timezone("UTC"),
+        )
+    };
+    let strings = |f: fn(&FlatMftEntryWithName) -> Option<String>| -> ArrayRef {
+        Arc::new(StringArray::from_iter(rows.iter().map(f)))
+    };
+    let deltas = |f: fn(&FlatMftEntryWithName) -> Option<i64>| -> ArrayRef {
+        Arc::new(Int64Array::from_iter(rows.iter().map(f)))
+    };
+
+    let columns: Vec<ArrayRef> = vec![
+        Arc::new(StringArray::from_iter_values(
+            rows.iter().map(|row| &row.signature),
+        )),
+        Arc::new(UInt64Array::from_iter_values(
+            rows.iter().map(|row| row.entry_id),
+        )),
+        Arc::new(UInt16Array::from_iter_values(
+            rows.iter().map(|row| row.sequence),
+        )),
+        Arc::new(UInt64Array::from_iter_values(
+            rows.iter().map(|row| row.base_entry_id),
+        )),
+        Arc::new(UInt16Array::from_iter_values(
+            rows.iter().map(|row| row.base_entry_sequence),
+        )),
+        Arc::new(UInt16Array::from_iter_values(
+            rows.iter().map(|row| row.hard_link_count),
+        )),
+        Arc::new(StringArray::from_iter_values(
+            rows.iter().map(|row| format!("{:?}", row.flags)),
+        )),
+        Arc::new(UInt32Array::from_iter_values(
+            rows.iter().map(|row| row.used_entry_size),
+        )),
+        Arc::new(UInt32Array::from_iter_values(
+            rows.iter().map(|row| row.total_entry_size),
+        )),
+        Arc::new(UInt16Array::from_iter_values(
+            rows.iter().map(|row| row.first_attribute_record_offset),
+        )),
+        Arc::new(UInt16Array::from_iter_values(
+            rows.iter().map(|row| row.first_attribute_id),
+        )),
+        Arc::new(UInt64Array::from_iter_values(
+            rows.iter().map(|row| row.logfile_sequence_number),
+        )),
+        Arc::new(UInt64Array::from_iter_values(
+            rows.iter().map(|row| row.file_size),
+        )),
+        Arc::new(BooleanArray::from_iter(
+            rows.iter().map(|row| Some(row.is_a_directory)),
+        )),
+        Arc::new(BooleanArray::from_iter(
+            rows.iter().map(|row| Some(row.is_deleted)),
+        )),
+        Arc::new(BooleanArray::from_iter(
+            rows.iter().map(|row| Some(row.has_alternate_data_streams)),
+        )),
+        Arc::new(BooleanArray::from_iter(
+            rows.iter().map(|row| Some(row.has_suspicious_name)),
+        )),
+        strings(|row| row.standard_info_flags.map(|flags| format!("{:?}", flags))),
+        timestamps(|row| row.standard_info_last_modified),
+        timestamps(|row| row.standard_info_last_access),
+        timestamps(|row| row.standard_info_created),
+        Arc::new(UInt64Array::from_iter_values(
+            rows.iter().map(|row| row.usn),
+        )),
+        strings(|row| row.file_name_flags.map(|flags| format!("{:?}", flags))),
+        timestamps(|row| row.file_name_last_modified),
+        timestamps(|row| row.file_name_last_access),
+        timestamps(|row| row.file_name_created),
+        deltas(|row| row.created_delta_secs),
+        deltas(|row| row.last_modified_delta_secs),
+        deltas(|row| row.last_access_delta_secs),
+        timestamps(|row| row.object_id_timestamp),
+        strings(|row| row.object_id.clone()),
+        strings(|row| row.birth_volume_id.clone()),
+        strings(|row| row.birth_object_id.clone()),
+        strings(|row| row.domain_id.clone()),
+        strings(|row| row.reparse_tag.map(|tag| format!("0x{:08X}", tag))),
+        strings(|row| row.reparse_target.clone()),
+        strings(|row| {
+            row.parent_reference_status
+                .map(|status| format!("{:?}", status))
+        }),
+        Arc::new(BooleanArray::from_iter(
+            rows.iter().map(|row| Some(row.is_orphan)),
+        )),
+        Arc::new(StringArray::from_iter_values(
+            rows.iter()
+                .map(|row| format!("{:?}", row.data_size_consistency)),
+        )),
+        strings(|row| {
+            row.timestomp_indicators
+                .map(|indicators| format!("{:?}", indicators))
+        }),
+        Arc::new(StringArray::from_iter_values(
+            rows.iter().map(|row| row.full_path.to_string_lossy()),
+        )),
+    ];
+
+    RecordBatch::try_new(schema, columns)
+}
+
+/// Writes rows as an Arrow IPC stream, buffering them into record batches.
+pub struct FlatMftEntryStreamWriter<W: Write> {
+    writer: StreamWriter<W>,
+    schema: SchemaRef,
+    rows: Vec<FlatMftEntryWithName>,
+    batch_size: usize,
+}
+
+impl<W: Write> FlatMftEntryStreamWriter<W> {
+    /// Starts a stream, writing the schema to `output`.
+    pub fn new(output: W) -> Result<Self, ArrowError> {
+        let schema = Arc::new(schema());
+
+        Ok(FlatMftEntryStreamWriter {
+            writer: StreamWriter::try_new(output, &schema)?,
+            schema,
+            rows: Vec::with_capacity(DEFAULT_BATCH_SIZE),
+            batch_size: DEFAULT_BATCH_SIZE,
+        })
+    }
+
+    /// Sets the number of rows in each record batch (the last batch may be smaller).
+    pub fn with_batch_size(mut self, batch_size: usize) -> Self {
+        self.batch_size = batch_size.max(1);
+        self
+    }
+
+    pub fn write(&mut self, row: FlatMftEntryWithName) -> Result<(), ArrowError> {
+        self.rows.push(row);
+
+        if self.rows.len() >= self.batch_size {
+            self.flush_batch()?;
+        }
+
+        Ok(())
+    }
+
+    /// Writes the remaining rows and the end of stream marker, returning the output.
+    pub fn finish(mut self) -> Result<W, ArrowError> {
+        self.flush_batch()?;
+        self.writer.finish()?;
+        self.writer.into_inner()
+    }
+
+    fn flush_batch(&mut self) -> Result<(), ArrowError> {
+        if self.rows.is_empty() {
+            return Ok(());
+        }
+
+        let batch = to_record_batch_with_schema(&self.rows, self.schema.clone())?;
+        self.rows.clear();
+
+        self.writer.write(&batch)
+    }
+}
+
+/// Writes rows to a Parquet file, buffering them into record batches.
+#[cfg(feature = "parquet")]
+pub struct FlatMftEntryParquetWriter<W: Write + Send> {
+    writer: ArrowWriter<W>,
+    schema: SchemaRef,
+    rows: Vec<FlatMftEntryWithName>,
+    batch_size: usize,
+}
+
+#[cfg(feature = "parquet")]
+impl<W: Write + Send> FlatMftEntryParquetWriter<W> {
+    /// Starts a Snappy compressed Parquet file, the metadata is written to `output` by `finish`.
+    ///
+    /// The file has a column for every field of `FlatMftEntryWithName` (see `schema`), named like the CSV header:
+    ///
+    /// * Record numbers, sequence numbers, sizes and offsets are unsigned integers (`UInt64`, `UInt32` or `UInt16`).
+    /// * `IsADirectory`, `IsDeleted`, `HasAlternateDataStreams`, `HasSuspiciousName` and `IsOrphan` are booleans.
+    /// * The 0x10, 0x30 and object id timestamps are nullable nanosecond timestamps in UTC,
+    ///   null when the attribute is missing.
+    /// * The timestamp deltas (`CreatedDeltaSecs` and the like) are nullable signed integers.
+    /// * Flags and enums (such as `Flags`, `StandardInfoFlags` or `ParentReferenceStatus`) are strings,
+    ///   in the same form as in the CSV output (for example `ALLOCATED | INDEX_PRESENT`).
+    /// * `Signature` and `FullPath` are strings.
+    pub fn new(output: W) -> Result<Self, ParquetError> {
+        let schema = Arc::new(schema());
+        let properties = WriterProperties::builder()
+            .set_compression(Compression::SNAPPY)
+            .build();
+
+        Ok(FlatMftEntryParquetWriter {
+            writer: ArrowWriter::try_new(output, schema.clone(), Some(properties))?,
+            schema,
+            rows: Vec::with_capacity(DEFAULT_BATCH_SIZE),
+            batch_size: DEFAULT_BATCH_SIZE,
+        })
+    }
+
+    /// Sets the number of rows in each record batch (the last batch may be smaller).
+    pub fn with_batch_size(mut self, batch_size: usize) -> Self {
+        self.batch_size = batch_size.max(1);
+        self
+    }
+
+    pub fn write(&mut self, row: FlatMftEntryWithName) -> Result<(), ParquetError> {
+        self.rows.push(row);
+
+        if self.rows.len() >= self.batch_size {
+            self.flush_batch()?;
+        }
+
+        Ok(())
+    }
+
+    /// Writes the remaining rows and the file metadata, returning the output.
+    pub fn finish(mut self) -> Result<W, ParquetError> {
+        self.flush_batch()?;
+        self.writer.into_inner()
+    }
+
+    fn flush_batch(&mut self) -> Result<(), ParquetError> {
+        if self.rows.is_empty() {
+            return Ok(());
+        }
+
+        let batch = to_record_batch_with_schema(&self.rows, self.schema.clone())?;
+        self.rows.clear();
+
+        self.writer.write(&batch)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{schema, FlatMftEntryStreamWriter};
+    use crate::csv::FlatMftEntryWithName;
+    use crate::tests::fixtures::mft_sample;
+    use crate::MftParser;
+
+    use arrow_array::{Array, StringArray, TimestampNanosecondArray, UInt64Array};
+    use arrow_ipc::reader::StreamReader;
+    use std::io::Cursor;
+
+    #[test]
+    fn test_stream_round_trip() {
+        let mut parser = MftParser::from_path(mft_sample()).unwrap();
+        let entries: Vec<_> = parser
+            .iter_entries()
+            .take(100)
+            .filter_map(Result::ok)
+            .collect();
+
+        let mut writer = FlatMftEntryStreamWriter::new(vec![])
+            .unwrap()
+            .with_batch_size(30);
+        for entry in &entries {
+            writer
+                .write(FlatMftEntryWithName::from_entry(entry, &mut parser))
+                .unwrap();
+        }
+        let stream = writer.finish().unwrap();
+
+        let reader = StreamReader::try_new(Cursor::new(stream), None).unwrap();
+        assert_eq!(reader.schema().as_ref(), &schema());
+
+        let batches: Vec<_> = reader.collect::<Result<_, _>>().unwrap();
+        assert_eq!(batches.len(), 4);
+        assert_eq!(
+            batches.iter().map(|b| b.num_rows()).sum::<usize>(),
+            entries.len()
+        );
+
+        let first = &batches[0];
+        let entry_ids = first
+            .column_by_name("EntryId")
+            .unwrap()
+            .as_any()
+            .downcast_ref::<UInt64Array>()
+            .unwrap();
+        let paths = first
+            .column_by_name("FullPath")
+            .unwrap()
+            .as_any()
+            .downcast_ref::<StringArray>()
+            .unwrap();
+        assert_eq!(entry_ids.value(0), 0);
+        assert_eq!(paths.value(0), "$MFT");
+
+        let created = first
+            .column_by_name("StandardInfoCreated")
+            .unwrap()
+            .as_any()
+            .downcast_ref::<TimestampNanosecondArray>()
+            .unwrap();
+        let expected = FlatMftEntryWithName::from_entry(&entries[0], &mut parser)
+            .standard_info_created
+            .unwrap();
+        assert_eq!(created.value_as_datetime(0).unwrap(), expected.naive_utc());
+        assert!(!created.is_null(0));
+    }
+
+    #[cfg(feature = "parquet")]
+    #[test]
+    fn test_parquet_round_trip() {
+        use super::FlatMftEntryParquetWriter;
+        use parquet::arrow::arrow_reader::ParquetRecordBatchReaderBuilder;
+
+        let mut parser = MftParser::from_path(mft_sample()).unwrap();
+        let entries: Vec<_> = parser
+            .iter_entries()
+            .take(100)
+            .filter_map(Result::ok)
+            .collect();
+
+        let mut writer = FlatMftEntryParquetWriter::new(tempfile::tempfile().unwrap())
+            .unwrap()
+            .with_batch_size(30);
+        for entry in &entries {
+            writer
+                .write(FlatMftEntryWithName::from_entry(entry, &mut parser))
+                .unwrap();
+        }
+        let file = writer.finish().unwrap();
+
+        let builder = ParquetRecordBatchReaderBuilder::try_new(file).unwrap();
+        assert_eq!(builder.schema().as_ref(), &schema());
+
+        let batches: Vec<_> = builder.build().unwrap().collect::<Result<_, _>>().unwrap();
+        assert_eq!(
+            batches.iter().map(|b| b.num_rows()).sum::<usize>(),
+            entries.len()
+        );
+
+        let first = &batches[0];
+        let paths = first
+            .column_by_name("FullPath")
+            .unwrap()
+            .as_any()
+            .downcast_ref::<StringArray>()
+            .unwrap();
+        assert_eq!(paths.value(0), "$MFT");
+
+        // Missing (and out of range) timestamps are null.
+        let file_name_created = first
+            .column_by_name("FileNameCreated")
+            .unwrap()
+            .as_any()
+            .downcast_ref::<TimestampNanosecondArray>()
+            .unwrap();
+        let expected_nulls = entries
+            .iter()
+            .take(first.num_rows())
+            .filter(|entry| {
+                FlatMftEntryWithName::from_entry(entry, &mut parser)
+                    .file_name_created
+                    .and_then(|created| created.timestamp_nanos_opt())
+                    .is_none()
+            })
+            .count();
+        assert!(expected_nulls > 0);
+        assert_eq!(file_name_created.null_count(), expected_nulls);
+    }
+}
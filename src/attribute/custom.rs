@@ -0,0 +1,80 @@
+use crate::attribute::{MftAttribute, MftAttributeContent};
+use crate::err::Result;
+
+use log::warn;
+use num_traits::ToPrimitive;
+
+use std::collections::HashMap;
+use std::fmt;
+use std::sync::Arc;
+
+/// A decoder for the content of an attribute type which is otherwise left raw.
+///
+/// Decoders are shared between the parser and every entry it reads, and entries are `Send + Sync`,
+/// so a decoder may be called concurrently from multiple threads when entries are processed in parallel.
+/// It should not rely on being called in order, or only once per attribute.
+pub type AttributeDecoder = dyn Fn(&[u8]) -> Result<serde_json::Value> + Send + Sync;
+
+/// The set of registered attribute decoders, keyed by attribute type code.
+///
+/// Cloning is cheap, the decoders themselves are shared.
+#[derive(Clone, Default)]
+pub struct AttributeDecoders {
+    decoders: Arc<HashMap<u32, Arc<AttributeDecoder>>>,
+}
+
+impl AttributeDecoders {
+    /// Registers `decoder` for attributes of `type_code`, replacing any previously registered decoder.
+    pub fn register(&mut self, type_code: u32, decoder: Box<AttributeDecoder>) {
+        Arc::make_mut(&mut self.decoders).insert(type_code, Arc::from(decoder));
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.decoders.is_empty()
+    }
+
+    /// Decodes the content of `attribute` if it is raw (a resident attribute this crate has no parser for),
+    /// and a decoder is registered for its type.
+    ///
+    /// If the decoder fails, the attribute is kept raw.
+    pub fn decode(&self, attribute: MftAttribute) -> MftAttribute {
+        let raw = match &attribute.data {
+            MftAttributeContent::Raw(raw) => raw,
+            _ => return attribute,
+        };
+
+        let decoder = match raw
+            .attribute_type
+            .to_u32()
+            .and_then(|type_code| self.decoders.get(&type_code))
+        {
+            Some(decoder) => decoder,
+            None => return attribute,
+        };
+
+        match decoder(&raw.data) {
+            Ok(value) => MftAttribute {
+                header: attribute.header,
+                data: MftAttributeContent::Custom(value),
+            },
+            Err(e) => {
+                warn!(
+                    "Custom decoder for {:?} failed, keeping the attribute raw: {}",
+                    raw.attribute_type, e
+                );
+                attribute
+            }
+        }
+    }
+}
+
+impl fmt::Debug for AttributeDecoders {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let mut type_codes: Vec<_> = self.decoders.keys().collect();
+        type_codes.sort();
+
+        f.debug_struct("AttributeDecoders")
+            .field("type_codes", &type_codes)
+            .finish()
+    }
+}
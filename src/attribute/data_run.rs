@@ -37,6 +37,8 @@ fn decode_run_svalue<T: Iterator<Item = u8>>(it: &mut T, bytes: u8) -> Option<i6
 pub fn decode_data_runs(runs: &[u8]) -> Option<Vec<DataRun>> {
     let mut it = runs.iter().copied();
     let mut out: Vec<DataRun> = Vec::new();
+    // Offsets are relative to the previous run which is backed by clusters (sparse runs have no offset).
+    let mut last_lcn_offset: Option<u64> = None;
 
     loop {
         let h = it.next()?;
@@ -52,12 +54,13 @@ pub fn decode_data_runs(runs: &[u8]) -> Option<Vec<DataRun>> {
         let abs_offset;
         let run_type;
         if offset_size != 0 { // offset_size of 0 == sparse cluster
-            if let Some(last) = out.last() {
+            if let Some(last_lcn_offset) = last_lcn_offset {
                 let rel_offset  = decode_run_svalue(&mut it, offset_size)?;
-                abs_offset = (last.lcn_offset as i64 + rel_offset) as u64;
+                abs_offset = (last_lcn_offset as i64 + rel_offset) as u64;
             } else {
                 abs_offset = decode_run_value(&mut it, offset_size)?;
             }
+            last_lcn_offset = Some(abs_offset);
             run_type = RunType::Standard;
         }
         else {
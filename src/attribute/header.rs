@@ -159,10 +159,31 @@ pub struct NonResidentHeader {
     ///  Contains the valid data size in number of bytes.
     /// This value is not valid if the first VCN is nonzero.
     pub valid_data_length: u64,
+    /// The total allocated size of the stream, in bytes.
+    /// This is only present when `unit_compression_size` is non-zero,
+    /// which implies the stream is compressed (or sparse).
+    #[serde(skip_serializing_if = "Option::is_none")]
     pub total_allocated: Option<u64>,
 }
 
 impl NonResidentHeader {
+    /// Returns true if the stream has bytes between `valid_data_length` and `file_size`,
+    /// which were never written and should be read as zeros regardless of what the clusters contain.
+    /// The sizes are only valid in the first record of the attribute, so this is false for the others.
+    pub fn has_uninitialized_tail(&self) -> bool {
+        self.vnc_first == 0 && self.valid_data_length < self.file_size
+    }
+
+    /// Returns the logical size of the stream in bytes (`file_size`),
+    /// or `None` for the other records of the attribute, where the sizes are not valid.
+    pub fn logical_size(&self) -> Option<u64> {
+        if self.vnc_first == 0 {
+            Some(self.file_size)
+        } else {
+            None
+        }
+    }
+
     pub fn from_stream<R: Read>(reader: &mut R) -> Result<NonResidentHeader> {
         let vnc_first = reader.read_u64::<LittleEndian>()?;
         let vnc_last = reader.read_u64::<LittleEndian>()?;
@@ -1,16 +1,25 @@
+pub mod custom;
+pub mod data_run;
 pub mod header;
+pub mod non_resident_attr;
 pub mod raw;
 pub mod x10;
+pub mod x100;
 pub mod x20;
 pub mod x30;
 pub mod x40;
+pub mod x50;
+pub mod x60;
+pub mod x70;
 pub mod x80;
 pub mod x90;
-pub mod non_resident_attr;
-pub mod data_run;
+pub mod xb0;
+pub mod xc0;
+pub mod xd0;
+pub mod xe0;
 
 use crate::err::Result;
-use crate::impl_serialize_for_bitflags;
+use crate::{impl_deserialize_for_bitflags, impl_serialize_for_bitflags};
 
 use std::io::{Cursor, Read, Seek};
 
@@ -21,19 +30,84 @@ use crate::attribute::x10::StandardInfoAttr;
 use crate::attribute::x20::AttributeListAttr;
 use crate::attribute::x30::FileNameAttr;
 
-use crate::attribute::header::{MftAttributeHeader, ResidentHeader, NonResidentHeader};
+use crate::attribute::header::{MftAttributeHeader, NonResidentHeader, ResidentHeader};
+use crate::attribute::non_resident_attr::NonResidentAttr;
+use crate::attribute::x100::{LoggedUtilityStreamAttr, TXF_DATA_SIZE, TXF_DATA_STREAM_NAME};
 use crate::attribute::x40::ObjectIdAttr;
+use crate::attribute::x50::SecurityDescriptorAttr;
+use crate::attribute::x60::VolumeNameAttr;
+use crate::attribute::x70::VolumeInformationAttr;
 use crate::attribute::x80::DataAttr;
 use crate::attribute::x90::IndexRootAttr;
-use crate::attribute::non_resident_attr::NonResidentAttr;
+use crate::attribute::xb0::BitmapAttr;
+use crate::attribute::xc0::ReparsePointAttr;
+use crate::attribute::xd0::EaInformationAttr;
+use crate::attribute::xe0::EaAttr;
+use serde::ser::{SerializeStruct, Serializer};
 use serde::Serialize;
+use std::fmt;
 
-#[derive(Serialize, Clone, Debug)]
+#[derive(Clone, Debug)]
 pub struct MftAttribute {
     pub header: MftAttributeHeader,
     pub data: MftAttributeContent,
 }
 
+/// Attributes are serialized with a `type_name` field (such as `"StandardInformation"`) next to the header and content,
+/// since the content is serialized untagged, and can't be told apart by its shape alone.
+impl Serialize for MftAttribute {
+    fn serialize<S>(&self, serializer: S) -> ::std::result::Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        let mut state = serializer.serialize_struct("MftAttribute", 3)?;
+        state.serialize_field("header", &self.header)?;
+        state.serialize_field("type_name", self.header.type_code.as_str())?;
+        state.serialize_field("data", &self.data)?;
+        state.end()
+    }
+}
+
+impl MftAttribute {
+    /// Returns true if the attribute content is compressed.
+    pub fn is_compressed(&self) -> bool {
+        self.header
+            .data_flags
+            .intersects(AttributeDataFlags::COMPRESSION_MASK)
+    }
+
+    /// Returns true if the attribute content is sparse.
+    pub fn is_sparse(&self) -> bool {
+        self.header.data_flags.contains(AttributeDataFlags::SPARSE)
+    }
+
+    /// Returns true if the attribute content is encrypted.
+    pub fn is_encrypted(&self) -> bool {
+        self.header
+            .data_flags
+            .contains(AttributeDataFlags::ENCRYPTED)
+    }
+
+    /// Returns the parsed content of the attribute as JSON, as it is serialized in the `data` field of the attribute.
+    ///
+    /// ```
+    /// # use mft::MftParser;
+    /// # use mft::attribute::MftAttributeType;
+    /// # let mut parser = MftParser::from_path("samples/MFT").unwrap();
+    /// let entry = parser.get_entry(0).unwrap();
+    /// let file_name = entry
+    ///     .iter_attributes_matching(Some(vec![MftAttributeType::FileName]))
+    ///     .next()
+    ///     .unwrap()
+    ///     .unwrap();
+    ///
+    /// assert_eq!(file_name.content_to_value()["name"], "$MFT");
+    /// ```
+    pub fn content_to_value(&self) -> serde_json::Value {
+        serde_json::to_value(&self.data).expect("Attribute content is always serializable to JSON")
+    }
+}
+
 impl MftAttributeContent {    
     pub fn from_stream_non_resident<S: Read + Seek>(
         stream: &mut S,
@@ -84,10 +158,55 @@ impl MftAttributeContent {
             MftAttributeType::ObjectId => Ok(MftAttributeContent::AttrX40(
                 ObjectIdAttr::from_stream(stream, resident.data_size as usize)?,
             )),
+            MftAttributeType::SecurityDescriptor => {
+                let mut buffer = vec![0; resident.data_size as usize];
+                stream.read_exact(&mut buffer)?;
+
+                // Only the layout of revision 1 descriptors is known, others (and malformed ones) are kept raw.
+                match SecurityDescriptorAttr::from_buffer(&buffer) {
+                    Ok(Some(descriptor)) => Ok(MftAttributeContent::AttrX50(descriptor)),
+                    Ok(None) | Err(_) => Ok(MftAttributeContent::Raw(RawAttribute {
+                        attribute_type: header.type_code.clone(),
+                        data: buffer,
+                    })),
+                }
+            }
+            // Always Resident
+            MftAttributeType::VolumeName => Ok(MftAttributeContent::AttrX60(
+                VolumeNameAttr::from_stream(stream, resident.data_size as usize)?,
+            )),
+            // Always Resident
+            MftAttributeType::VolumeInformation => Ok(MftAttributeContent::AttrX70(
+                VolumeInformationAttr::from_stream(stream)?,
+            )),
             // Always Resident
             MftAttributeType::IndexRoot => Ok(MftAttributeContent::AttrX90(
                 IndexRootAttr::from_stream(stream)?,
             )),
+            MftAttributeType::BITMAP => Ok(MftAttributeContent::AttrXB0(BitmapAttr::from_stream(
+                stream,
+                resident.data_size as usize,
+            )?)),
+            MftAttributeType::ReparsePoint => Ok(MftAttributeContent::AttrXC0(
+                ReparsePointAttr::from_stream(stream, resident.data_size as usize)?,
+            )),
+            // Always Resident
+            MftAttributeType::EaInformation => Ok(MftAttributeContent::AttrXD0(
+                EaInformationAttr::from_stream(stream)?,
+            )),
+            MftAttributeType::EA => Ok(MftAttributeContent::AttrXE0(EaAttr::from_stream(
+                stream,
+                resident.data_size as usize,
+            )?)),
+            // Only the layout of `$TXF_DATA` is known, other streams are kept raw.
+            MftAttributeType::LoggedUtilityStream
+                if header.name == TXF_DATA_STREAM_NAME
+                    && resident.data_size as usize >= TXF_DATA_SIZE =>
+            {
+                Ok(MftAttributeContent::AttrX100(
+                    LoggedUtilityStreamAttr::from_stream(stream)?,
+                ))
+            }
             // An unparsed resident attribute
             _ => Ok(MftAttributeContent::Raw(RawAttribute::from_stream(
                 stream,
@@ -120,6 +239,71 @@ impl MftAttributeContent {
             _ => None,
         }
     }
+
+    /// Converts the given attributes into a `SecurityDescriptorAttr`, consuming the object attribute object.
+    pub fn into_security_descriptor(self) -> Option<SecurityDescriptorAttr> {
+        match self {
+            MftAttributeContent::AttrX50(content) => Some(content),
+            _ => None,
+        }
+    }
+
+    /// Converts the given attributes into a `VolumeNameAttr`, consuming the object attribute object.
+    pub fn into_volume_name(self) -> Option<VolumeNameAttr> {
+        match self {
+            MftAttributeContent::AttrX60(content) => Some(content),
+            _ => None,
+        }
+    }
+
+    /// Converts the given attributes into a `VolumeInformationAttr`, consuming the object attribute object.
+    pub fn into_volume_information(self) -> Option<VolumeInformationAttr> {
+        match self {
+            MftAttributeContent::AttrX70(content) => Some(content),
+            _ => None,
+        }
+    }
+
+    /// Converts the given attributes into a `BitmapAttr`, consuming the object attribute object.
+    pub fn into_bitmap(self) -> Option<BitmapAttr> {
+        match self {
+            MftAttributeContent::AttrXB0(content) => Some(content),
+            _ => None,
+        }
+    }
+
+    /// Converts the given attributes into a `ReparsePointAttr`, consuming the object attribute object.
+    pub fn into_reparse_point(self) -> Option<ReparsePointAttr> {
+        match self {
+            MftAttributeContent::AttrXC0(content) => Some(content),
+            _ => None,
+        }
+    }
+
+    /// Converts the given attributes into a `EaInformationAttr`, consuming the object attribute object.
+    pub fn into_ea_information(self) -> Option<EaInformationAttr> {
+        match self {
+            MftAttributeContent::AttrXD0(content) => Some(content),
+            _ => None,
+        }
+    }
+
+    /// Converts the given attributes into a `EaAttr`, consuming the object attribute object.
+    pub fn into_ea(self) -> Option<EaAttr> {
+        match self {
+            MftAttributeContent::AttrXE0(content) => Some(content),
+            _ => None,
+        }
+    }
+
+    /// Converts the given attributes into a `LoggedUtilityStreamAttr`, consuming the object attribute object.
+    pub fn into_logged_utility_stream(self) -> Option<LoggedUtilityStreamAttr> {
+        match self {
+            MftAttributeContent::AttrX100(content) => Some(content),
+            _ => None,
+        }
+    }
+
     /// Converts the given attributes into a `StandardInfoAttr`, consuming the object attribute object.
     pub fn into_standard_info(self) -> Option<StandardInfoAttr> {
         match self {
@@ -161,15 +345,28 @@ pub enum MftAttributeContent {
     AttrX20(AttributeListAttr),
     AttrX30(FileNameAttr),
     AttrX40(ObjectIdAttr),
+    AttrX50(SecurityDescriptorAttr),
+    AttrX60(VolumeNameAttr),
+    AttrX70(VolumeInformationAttr),
     AttrX80(DataAttr),
     AttrX90(IndexRootAttr),
+    AttrXB0(BitmapAttr),
+    AttrXC0(ReparsePointAttr),
+    AttrXD0(EaInformationAttr),
+    AttrXE0(EaAttr),
+    AttrX100(LoggedUtilityStreamAttr),
+    /// The output of a decoder registered with `MftParser::register_attribute_decoder`.
+    Custom(serde_json::Value),
     DataRun(NonResidentAttr),
     /// Empty - used when data is non resident.
     None,
 }
 
 /// MFT Possible attribute types, from <https://docs.microsoft.com/en-us/windows/desktop/devnotes/attribute-list-entry>
-#[derive(Serialize, Debug, Clone, FromPrimitive, ToPrimitive, PartialOrd, PartialEq)]
+///
+/// Serialized (and displayed) by name, see `MftAttributeType::as_str`.
+/// The numeric type code is available with `ToPrimitive::to_u32`.
+#[derive(Debug, Clone, FromPrimitive, ToPrimitive, PartialOrd, PartialEq)]
 #[repr(u32)]
 pub enum MftAttributeType {
     /// File attributes (such as read-only and archive), time stamps (such as file creation and last modified), and the hard link count.
@@ -206,6 +403,52 @@ pub enum MftAttributeType {
     LoggedUtilityStream = 0x100_u32,
 }
 
+impl MftAttributeType {
+    /// Returns the name of the attribute type, which is the name of the variant (such as `"FileName"`).
+    ///
+    /// ```
+    /// use mft::attribute::MftAttributeType;
+    /// use num_traits::ToPrimitive;
+    ///
+    /// assert_eq!(MftAttributeType::FileName.as_str(), "FileName");
+    /// assert_eq!(MftAttributeType::FileName.to_u32(), Some(0x30));
+    /// ```
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            MftAttributeType::StandardInformation => "StandardInformation",
+            MftAttributeType::AttributeList => "AttributeList",
+            MftAttributeType::FileName => "FileName",
+            MftAttributeType::ObjectId => "ObjectId",
+            MftAttributeType::SecurityDescriptor => "SecurityDescriptor",
+            MftAttributeType::VolumeName => "VolumeName",
+            MftAttributeType::VolumeInformation => "VolumeInformation",
+            MftAttributeType::DATA => "DATA",
+            MftAttributeType::IndexRoot => "IndexRoot",
+            MftAttributeType::IndexAllocation => "IndexAllocation",
+            MftAttributeType::BITMAP => "BITMAP",
+            MftAttributeType::ReparsePoint => "ReparsePoint",
+            MftAttributeType::EaInformation => "EaInformation",
+            MftAttributeType::EA => "EA",
+            MftAttributeType::LoggedUtilityStream => "LoggedUtilityStream",
+        }
+    }
+}
+
+impl fmt::Display for MftAttributeType {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+impl Serialize for MftAttributeType {
+    fn serialize<S>(&self, serializer: S) -> ::std::result::Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        serializer.serialize_str(self.as_str())
+    }
+}
+
 bitflags! {
     /// Flag sources:
     /// <https://github.com/EricZimmerman/MFT/blob/3bed2626ee85e9a96a6db70a17407d0c3696056a/MFT/Attributes/StandardInfo.cs#L10>
@@ -235,6 +478,7 @@ bitflags! {
 }
 
 impl_serialize_for_bitflags! {FileAttributeFlags}
+impl_deserialize_for_bitflags! {FileAttributeFlags}
 
 bitflags! {
     #[derive(Default)]
@@ -247,3 +491,11 @@ bitflags! {
 }
 
 impl_serialize_for_bitflags! {AttributeDataFlags}
+
+/// Serializes the raw bytes of an attribute as a hex string.
+pub(crate) fn data_as_hex<S>(x: &[u8], s: S) -> std::result::Result<S::Ok, S::Error>
+where
+    S: Serializer,
+{
+    s.serialize_str(&crate::utils::to_hex_string(x))
+}
@@ -1,10 +1,23 @@
 use crate::err::{Error, Result};
-use crate::attribute::header::{MftAttributeHeader, NonResidentHeader};
-use crate::attribute::data_run::{DataRun, decode_data_runs};
+use crate::attribute::header::{MftAttributeHeader, NonResidentHeader, ResidentialHeader};
+use crate::attribute::MftAttribute;
+use crate::attribute::data_run::{DataRun, RunType, decode_data_runs};
 
 use std::io::{Read, Seek, SeekFrom};
 use serde::Serialize;
 
+/// The most memory `read_non_resident_data` reserves upfront, the rest grows as the runs are actually read,
+/// so that a corrupted header can't make it allocate more than the volume holds.
+const MAX_PREALLOCATED_SIZE: usize = 64 * 1024 * 1024;
+
+/// A data run resolved to bytes, see `NonResidentAttr::byte_runs`.
+#[derive(Serialize, Debug, Copy, Clone, Eq, PartialEq)]
+pub struct ByteRun {
+    /// The offset of the run from the start of the volume, `None` for sparse runs (which read as zeros).
+    pub offset: Option<u64>,
+    pub length: u64,
+}
+
 #[derive(Serialize, Clone, Debug)]
 pub struct NonResidentAttr {
     pub data_runs: Vec<DataRun>
@@ -18,25 +31,224 @@ impl NonResidentAttr {
     ) -> Result<Self> {
         let data_run_bytes_count = (header.record_length - u32::from(resident.datarun_offset)) as usize;
         let mut data_run_bytes = vec![0_u8; data_run_bytes_count];
-        if resident.valid_data_length != 0 {            
-            stream.seek(SeekFrom::Start(header.start_offset + u64::from(resident.datarun_offset)))?;
-            stream.read_exact(&mut data_run_bytes)?;
-            if let Some(data_runs) = decode_data_runs(&data_run_bytes) {
-                Ok(Self {
-                    data_runs
+        // The runs are decoded even if the valid data length is 0 (such as for preallocated files, or `$BadClus:$Bad`),
+        // since the clusters are allocated regardless of whether they were written.
+        stream.seek(SeekFrom::Start(header.start_offset + u64::from(resident.datarun_offset)))?;
+        stream.read_exact(&mut data_run_bytes)?;
+        if let Some(data_runs) = decode_data_runs(&data_run_bytes) {
+            Ok(Self {
+                data_runs
+            })
+        }
+        else {
+            Err(Error::FailedToDecodeDataRuns {
+                bad_data_runs: data_run_bytes,
+            })
+        }
+    }
+
+    /// Returns the logical layout of the stream as `(logical_offset, length, is_allocated)` extents, in bytes.
+    /// Sparse runs advance the logical offset, but are not backed by clusters (`is_allocated` is false).
+    pub fn logical_extents(&self, bytes_per_cluster: u64) -> Vec<(u64, u64, bool)> {
+        let mut logical_offset = 0;
+
+        self.data_runs
+            .iter()
+            .map(|run| {
+                let length = run.lcn_length * bytes_per_cluster;
+                let extent = (logical_offset, length, run.run_type == RunType::Standard);
+                logical_offset += length;
+                extent
+            })
+            .collect()
+    }
+
+    /// Resolves the data runs to byte offsets and lengths on the volume, in the order of the stream.
+    ///
+    /// The runs cover whole clusters, so the last one usually extends past the logical size of the stream
+    /// (see `NonResidentHeader::logical_size`).
+    pub fn byte_runs(&self, bytes_per_cluster: u64) -> Vec<ByteRun> {
+        self.data_runs
+            .iter()
+            .map(|run| ByteRun {
+                offset: match run.run_type {
+                    RunType::Standard => Some(run.lcn_offset * bytes_per_cluster),
+                    RunType::Sparse => None,
+                },
+                length: run.lcn_length * bytes_per_cluster,
+            })
+            .collect()
+    }
+
+    /// Like `byte_runs`, but fails with `Error::DataRunOverflow` if a run does not fit in 64 bits when converted to bytes.
+    fn checked_byte_runs(&self, bytes_per_cluster: u64) -> Result<Vec<ByteRun>> {
+        self.data_runs
+            .iter()
+            .map(|run| {
+                let overflow = || Error::DataRunOverflow {
+                    lcn_offset: run.lcn_offset,
+                    lcn_length: run.lcn_length,
+                    bytes_per_cluster,
+                };
+
+                Ok(ByteRun {
+                    offset: match run.run_type {
+                        RunType::Standard => Some(
+                            run.lcn_offset
+                                .checked_mul(bytes_per_cluster)
+                                .ok_or_else(overflow)?,
+                        ),
+                        RunType::Sparse => None,
+                    },
+                    length: run
+                        .lcn_length
+                        .checked_mul(bytes_per_cluster)
+                        .ok_or_else(overflow)?,
                 })
+            })
+            .collect()
+    }
+
+    /// Returns the number of allocated bytes after the end of the logical content (the file slack),
+    /// which is `allocated_length - file_size`.
+    ///
+    /// For compressed streams the allocation is rounded to the compression unit, so only the slack
+    /// up to the end of the last cluster is counted.
+    /// Returns 0 if `header` does not belong to the first record of the attribute, as the sizes are only valid there.
+    pub fn slack_bytes(&self, header: &NonResidentHeader, bytes_per_cluster: u64) -> u64 {
+        if header.vnc_first != 0 {
+            return 0;
+        }
+
+        let slack = header.allocated_length.saturating_sub(header.file_size);
+
+        if header.unit_compression_size > 0 && bytes_per_cluster > 0 {
+            let last_cluster_slack =
+                (bytes_per_cluster - header.file_size % bytes_per_cluster) % bytes_per_cluster;
+            slack.min(last_cluster_slack)
+        } else {
+            slack
+        }
+    }
+
+    /// Reads the logical content of the stream (`file_size` bytes) from `volume`, following the data runs.
+    ///
+    /// Sparse runs, and anything after `valid_data_length`, are read back as zeros without touching the volume,
+    /// since the clusters beyond the valid data length may be allocated but were never written.
+    /// Compressed streams are not decompressed, their clusters are returned as they are stored.
+    ///
+    /// `header` must belong to the first record of the attribute, as the sizes are only valid there.
+    /// Fails without allocating if `file_size` exceeds the length of the runs, or if a run overflows
+    /// when converted to bytes, since both mean the header or the runs are corrupted.
+    pub fn read_non_resident_data<R: Read + Seek>(
+        &self,
+        volume: &mut R,
+        header: &NonResidentHeader,
+        bytes_per_cluster: u64,
+    ) -> Result<Vec<u8>> {
+        if header.vnc_first != 0 {
+            return Err(Error::Any {
+                detail: format!(
+                    "Cannot read stream data from an extension record (starting at VCN {})",
+                    header.vnc_first
+                ),
+            });
+        }
+
+        let byte_runs = self.checked_byte_runs(bytes_per_cluster)?;
+
+        let runs_length = byte_runs
+            .iter()
+            .fold(0_u64, |total, run| total.saturating_add(run.length));
+        if header.file_size > runs_length {
+            return Err(Error::StreamSizeExceedsDataRuns {
+                file_size: header.file_size,
+                runs_length,
+            });
+        }
+
+        let file_size = header.file_size as usize;
+        let valid_data_length = header.valid_data_length.min(header.file_size) as usize;
+        let mut data = Vec::with_capacity(file_size.min(MAX_PREALLOCATED_SIZE));
+
+        for run in &byte_runs {
+            if data.len() >= valid_data_length {
+                break;
+            }
+
+            let length = (run.length as usize).min(valid_data_length - data.len());
+
+            match run.offset {
+                Some(offset) => {
+                    volume.seek(SeekFrom::Start(offset))?;
+                    let read = volume.by_ref().take(length as u64).read_to_end(&mut data)?;
+                    if read < length {
+                        return Err(std::io::Error::from(std::io::ErrorKind::UnexpectedEof).into());
+                    }
+                }
+                None => data.resize(data.len() + length, 0),
+            }
+        }
+
+        // The uninitialized tail (and anything the runs do not cover) is logically zero.
+        data.resize(file_size, 0);
+
+        Ok(data)
+    }
+}
+
+/// Combines the data runs of a stream which is split across multiple non-resident attributes
+/// (one per extension record) into a single run list, covering VCN 0..N.
+///
+/// `attributes` should contain all the attributes of the stream (same type and name), in any order.
+/// Their VCN ranges must be contiguous and start at VCN 0, otherwise an error is returned.
+pub fn combine_data_runs(attributes: &[MftAttribute]) -> Result<NonResidentAttr> {
+    let mut parts = Vec::with_capacity(attributes.len());
+
+    for attribute in attributes {
+        let header = &attribute.header.residential_header;
+
+        match (header, attribute.data.clone().into_data_runs()) {
+            (ResidentialHeader::NonResident(header), Some(content)) => {
+                parts.push((header.vnc_first, header.vnc_last, content))
             }
-            else {
-                Err(Error::FailedToDecodeDataRuns {
-                    bad_data_runs: data_run_bytes,
+            _ => {
+                return Err(Error::Any {
+                    detail: format!(
+                        "Attribute {:?} `{}` is resident, and cannot be combined",
+                        attribute.header.type_code, attribute.header.name
+                    ),
                 })
             }
         }
-        else {            
-            let data_runs = Vec::new();
-            Ok(Self {
-                data_runs
-            })
+
+        if attribute.header.name != attributes[0].header.name
+            || attribute.header.type_code != attributes[0].header.type_code
+        {
+            return Err(Error::Any {
+                detail: "Attributes of different streams cannot be combined".to_string(),
+            });
         }
     }
+
+    parts.sort_by_key(|(vnc_first, _, _)| *vnc_first);
+
+    let mut expected_vcn = 0;
+    let mut data_runs = vec![];
+
+    for (vnc_first, vnc_last, content) in parts {
+        if vnc_first != expected_vcn {
+            return Err(Error::NonContiguousDataRuns {
+                expected_vcn,
+                found_vcn: vnc_first,
+            });
+        }
+
+        expected_vcn = vnc_last
+            .checked_add(1)
+            .ok_or(Error::InvalidLastVcn { vnc_last })?;
+        data_runs.extend(content.data_runs);
+    }
+
+    Ok(NonResidentAttr { data_runs })
 }
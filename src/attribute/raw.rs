@@ -1,9 +1,8 @@
 use std::io::{Read, Seek};
 
-use crate::attribute::MftAttributeType;
+use crate::attribute::{data_as_hex, MftAttributeType};
 use crate::err::Result;
-use crate::utils;
-use serde::{ser, Serialize};
+use serde::Serialize;
 
 /// Placeholder attribute for currently unparsed attributes.
 #[derive(Serialize, Clone, Debug)]
@@ -29,10 +28,3 @@ impl RawAttribute {
         })
     }
 }
-
-fn data_as_hex<S>(x: &[u8], s: S) -> std::result::Result<S::Ok, S::Error>
-where
-    S: ser::Serializer,
-{
-    s.serialize_str(&utils::to_hex_string(x))
-}
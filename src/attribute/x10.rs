@@ -9,12 +9,52 @@ use log::trace;
 use serde::Serialize;
 use winstructs::timestamp::WinTimestamp;
 
+/// The timestamps of a `$STANDARD_INFORMATION` or `$FILE_NAME` attribute, as raw FILETIMEs
+/// (the number of 100-nanosecond intervals since 1601-01-01).
+#[derive(Serialize, Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct RawTimestamps {
+    pub created: u64,
+    pub modified: u64,
+    pub mft_modified: u64,
+    pub accessed: u64,
+}
+
+impl RawTimestamps {
+    pub(crate) fn from_reader<R: Read>(reader: &mut R) -> Result<RawTimestamps> {
+        Ok(RawTimestamps {
+            created: reader.read_u64::<LittleEndian>()?,
+            modified: reader.read_u64::<LittleEndian>()?,
+            mft_modified: reader.read_u64::<LittleEndian>()?,
+            accessed: reader.read_u64::<LittleEndian>()?,
+        })
+    }
+
+    /// The created, modified, mft_modified and accessed timestamps, in this order.
+    pub(crate) fn to_datetimes(self) -> Result<[DateTime<Utc>; 4]> {
+        let to_datetime = |filetime: u64| {
+            WinTimestamp::new(&filetime.to_le_bytes())
+                .map(|timestamp| timestamp.to_datetime())
+                .map_err(Error::failed_to_read_windows_time)
+        };
+
+        Ok([
+            to_datetime(self.created)?,
+            to_datetime(self.modified)?,
+            to_datetime(self.mft_modified)?,
+            to_datetime(self.accessed)?,
+        ])
+    }
+}
+
 #[derive(Serialize, Debug, Clone)]
 pub struct StandardInfoAttr {
     pub created: DateTime<Utc>,
     pub modified: DateTime<Utc>,
     pub mft_modified: DateTime<Utc>,
     pub accessed: DateTime<Utc>,
+    /// The timestamps above as they are stored, which are more precise than their parsed counterparts.
+    #[serde(skip)]
+    pub raw_timestamps: RawTimestamps,
     /// DOS File Permissions
     pub file_flags: FileAttributeFlags,
     pub max_version: u32,
@@ -61,24 +101,15 @@ impl StandardInfoAttr {
     /// ```
     pub fn from_reader<S: Read + Seek>(reader: &mut S) -> Result<StandardInfoAttr> {
         trace!("Offset {}: StandardInfoAttr", reader.stream_position()?);
-        let created = WinTimestamp::from_reader(reader)
-            .map_err(Error::failed_to_read_windows_time)?
-            .to_datetime();
-        let modified = WinTimestamp::from_reader(reader)
-            .map_err(Error::failed_to_read_windows_time)?
-            .to_datetime();
-        let mft_modified = WinTimestamp::from_reader(reader)
-            .map_err(Error::failed_to_read_windows_time)?
-            .to_datetime();
-        let accessed = WinTimestamp::from_reader(reader)
-            .map_err(Error::failed_to_read_windows_time)?
-            .to_datetime();
+        let raw_timestamps = RawTimestamps::from_reader(reader)?;
+        let [created, modified, mft_modified, accessed] = raw_timestamps.to_datetimes()?;
 
         Ok(StandardInfoAttr {
             created,
             modified,
             mft_modified,
             accessed,
+            raw_timestamps,
             file_flags: FileAttributeFlags::from_bits_truncate(reader.read_u32::<LittleEndian>()?),
             max_version: reader.read_u32::<LittleEndian>()?,
             version: reader.read_u32::<LittleEndian>()?,
@@ -0,0 +1,72 @@
+use std::io::{Read, Seek, SeekFrom};
+
+use crate::err::{Error, Result};
+
+use byteorder::{LittleEndian, ReadBytesExt};
+use serde::Serialize;
+use winstructs::ntfs::mft_reference::MftReference;
+
+/// The name of the logged utility stream used by Transactional NTFS (TxF).
+pub const TXF_DATA_STREAM_NAME: &str = "$TXF_DATA";
+
+/// The size of the `$TXF_DATA` stream, shorter streams are kept raw.
+pub const TXF_DATA_SIZE: usize = 56;
+
+/// $LOGGED_UTILITY_STREAM Attribute named `$TXF_DATA`, which links a file to Transactional NTFS (TxF) metadata.
+///
+/// Other logged utility streams (such as `$EFS`) are kept raw.
+/// <https://github.com/libyal/libfsntfs/blob/main/documentation/New%20Technologies%20File%20System%20(NTFS).asciidoc#transactional-data>
+#[derive(Serialize, Debug, Clone, PartialEq)]
+pub struct LoggedUtilityStreamAttr {
+    /// The root directory of the resource manager which owns the transaction.
+    pub rm_root_reference: MftReference,
+    pub usn_index: u64,
+    /// The TxF file identifier (TxID).
+    pub file_id: u64,
+    /// The LSNs of the last transacted changes, in the `$TxfLog` of the resource manager.
+    pub data_lsn: u64,
+    pub metadata_lsn: u64,
+    pub directory_index_lsn: u64,
+    pub flags: u16,
+}
+
+impl LoggedUtilityStreamAttr {
+    /// Parses a `$TXF_DATA` stream, which is expected to be at least `TXF_DATA_SIZE` bytes long.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use mft::attribute::x100::LoggedUtilityStreamAttr;
+    /// # use std::io::Cursor;
+    ///
+    /// let attribute_buffer: &[u8] = &[
+    ///     0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x05, 0x00, 0x00, 0x00, 0x00, 0x00, 0x05, 0x00,
+    ///     0x20, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x02, 0x00, 0x00, 0x00, 0x00, 0x00,
+    ///     0x00, 0x00, 0x00, 0x10, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
+    ///     0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
+    /// ];
+    ///
+    /// let attribute = LoggedUtilityStreamAttr::from_stream(&mut Cursor::new(attribute_buffer)).unwrap();
+    ///
+    /// assert_eq!(attribute.rm_root_reference.entry, 5);
+    /// assert_eq!(attribute.usn_index, 0x20);
+    /// assert_eq!(attribute.data_lsn, 0x1000);
+    /// ```
+    pub fn from_stream<S: Read + Seek>(stream: &mut S) -> Result<LoggedUtilityStreamAttr> {
+        // 6 unknown bytes.
+        stream.seek(SeekFrom::Current(6))?;
+
+        let rm_root_reference =
+            MftReference::from_reader(stream).map_err(Error::failed_to_read_mft_reference)?;
+
+        Ok(LoggedUtilityStreamAttr {
+            rm_root_reference,
+            usn_index: stream.read_u64::<LittleEndian>()?,
+            file_id: stream.read_u64::<LittleEndian>()?,
+            data_lsn: stream.read_u64::<LittleEndian>()?,
+            metadata_lsn: stream.read_u64::<LittleEndian>()?,
+            directory_index_lsn: stream.read_u64::<LittleEndian>()?,
+            flags: stream.read_u16::<LittleEndian>()?,
+        })
+    }
+}
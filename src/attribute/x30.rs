@@ -1,5 +1,6 @@
 use std::io::{Read, Seek};
 
+use crate::attribute::x10::RawTimestamps;
 use crate::attribute::FileAttributeFlags;
 use crate::err::{Error, Result};
 use log::trace;
@@ -13,7 +14,6 @@ use num_traits::FromPrimitive;
 use serde::Serialize;
 
 use winstructs::ntfs::mft_reference::MftReference;
-use winstructs::timestamp::WinTimestamp;
 
 #[derive(FromPrimitive, Serialize, Clone, Debug, PartialOrd, PartialEq)]
 #[repr(u8)]
@@ -24,6 +24,16 @@ pub enum FileNamespace {
     Win32AndDos = 3,
 }
 
+/// How invalid UTF-16 in file names (such as unpaired surrogates) is handled.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum NameDecodePolicy {
+    /// Invalid sequences are replaced with U+FFFD, and `FileNameAttr::name_had_decode_errors` is set.
+    #[default]
+    Lossy,
+    /// Invalid sequences fail the attribute with `Error::InvalidFilename`.
+    Strict,
+}
+
 #[derive(Serialize, Clone, Debug, PartialEq)]
 pub struct FileNameAttr {
     pub parent: MftReference,
@@ -31,6 +41,9 @@ pub struct FileNameAttr {
     pub modified: DateTime<Utc>,
     pub mft_modified: DateTime<Utc>,
     pub accessed: DateTime<Utc>,
+    /// The timestamps above as they are stored, which are more precise than their parsed counterparts.
+    #[serde(skip)]
+    pub raw_timestamps: RawTimestamps,
     pub logical_size: u64,
     pub physical_size: u64,
     pub flags: FileAttributeFlags,
@@ -38,6 +51,14 @@ pub struct FileNameAttr {
     pub name_length: u8,
     pub namespace: FileNamespace,
     pub name: String,
+    /// Set if the name is not valid UTF-16, and was decoded lossily.
+    /// Such names are sometimes crafted on purpose, to break tools which expect valid names.
+    #[serde(skip_serializing_if = "is_false")]
+    pub name_had_decode_errors: bool,
+}
+
+fn is_false(value: &bool) -> bool {
+    !value
 }
 
 impl FileNameAttr {
@@ -75,21 +96,19 @@ impl FileNameAttr {
     /// assert_eq!(attribute.name, "$LogFile");
     /// ```
     pub fn from_stream<S: Read + Seek>(stream: &mut S) -> Result<FileNameAttr> {
+        Self::from_stream_with_policy(stream, NameDecodePolicy::default())
+    }
+
+    /// Parse a Filename attribute buffer, handling names which are not valid UTF-16 according to `policy`.
+    pub fn from_stream_with_policy<S: Read + Seek>(
+        stream: &mut S,
+        policy: NameDecodePolicy,
+    ) -> Result<FileNameAttr> {
         trace!("Offset {}: FilenameAttr", stream.stream_position()?);
         let parent =
             MftReference::from_reader(stream).map_err(Error::failed_to_read_mft_reference)?;
-        let created = WinTimestamp::from_reader(stream)
-            .map_err(Error::failed_to_read_windows_time)?
-            .to_datetime();
-        let modified = WinTimestamp::from_reader(stream)
-            .map_err(Error::failed_to_read_windows_time)?
-            .to_datetime();
-        let mft_modified = WinTimestamp::from_reader(stream)
-            .map_err(Error::failed_to_read_windows_time)?
-            .to_datetime();
-        let accessed = WinTimestamp::from_reader(stream)
-            .map_err(Error::failed_to_read_windows_time)?
-            .to_datetime();
+        let raw_timestamps = RawTimestamps::from_reader(stream)?;
+        let [created, modified, mft_modified, accessed] = raw_timestamps.to_datetimes()?;
 
         let logical_size = stream.read_u64::<LittleEndian>()?;
         let physical_size = stream.read_u64::<LittleEndian>()?;
@@ -103,9 +122,15 @@ impl FileNameAttr {
         let mut name_buffer = vec![0; name_length as usize * 2];
         stream.read_exact(&mut name_buffer)?;
 
-        let name = match UTF_16LE.decode(&name_buffer, DecoderTrap::Ignore) {
-            Ok(s) => s,
-            Err(_e) => return Err(Error::InvalidFilename {}),
+        let (name, name_had_decode_errors) = match UTF_16LE
+            .decode(&name_buffer, DecoderTrap::Strict)
+        {
+            Ok(s) => (s, false),
+            Err(_e) if policy == NameDecodePolicy::Strict => return Err(Error::InvalidFilename {}),
+            Err(_e) => match UTF_16LE.decode(&name_buffer, DecoderTrap::Replace) {
+                Ok(s) => (s, true),
+                Err(_e) => return Err(Error::InvalidFilename {}),
+            },
         };
 
         Ok(FileNameAttr {
@@ -114,6 +139,7 @@ impl FileNameAttr {
             modified,
             mft_modified,
             accessed,
+            raw_timestamps,
             logical_size,
             physical_size,
             flags,
@@ -121,6 +147,43 @@ impl FileNameAttr {
             name_length,
             namespace,
             name,
+            name_had_decode_errors,
         })
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::{FileNameAttr, NameDecodePolicy};
+    use crate::err::Error;
+    use std::io::Cursor;
+
+    /// A $FILE_NAME attribute of `$LogFile`, with an unpaired surrogate in place of the `L`.
+    const INVALID_NAME_ATTRIBUTE: &[u8] = &[
+        0x05, 0x00, 0x00, 0x00, 0x00, 0x00, 0x05, 0x00, 0xD5, 0x2D, 0x48, 0x58, 0x43, 0x5F, 0xCE,
+        0x01, 0xD5, 0x2D, 0x48, 0x58, 0x43, 0x5F, 0xCE, 0x01, 0xD5, 0x2D, 0x48, 0x58, 0x43, 0x5F,
+        0xCE, 0x01, 0xD5, 0x2D, 0x48, 0x58, 0x43, 0x5F, 0xCE, 0x01, 0x00, 0x00, 0x00, 0x04, 0x00,
+        0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x04, 0x00, 0x00, 0x00, 0x00, 0x06, 0x00, 0x00, 0x00,
+        0x00, 0x00, 0x00, 0x00, 0x08, 0x03, 0x24, 0x00, 0x00, 0xD8, 0x6F, 0x00, 0x67, 0x00, 0x46,
+        0x00, 0x69, 0x00, 0x6C, 0x00, 0x65, 0x00,
+    ];
+
+    #[test]
+    fn test_invalid_name_is_decoded_lossily() {
+        let attribute =
+            FileNameAttr::from_stream(&mut Cursor::new(INVALID_NAME_ATTRIBUTE)).unwrap();
+
+        assert_eq!(attribute.name, "$\u{FFFD}ogFile");
+        assert!(attribute.name_had_decode_errors);
+    }
+
+    #[test]
+    fn test_invalid_name_fails_with_strict_policy() {
+        let result = FileNameAttr::from_stream_with_policy(
+            &mut Cursor::new(INVALID_NAME_ATTRIBUTE),
+            NameDecodePolicy::Strict,
+        );
+
+        assert!(matches!(result, Err(Error::InvalidFilename)));
+    }
+}
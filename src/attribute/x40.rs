@@ -1,9 +1,13 @@
 use std::io::{Read, Seek};
 
 use crate::err::{Error, Result};
+use chrono::{DateTime, TimeZone, Utc};
 use serde::Serialize;
 use winstructs::guid::Guid;
 
+/// The number of 100-nanosecond intervals between the UUID epoch (1582-10-15) and the unix epoch.
+const UUID_EPOCH_OFFSET: i64 = 0x01B2_1DD2_1381_4000;
+
 /// $Data Attribute
 #[derive(Serialize, Clone, Debug)]
 pub struct ObjectIdAttr {
@@ -15,12 +19,17 @@ pub struct ObjectIdAttr {
     pub birth_object_id: Option<Guid>,
     /// Domain in which object was created
     pub domain_id: Option<Guid>,
+    /// The raw bytes of `object_id`, which `Guid` does not expose.
+    #[serde(skip)]
+    object_id_bytes: [u8; 16],
 }
 
 impl ObjectIdAttr {
     /// Data size should be either 16 or 64
     pub fn from_stream<S: Read + Seek>(stream: &mut S, data_size: usize) -> Result<ObjectIdAttr> {
-        let object_id = Guid::from_reader(stream).map_err(Error::failed_to_read_guid)?;
+        let mut object_id_bytes = [0; 16];
+        stream.read_exact(&mut object_id_bytes)?;
+        let object_id = Guid::from_buffer(&object_id_bytes).map_err(Error::failed_to_read_guid)?;
         let (birth_volume_id, birth_object_id, domain_id) = if data_size == 64 {
             let g1 = Guid::from_reader(stream).map_err(Error::failed_to_read_guid)?;
             let g2 = Guid::from_reader(stream).map_err(Error::failed_to_read_guid)?;
@@ -35,6 +44,32 @@ impl ObjectIdAttr {
             birth_volume_id,
             birth_object_id,
             domain_id,
+            object_id_bytes,
         })
     }
+
+    /// Returns the creation time embedded in the object id, if it is a version 1 (time-based) GUID.
+    ///
+    /// This is the time at which the object id was assigned, and is rarely tampered with,
+    /// so it can corroborate the creation time of the file.
+    pub fn object_id_timestamp(&self) -> Option<DateTime<Utc>> {
+        let bytes = &self.object_id_bytes;
+        let time_low = u64::from(u32::from_le_bytes([bytes[0], bytes[1], bytes[2], bytes[3]]));
+        let time_mid = u64::from(u16::from_le_bytes([bytes[4], bytes[5]]));
+        let time_hi_and_version = u64::from(u16::from_le_bytes([bytes[6], bytes[7]]));
+
+        if time_hi_and_version >> 12 != 1 {
+            return None;
+        }
+
+        // A 60-bit count of 100-nanosecond intervals since the UUID epoch.
+        let timestamp = ((time_hi_and_version & 0x0FFF) << 48) | (time_mid << 32) | time_low;
+        let since_unix_epoch = timestamp as i64 - UUID_EPOCH_OFFSET;
+
+        Utc.timestamp_opt(
+            since_unix_epoch.div_euclid(10_000_000),
+            (since_unix_epoch.rem_euclid(10_000_000) * 100) as u32,
+        )
+        .single()
+    }
 }
@@ -0,0 +1,294 @@
+use std::io::{Cursor, Seek, SeekFrom};
+
+use crate::err::Result;
+use crate::impl_serialize_for_bitflags;
+use crate::sid::Sid;
+
+use bitflags::bitflags;
+use byteorder::{LittleEndian, ReadBytesExt};
+use serde::Serialize;
+
+/// The only known revision of `SECURITY_DESCRIPTOR_RELATIVE`.
+const SECURITY_DESCRIPTOR_REVISION: u8 = 1;
+
+bitflags! {
+    /// <https://learn.microsoft.com/en-us/openspecs/windows_protocols/ms-dtyp/7d4dac05-9cef-4563-a058-f108abecce1d>
+    pub struct SecurityDescriptorControl: u16 {
+        const OWNER_DEFAULTED       = 0x0001;
+        const GROUP_DEFAULTED       = 0x0002;
+        const DACL_PRESENT          = 0x0004;
+        const DACL_DEFAULTED        = 0x0008;
+        const SACL_PRESENT          = 0x0010;
+        const SACL_DEFAULTED        = 0x0020;
+        const DACL_TRUSTED          = 0x0040;
+        const SERVER_SECURITY       = 0x0080;
+        const DACL_AUTO_INHERIT_REQ = 0x0100;
+        const SACL_AUTO_INHERIT_REQ = 0x0200;
+        const DACL_AUTO_INHERITED   = 0x0400;
+        const SACL_AUTO_INHERITED   = 0x0800;
+        const DACL_PROTECTED        = 0x1000;
+        const SACL_PROTECTED        = 0x2000;
+        const RM_CONTROL_VALID      = 0x4000;
+        const SELF_RELATIVE         = 0x8000;
+    }
+}
+impl_serialize_for_bitflags! {SecurityDescriptorControl}
+
+bitflags! {
+    pub struct AceFlags: u8 {
+        const OBJECT_INHERIT_ACE         = 0x01;
+        const CONTAINER_INHERIT_ACE      = 0x02;
+        const NO_PROPAGATE_INHERIT_ACE   = 0x04;
+        const INHERIT_ONLY_ACE           = 0x08;
+        const INHERITED_ACE              = 0x10;
+        const SUCCESSFUL_ACCESS_ACE_FLAG = 0x40;
+        const FAILED_ACCESS_ACE_FLAG     = 0x80;
+    }
+}
+impl_serialize_for_bitflags! {AceFlags}
+
+/// The type of an access control entry.
+/// <https://learn.microsoft.com/en-us/openspecs/windows_protocols/ms-dtyp/628ebb1d-c509-4ea0-a10f-77ef97ca4586>
+#[derive(Serialize, Debug, Clone, Copy, PartialEq, Eq)]
+pub enum AceType {
+    AccessAllowed,
+    AccessDenied,
+    SystemAudit,
+    SystemAlarm,
+    /// Other types (such as object or callback ACEs), whose SID is not parsed.
+    Unknown(u8),
+}
+
+impl From<u8> for AceType {
+    fn from(ace_type: u8) -> Self {
+        match ace_type {
+            0x00 => AceType::AccessAllowed,
+            0x01 => AceType::AccessDenied,
+            0x02 => AceType::SystemAudit,
+            0x03 => AceType::SystemAlarm,
+            ace_type => AceType::Unknown(ace_type),
+        }
+    }
+}
+
+/// An access control entry.
+#[derive(Serialize, Debug, Clone, PartialEq, Eq)]
+pub struct Ace {
+    pub ace_type: AceType,
+    pub ace_flags: AceFlags,
+    pub access_mask: u32,
+    /// The trustee of the entry, `None` for ACE types whose layout is not known.
+    pub sid: Option<Sid>,
+}
+
+/// $SECURITY_DESCRIPTOR Attribute, a self-relative security descriptor.
+///
+/// Newer volumes store security descriptors in the `$Secure` file instead,
+/// so this attribute is mostly found on volumes formatted by Windows NT.
+#[derive(Serialize, Debug, Clone)]
+pub struct SecurityDescriptorAttr {
+    pub revision: u8,
+    pub control: SecurityDescriptorControl,
+    /// Offsets of the components from the start of the descriptor, 0 if a component is absent.
+    pub owner_offset: u32,
+    pub group_offset: u32,
+    pub sacl_offset: u32,
+    pub dacl_offset: u32,
+    pub owner: Option<Sid>,
+    pub group: Option<Sid>,
+    /// The entries of the discretionary ACL, `None` if the descriptor has no DACL (which grants full access to everyone).
+    pub dacl: Option<Vec<Ace>>,
+}
+
+impl SecurityDescriptorAttr {
+    /// Parses a self-relative security descriptor.
+    /// Returns `None` if the revision of the descriptor is unknown.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use mft::attribute::x50::SecurityDescriptorAttr;
+    ///
+    /// let descriptor_buffer: &[u8] = &[
+    ///     0x01, 0x00, 0x00, 0x80, 0x14, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
+    ///     0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x01, 0x01, 0x00, 0x00, 0x00, 0x00, 0x00, 0x05,
+    ///     0x12, 0x00, 0x00, 0x00,
+    /// ];
+    ///
+    /// let descriptor = SecurityDescriptorAttr::from_buffer(descriptor_buffer)
+    ///     .unwrap()
+    ///     .unwrap();
+    ///
+    /// assert_eq!(descriptor.owner.unwrap().to_string(), "S-1-5-18");
+    /// assert!(descriptor.dacl.is_none());
+    /// ```
+    pub fn from_buffer(buffer: &[u8]) -> Result<Option<SecurityDescriptorAttr>> {
+        let mut cursor = Cursor::new(buffer);
+
+        let revision = cursor.read_u8()?;
+        if revision != SECURITY_DESCRIPTOR_REVISION {
+            return Ok(None);
+        }
+
+        let _sbz1 = cursor.read_u8()?;
+        let control =
+            SecurityDescriptorControl::from_bits_truncate(cursor.read_u16::<LittleEndian>()?);
+        let owner_offset = cursor.read_u32::<LittleEndian>()?;
+        let group_offset = cursor.read_u32::<LittleEndian>()?;
+        let sacl_offset = cursor.read_u32::<LittleEndian>()?;
+        let dacl_offset = cursor.read_u32::<LittleEndian>()?;
+
+        let mut read_sid = |offset: u32| -> Result<Option<Sid>> {
+            if offset == 0 {
+                return Ok(None);
+            }
+
+            cursor.seek(SeekFrom::Start(u64::from(offset)))?;
+            Ok(Some(Sid::from_stream(&mut cursor)?))
+        };
+
+        let owner = read_sid(owner_offset)?;
+        let group = read_sid(group_offset)?;
+
+        let dacl = if control.contains(SecurityDescriptorControl::DACL_PRESENT) && dacl_offset != 0
+        {
+            Some(read_acl(buffer, dacl_offset)?)
+        } else {
+            None
+        };
+
+        Ok(Some(SecurityDescriptorAttr {
+            revision,
+            control,
+            owner_offset,
+            group_offset,
+            sacl_offset,
+            dacl_offset,
+            owner,
+            group,
+            dacl,
+        }))
+    }
+}
+
+/// Reads the entries of the ACL at `offset`.
+fn read_acl(buffer: &[u8], offset: u32) -> Result<Vec<Ace>> {
+    let mut cursor = Cursor::new(buffer);
+    cursor.seek(SeekFrom::Start(u64::from(offset)))?;
+
+    let _revision = cursor.read_u8()?;
+    let _sbz1 = cursor.read_u8()?;
+    let _acl_size = cursor.read_u16::<LittleEndian>()?;
+    let ace_count = cursor.read_u16::<LittleEndian>()?;
+    let _sbz2 = cursor.read_u16::<LittleEndian>()?;
+
+    let mut aces = Vec::with_capacity(usize::from(ace_count));
+    for _ in 0..ace_count {
+        let ace_start = cursor.position();
+
+        let ace_type = AceType::from(cursor.read_u8()?);
+        let ace_flags = AceFlags::from_bits_truncate(cursor.read_u8()?);
+        let ace_size = cursor.read_u16::<LittleEndian>()?;
+        let access_mask = cursor.read_u32::<LittleEndian>()?;
+
+        let sid = match ace_type {
+            AceType::Unknown(_) => None,
+            _ => Some(Sid::from_stream(&mut cursor)?),
+        };
+
+        aces.push(Ace {
+            ace_type,
+            ace_flags,
+            access_mask,
+            sid,
+        });
+
+        cursor.seek(SeekFrom::Start(ace_start + u64::from(ace_size)))?;
+    }
+
+    Ok(aces)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{AceFlags, AceType, SecurityDescriptorAttr, SecurityDescriptorControl};
+    use crate::attribute::MftAttributeContent;
+    use crate::tests::fixtures::synthetic_entry_with_attributes;
+    use crate::MftEntry;
+
+    /// Owned by `BUILTIN\Administrators`, with `SYSTEM` as the group, and a DACL granting
+    /// full control to `BUILTIN\Administrators` and read access to `Authenticated Users`.
+    #[rustfmt::skip]
+    const DESCRIPTOR: &[u8] = &[
+        // The header, with the owner at 0x14, the group at 0x24 and the DACL at 0x30.
+        0x01, 0x00, 0x04, 0x80, 0x14, 0x00, 0x00, 0x00, 0x24, 0x00, 0x00, 0x00,
+        0x00, 0x00, 0x00, 0x00, 0x30, 0x00, 0x00, 0x00,
+        // The owner, S-1-5-32-544.
+        0x01, 0x02, 0x00, 0x00, 0x00, 0x00, 0x00, 0x05, 0x20, 0x00, 0x00, 0x00, 0x20, 0x02, 0x00, 0x00,
+        // The group, S-1-5-18.
+        0x01, 0x01, 0x00, 0x00, 0x00, 0x00, 0x00, 0x05, 0x12, 0x00, 0x00, 0x00,
+        // The DACL header, with two entries.
+        0x02, 0x00, 0x34, 0x00, 0x02, 0x00, 0x00, 0x00,
+        // Allows full control to S-1-5-32-544, inherited by files and directories.
+        0x00, 0x03, 0x18, 0x00, 0xFF, 0x01, 0x1F, 0x00,
+        0x01, 0x02, 0x00, 0x00, 0x00, 0x00, 0x00, 0x05, 0x20, 0x00, 0x00, 0x00, 0x20, 0x02, 0x00, 0x00,
+        // Allows read and execute to S-1-5-11.
+        0x00, 0x00, 0x14, 0x00, 0xA9, 0x00, 0x12, 0x00,
+        0x01, 0x01, 0x00, 0x00, 0x00, 0x00, 0x00, 0x05, 0x0B, 0x00, 0x00, 0x00,
+    ];
+
+    #[test]
+    fn test_security_descriptor_with_dacl() {
+        let descriptor = SecurityDescriptorAttr::from_buffer(DESCRIPTOR)
+            .unwrap()
+            .unwrap();
+
+        assert_eq!(
+            descriptor.control,
+            SecurityDescriptorControl::SELF_RELATIVE | SecurityDescriptorControl::DACL_PRESENT
+        );
+        assert_eq!(descriptor.owner.unwrap().to_string(), "S-1-5-32-544");
+        assert_eq!(descriptor.group.unwrap().to_string(), "S-1-5-18");
+
+        let dacl = descriptor.dacl.unwrap();
+        assert_eq!(dacl.len(), 2);
+
+        assert_eq!(dacl[0].ace_type, AceType::AccessAllowed);
+        assert_eq!(
+            dacl[0].ace_flags,
+            AceFlags::OBJECT_INHERIT_ACE | AceFlags::CONTAINER_INHERIT_ACE
+        );
+        assert_eq!(dacl[0].access_mask, 0x001F_01FF);
+        assert_eq!(dacl[0].sid.as_ref().unwrap().to_string(), "S-1-5-32-544");
+
+        assert_eq!(dacl[1].ace_type, AceType::AccessAllowed);
+        assert_eq!(dacl[1].access_mask, 0x0012_00A9);
+        assert_eq!(dacl[1].sid.as_ref().unwrap().to_string(), "S-1-5-11");
+    }
+
+    #[test]
+    fn test_unknown_revision() {
+        let mut buffer = DESCRIPTOR.to_vec();
+        buffer[0] = 2;
+
+        assert!(SecurityDescriptorAttr::from_buffer(&buffer)
+            .unwrap()
+            .is_none());
+    }
+
+    #[test]
+    fn test_malformed_descriptor_is_kept_raw() {
+        // The owner SID is cut short.
+        let truncated = &DESCRIPTOR[..0x18];
+        let buffer = synthetic_entry_with_attributes(1024, 0, &[(0x50, truncated)]);
+        let entry = MftEntry::from_buffer(buffer, 0).unwrap();
+
+        assert!(SecurityDescriptorAttr::from_buffer(truncated).is_err());
+
+        let attribute = entry.iter_attributes().next().unwrap().unwrap();
+        match attribute.data {
+            MftAttributeContent::Raw(raw) => assert_eq!(raw.data, truncated),
+            other => panic!("Expected a raw attribute, got {:?}", other),
+        }
+    }
+}
@@ -0,0 +1,69 @@
+use std::io::{Read, Seek};
+
+use crate::err::Result;
+use crate::utils::read_utf16_string;
+
+use serde::Serialize;
+
+/// $VOLUME_NAME Attribute, the label of the volume.
+///
+/// Only found in the `$Volume` entry (entry 3).
+#[derive(Serialize, Debug, Clone, PartialEq, Eq)]
+pub struct VolumeNameAttr {
+    /// The volume label, empty if the volume has no label.
+    pub name: String,
+}
+
+impl VolumeNameAttr {
+    /// Reads a volume label of `data_size` bytes, the label is not null terminated.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use mft::attribute::x60::VolumeNameAttr;
+    /// # use std::io::Cursor;
+    ///
+    /// let label_buffer: &[u8] = &[0x44, 0x00, 0x61, 0x00, 0x74, 0x00, 0x61, 0x00];
+    ///
+    /// let volume_name =
+    ///     VolumeNameAttr::from_stream(&mut Cursor::new(label_buffer), label_buffer.len()).unwrap();
+    ///
+    /// assert_eq!(volume_name.name, "Data");
+    /// ```
+    pub fn from_stream<S: Read + Seek>(stream: &mut S, data_size: usize) -> Result<VolumeNameAttr> {
+        let name = read_utf16_string(stream, Some(data_size / 2))?;
+
+        Ok(VolumeNameAttr { name })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::VolumeNameAttr;
+    use crate::tests::fixtures::mft_sample;
+    use crate::MftParser;
+
+    use std::io::Cursor;
+
+    #[test]
+    fn test_empty_volume_name() {
+        let volume_name = VolumeNameAttr::from_stream(&mut Cursor::new(&[]), 0).unwrap();
+
+        assert_eq!(volume_name.name, "");
+    }
+
+    #[test]
+    fn test_volume_name_of_sample() {
+        let mut parser = MftParser::from_path(mft_sample()).unwrap();
+        let volume = parser.get_entry(3).unwrap();
+
+        let volume_name = volume
+            .iter_attributes()
+            .filter_map(Result::ok)
+            .find_map(|a| a.data.into_volume_name())
+            .unwrap();
+
+        // The sample volume has no label.
+        assert_eq!(volume_name.name, "");
+    }
+}
@@ -0,0 +1,107 @@
+use std::io::{Read, Seek, SeekFrom};
+
+use crate::err::Result;
+use crate::impl_serialize_for_bitflags;
+
+use bitflags::bitflags;
+use byteorder::{LittleEndian, ReadBytesExt};
+use serde::Serialize;
+
+bitflags! {
+    /// <https://github.com/libyal/libfsntfs/blob/main/documentation/New%20Technologies%20File%20System%20(NTFS).asciidoc#volume-information-flags>
+    pub struct VolumeFlags: u16 {
+        /// The volume was not cleanly unmounted, and will be checked on the next mount.
+        const IS_DIRTY              = 0x0001;
+        const RESIZE_LOG_FILE       = 0x0002;
+        const UPGRADE_ON_MOUNT      = 0x0004;
+        const MOUNTED_ON_NT4        = 0x0008;
+        const DELETE_USN_UNDERWAY   = 0x0010;
+        const REPAIR_OBJECT_ID      = 0x0020;
+        const CHKDSK_UNDERWAY       = 0x4000;
+        const MODIFIED_BY_CHKDSK    = 0x8000;
+    }
+}
+impl_serialize_for_bitflags! {VolumeFlags}
+
+/// $VOLUME_INFORMATION Attribute, the version of NTFS which formatted the volume and its state.
+///
+/// Only found in the `$Volume` entry (entry 3).
+#[derive(Serialize, Debug, Clone, PartialEq, Eq)]
+pub struct VolumeInformationAttr {
+    pub major_version: u8,
+    pub minor_version: u8,
+    pub flags: VolumeFlags,
+}
+
+impl VolumeInformationAttr {
+    /// # Example
+    ///
+    /// ```
+    /// use mft::attribute::x70::{VolumeFlags, VolumeInformationAttr};
+    /// # use std::io::Cursor;
+    ///
+    /// let information_buffer: &[u8] = &[
+    ///     0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x03, 0x01, 0x01, 0x00,
+    /// ];
+    ///
+    /// let information =
+    ///     VolumeInformationAttr::from_stream(&mut Cursor::new(information_buffer)).unwrap();
+    ///
+    /// assert!(information.flags.contains(VolumeFlags::IS_DIRTY));
+    /// ```
+    pub fn from_stream<S: Read + Seek>(stream: &mut S) -> Result<VolumeInformationAttr> {
+        // 8 reserved bytes.
+        stream.seek(SeekFrom::Current(8))?;
+
+        let major_version = stream.read_u8()?;
+        let minor_version = stream.read_u8()?;
+        let flags = VolumeFlags::from_bits_truncate(stream.read_u16::<LittleEndian>()?);
+
+        Ok(VolumeInformationAttr {
+            major_version,
+            minor_version,
+            flags,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{VolumeFlags, VolumeInformationAttr};
+    use crate::tests::fixtures::mft_sample;
+    use crate::MftParser;
+
+    use std::io::Cursor;
+
+    #[test]
+    fn test_volume_information() {
+        let buffer: &[u8] = &[
+            0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x03, 0x01, 0x00, 0x00,
+        ];
+
+        let information = VolumeInformationAttr::from_stream(&mut Cursor::new(buffer)).unwrap();
+
+        assert_eq!(
+            (information.major_version, information.minor_version),
+            (3, 1)
+        );
+        assert_eq!(information.flags, VolumeFlags::empty());
+    }
+
+    #[test]
+    fn test_volume_information_of_sample() {
+        let mut parser = MftParser::from_path(mft_sample()).unwrap();
+        let volume = parser.get_entry(3).unwrap();
+
+        let information = volume
+            .iter_attributes()
+            .filter_map(Result::ok)
+            .find_map(|a| a.data.into_volume_information())
+            .unwrap();
+
+        assert_eq!(
+            (information.major_version, information.minor_version),
+            (3, 1)
+        );
+    }
+}
@@ -3,8 +3,11 @@ use std::io::{Read, Seek};
 use crate::impl_serialize_for_bitflags;
 use crate::err::{Error, Result};
 use crate::attribute::x30::FileNameAttr;
+use crate::attribute::MftAttributeType;
+use crate::index::INDX_SIGNATURE;
+use crate::utils::apply_usa_fixups;
 
-use byteorder::{LittleEndian, ReadBytesExt};
+use byteorder::{ByteOrder, LittleEndian, ReadBytesExt};
 
 use bitflags::bitflags;
 use serde::Serialize;
@@ -77,7 +80,16 @@ impl IndexRootAttr {
         let index_node_length = stream.read_u32::<LittleEndian>()?;
         let index_node_allocation_length = stream.read_u32::<LittleEndian>()?;            
         let index_root_flags = IndexRootFlags::from_bits_truncate(stream.read_u32::<LittleEndian>()?);
-        let index_entries = IndexEntries::from_stream(stream, index_node_length, index_node_start_pos)?;
+        // Only filename indexes ($I30) contain `$FILE_NAME` keys,
+        // view indexes (such as `$Q` or `$O`) have an attribute type of 0 and are not parsed here.
+        let index_entries = if attribute_type == MftAttributeType::FileName as u32 {
+            IndexEntries::from_stream(stream, index_node_length, index_node_start_pos)?
+        } else {
+            IndexEntries {
+                index_entries: Vec::new(),
+                end_sub_node_vcn: None,
+            }
+        };
 
         Ok(IndexRootAttr {
             attribute_type,
@@ -99,7 +111,10 @@ pub struct IndexEntryHeader {
     pub index_record_length: u16,
     pub attr_fname_length: u16,
     pub flags: IndexEntryFlags,
-    pub fname_info: FileNameAttr
+    pub fname_info: FileNameAttr,
+    /// The VCN of the sub-node in the $INDEX_ALLOCATION attribute.
+    /// Only present if the `INDEX_ENTRY_NODE` flag is set, stored in the last 8 bytes of the entry.
+    pub sub_node_vcn: Option<u64>
 }
 bitflags! {
     pub struct IndexEntryFlags: u32 {
@@ -109,38 +124,91 @@ bitflags! {
 }
 impl_serialize_for_bitflags! {IndexEntryFlags}
 
+/// The size of the fixed part of an index entry, before its key.
+const INDEX_ENTRY_HEADER_SIZE: u16 = 0x10;
+
+/// An entry of an index node, see `IndexEntryHeader::read_entry`.
+#[derive(Serialize, Clone, Debug, PartialEq)]
+pub enum IndexNodeEntry {
+    Entry(IndexEntryHeader),
+    /// The terminating entry of a node (`INDEX_ENTRY_END`), which has no key.
+    /// If the node has sub-nodes, it holds the VCN of the rightmost one.
+    End { sub_node_vcn: Option<u64> },
+}
+
 impl IndexEntryHeader {  
+    /// Reads an index entry, returning `None` for the terminating entry of the node (see `read_entry`).
     pub fn from_stream<S: Read + Seek>(stream: &mut S) -> Result<Option<IndexEntryHeader>> {          
-        let start_pos = stream.stream_position().unwrap();
+        match IndexEntryHeader::read_entry(stream)? {
+            IndexNodeEntry::Entry(entry) => Ok(Some(entry)),
+            IndexNodeEntry::End { .. } => Ok(None),
+        }
+    }
+
+    /// Reads an index entry, including the terminating entry of the node and its sub-node VCN,
+    /// which is needed to reach the rightmost sub-node when traversing the index.
+    ///
+    /// Entries without a valid MFT reference have no `$FILE_NAME` key, so they are treated as terminating entries too.
+    /// Fails if the length of the entry is too short to hold its key (and sub-node VCN).
+    pub fn read_entry<S: Read + Seek>(stream: &mut S) -> Result<IndexNodeEntry> {
+        let start_pos = stream.stream_position()?;
 
         let mft_reference =
             MftReference::from_reader(stream).map_err(Error::failed_to_read_mft_reference)?;
-        if mft_reference.entry > 0 && mft_reference.sequence > 0 {
-            let index_record_length = stream.read_u16::<LittleEndian>()?;            
-            let end_pos = start_pos + u64::from(index_record_length);           
-            let attr_fname_length = stream.read_u16::<LittleEndian>()?;
-            let flags = IndexEntryFlags::from_bits_truncate(stream.read_u32::<LittleEndian>()?);
-            let fname_info = FileNameAttr::from_stream(stream)?;
-
-            stream.seek(SeekFrom::Start(end_pos)).unwrap(); 
-            
-            Ok(Some(IndexEntryHeader {
+        let index_record_length = stream.read_u16::<LittleEndian>()?;            
+        let end_pos = start_pos + u64::from(index_record_length);           
+        let attr_fname_length = stream.read_u16::<LittleEndian>()?;
+        let flags = IndexEntryFlags::from_bits_truncate(stream.read_u32::<LittleEndian>()?);
+
+        let is_end = flags.contains(IndexEntryFlags::INDEX_ENTRY_END)
+            || mft_reference.entry == 0
+            || mft_reference.sequence == 0;
+        // The key of the terminating entry is ignored, even if it claims to have one.
+        let key_length = if is_end { 0 } else { attr_fname_length };
+        let sub_node_vcn_length = if flags.contains(IndexEntryFlags::INDEX_ENTRY_NODE) { 8 } else { 0 };
+
+        let minimum_length = u32::from(INDEX_ENTRY_HEADER_SIZE) + u32::from(key_length) + sub_node_vcn_length;
+        if u32::from(index_record_length) < minimum_length {
+            return Err(Error::InvalidIndexEntry {
+                length: index_record_length,
+                minimum_length,
+            });
+        }
+
+        let fname_info = if is_end {
+            None
+        } else {
+            Some(FileNameAttr::from_stream(stream)?)
+        };
+
+        let sub_node_vcn = if flags.contains(IndexEntryFlags::INDEX_ENTRY_NODE) {
+            stream.seek(SeekFrom::Start(end_pos - 8))?;
+            Some(stream.read_u64::<LittleEndian>()?)
+        } else {
+            None
+        };
+
+        stream.seek(SeekFrom::Start(end_pos))?; 
+
+        Ok(match fname_info {
+            Some(fname_info) => IndexNodeEntry::Entry(IndexEntryHeader {
                 mft_reference,
                 index_record_length,
                 attr_fname_length,
                 flags,
-                fname_info
-            }))
-        }
-        else {
-            Ok(None)
-        }
+                fname_info,
+                sub_node_vcn
+            }),
+            None => IndexNodeEntry::End { sub_node_vcn },
+        })
     }
 }
 
 #[derive(Serialize, Clone, Debug)]
 pub struct IndexEntries {
-    pub index_entries: Vec<IndexEntryHeader>
+    pub index_entries: Vec<IndexEntryHeader>,
+    /// The VCN of the rightmost sub-node, stored in the terminating entry of the node.
+    pub end_sub_node_vcn: Option<u64>
 }
 
 impl IndexEntries {
@@ -152,17 +220,162 @@ impl IndexEntries {
         let end_pos = index_node_start_pos + u64::from(index_node_length);
 
         let mut index_entries: Vec<IndexEntryHeader> = Vec::new();
+        let mut end_sub_node_vcn = None;
         while stream.stream_position().unwrap() < end_pos
         {            
-            let index_entry = IndexEntryHeader::from_stream(stream)?;
+            let index_entry = IndexEntryHeader::read_entry(stream)?;
             match index_entry {
-                Some(inner)   => index_entries.push(inner),
-                None          => break,
+                IndexNodeEntry::Entry(inner) => index_entries.push(inner),
+                IndexNodeEntry::End { sub_node_vcn } => {
+                    end_sub_node_vcn = sub_node_vcn;
+                    break;
+                }
             }
         }
 
         Ok(IndexEntries{
-            index_entries
+            index_entries,
+            end_sub_node_vcn
+        })
+    }
+}
+
+/// The size of the header of an `INDX` record, including the index node header.
+pub(crate) const INDEX_RECORD_HEADER_SIZE: usize = 0x28;
+
+/// The header of an `INDX` record (a node of the `$INDEX_ALLOCATION` attribute), followed by its index node header.
+/// <https://github.com/libyal/libfsntfs/blob/main/documentation/New%20Technologies%20File%20System%20(NTFS).asciidoc#index-entry-record>
+#[derive(Serialize, Clone, Debug, PartialEq, Eq)]
+pub struct IndexRecordHeader {
+    pub usa_offset: u16,
+    pub usa_size: u16,
+    /// The `$LogFile` sequence number of the last change to the record.
+    pub lsn: u64,
+    /// The VCN of the record in the `$INDEX_ALLOCATION` attribute.
+    pub vcn: u64,
+    /// Relative to the start of the index node header (offset 0x18 of the record), like `index_length`.
+    pub entries_offset: u32,
+    pub index_length: u32,
+    pub allocated_length: u32,
+    /// `LARGE_INDEX` is set if the entries of the record have sub-nodes.
+    pub flags: IndexRootFlags,
+}
+
+impl IndexRecordHeader {
+    /// Parses the header of an `INDX` record, validating its signature and applying the fixups to `buffer` in place.
+    ///
+    /// Unlike MFT entries, a record whose fixups do not match its update sequence is an error
+    /// (`Error::FailedToApplyFixup`), since such a record was only partially written or is not an index record at all.
+    pub fn from_buffer(buffer: &mut [u8]) -> Result<IndexRecordHeader> {
+        if buffer.len() < INDEX_RECORD_HEADER_SIZE {
+            return Err(Error::InvalidIndexRecord {
+                detail: format!("record is too small ({} bytes)", buffer.len()),
+            });
+        }
+
+        if &buffer[..4] != INDX_SIGNATURE {
+            return Err(Error::InvalidIndexRecordSignature {
+                bad_sig: buffer[..4].to_vec(),
+            });
+        }
+
+        let usa_offset = LittleEndian::read_u16(&buffer[0x04..]);
+        let usa_size = LittleEndian::read_u16(&buffer[0x06..]);
+
+        if usa_size == 0 {
+            return Err(Error::InvalidIndexRecord {
+                detail: "record has an empty update sequence array".to_string(),
+            });
+        }
+
+        apply_usa_fixups(buffer, usa_offset, usa_size)?;
+
+        Ok(IndexRecordHeader {
+            usa_offset,
+            usa_size,
+            lsn: LittleEndian::read_u64(&buffer[0x08..]),
+            vcn: LittleEndian::read_u64(&buffer[0x10..]),
+            entries_offset: LittleEndian::read_u32(&buffer[0x18..]),
+            index_length: LittleEndian::read_u32(&buffer[0x1C..]),
+            allocated_length: LittleEndian::read_u32(&buffer[0x20..]),
+            flags: IndexRootFlags::from_bits_truncate(LittleEndian::read_u32(&buffer[0x24..])),
         })
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::{IndexEntryFlags, IndexEntryHeader, IndexNodeEntry, IndexRecordHeader};
+    use crate::err::Error;
+    use crate::tests::fixtures::indx_record;
+    use std::io::Cursor;
+
+    /// Builds an index entry without a key, with the given length and flags, followed by a sub-node VCN of 7.
+    fn keyless_index_entry(length: u16, flags: IndexEntryFlags) -> Vec<u8> {
+        let mut entry = vec![0_u8; 8];
+        entry.extend(length.to_le_bytes());
+        // The length of the key.
+        entry.extend(0_u16.to_le_bytes());
+        entry.extend(flags.bits().to_le_bytes());
+        entry.extend(7_u64.to_le_bytes());
+        entry
+    }
+
+    #[test]
+    fn test_end_entry_sub_node_vcn() {
+        let entry = keyless_index_entry(
+            0x18,
+            IndexEntryFlags::INDEX_ENTRY_NODE | IndexEntryFlags::INDEX_ENTRY_END,
+        );
+
+        assert_eq!(
+            IndexEntryHeader::read_entry(&mut Cursor::new(&entry)).unwrap(),
+            IndexNodeEntry::End {
+                sub_node_vcn: Some(7)
+            }
+        );
+    }
+
+    #[test]
+    fn test_index_entry_too_short_for_sub_node_vcn() {
+        let entry = keyless_index_entry(
+            0x10,
+            IndexEntryFlags::INDEX_ENTRY_NODE | IndexEntryFlags::INDEX_ENTRY_END,
+        );
+
+        assert!(matches!(
+            IndexEntryHeader::read_entry(&mut Cursor::new(&entry)),
+            Err(Error::InvalidIndexEntry {
+                length: 0x10,
+                minimum_length: 0x18
+            })
+        ));
+    }
+
+    #[test]
+    fn test_index_record_header() {
+        let mut record = indx_record(&[(64, "a.txt")]);
+
+        let header = IndexRecordHeader::from_buffer(&mut record).unwrap();
+
+        assert_eq!((header.usa_offset, header.usa_size), (0x28, 9));
+        assert_eq!(header.entries_offset, 0x40);
+        assert_eq!(header.allocated_length, 4096 - 0x18);
+        // The fixups were applied.
+        assert_eq!(&record[510..512], &[0, 0]);
+    }
+
+    #[test]
+    fn test_index_record_header_with_corrupted_fixup() {
+        let mut record = indx_record(&[(64, "a.txt")]);
+        record[2046] = 0xFF;
+
+        assert!(matches!(
+            IndexRecordHeader::from_buffer(&mut record),
+            Err(Error::FailedToApplyFixup {
+                stride_number: 3,
+                ..
+            })
+        ));
+    }
+}
@@ -0,0 +1,81 @@
+use std::io::Read;
+
+use crate::attribute::data_as_hex;
+use crate::err::Result;
+
+use serde::Serialize;
+
+/// $BITMAP Attribute, a bit per allocation unit (such as a record of the `$MFT`, or an `INDX` record of a directory),
+/// set if the unit is in use.
+#[derive(Serialize, Debug, Clone, PartialEq, Eq)]
+pub struct BitmapAttr {
+    #[serde(serialize_with = "data_as_hex")]
+    pub data: Vec<u8>,
+}
+
+impl BitmapAttr {
+    /// Reads a bitmap of `data_size` bytes.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use mft::attribute::xb0::BitmapAttr;
+    /// # use std::io::Cursor;
+    ///
+    /// let bitmap_buffer: &[u8] = &[0x05, 0x00];
+    ///
+    /// let bitmap = BitmapAttr::from_stream(&mut Cursor::new(bitmap_buffer), 2).unwrap();
+    ///
+    /// assert_eq!(bitmap.iter_set_bits().collect::<Vec<_>>(), vec![0, 2]);
+    /// ```
+    pub fn from_stream<S: Read>(stream: &mut S, data_size: usize) -> Result<BitmapAttr> {
+        let mut data = vec![0_u8; data_size];
+        stream.read_exact(&mut data)?;
+
+        Ok(BitmapAttr { data })
+    }
+
+    /// Returns true if bit `index` is set, bits past the end of the bitmap are not set.
+    /// Bits are numbered from the least significant bit of the first byte.
+    pub fn is_set(&self, index: u64) -> bool {
+        usize::try_from(index / 8)
+            .ok()
+            .and_then(|byte_index| self.data.get(byte_index))
+            .is_some_and(|byte| byte & (1 << (index % 8)) != 0)
+    }
+
+    /// Returns an iterator over the indexes of the set bits, in increasing order.
+    pub fn iter_set_bits(&self) -> impl Iterator<Item = u64> + '_ {
+        self.data
+            .iter()
+            .enumerate()
+            .filter(|(_, byte)| **byte != 0)
+            .flat_map(|(byte_index, byte)| {
+                (0..8)
+                    .filter(move |bit| byte & (1 << bit) != 0)
+                    .map(move |bit| byte_index as u64 * 8 + bit)
+            })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::BitmapAttr;
+
+    #[test]
+    fn test_bitmap() {
+        let bitmap = BitmapAttr {
+            data: vec![0b0000_1000, 0b0000_0100],
+        };
+
+        assert!(bitmap.is_set(3));
+        assert!(bitmap.is_set(10));
+        assert!(!bitmap.is_set(0));
+        assert!(!bitmap.is_set(11));
+        // Past the end of the bitmap.
+        assert!(!bitmap.is_set(16));
+        assert!(!bitmap.is_set(u64::MAX));
+
+        assert_eq!(bitmap.iter_set_bits().collect::<Vec<_>>(), vec![3, 10]);
+    }
+}
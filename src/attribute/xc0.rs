@@ -0,0 +1,298 @@
+use std::io::{Cursor, Read, Seek, SeekFrom};
+
+use crate::attribute::data_as_hex;
+use crate::err::Result;
+use crate::utils;
+use byteorder::{LittleEndian, ReadBytesExt};
+use serde::Serialize;
+
+/// The size of the `REPARSE_DATA_BUFFER` header (tag, data length and a reserved field).
+const REPARSE_HEADER_SIZE: usize = 8;
+
+/// Set for relative symbolic links.
+const SYMLINK_FLAG_RELATIVE: u32 = 0x1;
+
+/// The tag of a reparse point, identifying the filter which owns it.
+/// <https://learn.microsoft.com/en-us/openspecs/windows_protocols/ms-fscc/c8e77b37-3909-4fe6-a4ea-2b9d423b1ee4>
+#[derive(Serialize, Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ReparseTag {
+    /// A junction or a volume mount point.
+    MountPoint,
+    SymbolicLink,
+    /// A file whose content was moved to the data deduplication chunk store.
+    Dedup,
+    /// A file compressed (or backed by a WIM image) by the Windows Overlay Filter.
+    Wof,
+    /// A cloud files placeholder (such as OneDrive files).
+    Cloud,
+    /// An app execution alias of a Store application.
+    AppExecLink,
+    Unknown(u32),
+}
+
+impl From<u32> for ReparseTag {
+    fn from(tag: u32) -> Self {
+        match tag {
+            0xA000_0003 => ReparseTag::MountPoint,
+            0xA000_000C => ReparseTag::SymbolicLink,
+            0x8000_0013 => ReparseTag::Dedup,
+            0x8000_0017 => ReparseTag::Wof,
+            0x8000_001B => ReparseTag::AppExecLink,
+            // There are 16 cloud files tags, which differ only in bits 12-15.
+            tag if tag & 0xFFFF_0FFF == 0x9000_001A => ReparseTag::Cloud,
+            tag => ReparseTag::Unknown(tag),
+        }
+    }
+}
+
+/// The reparse data, for tags with a known structure.
+#[derive(Serialize, Debug, Clone, PartialEq, Eq)]
+pub enum ReparseData {
+    /// Symbolic links, junctions and mount points.
+    Link {
+        substitute_name: String,
+        print_name: String,
+        is_relative: bool,
+    },
+    AppExecLink {
+        package_id: String,
+        app_user_model_id: String,
+        target_path: String,
+    },
+    /// The reparse data of other tags, or data which failed to parse.
+    Raw(#[serde(serialize_with = "data_as_hex")] Vec<u8>),
+}
+
+/// $REPARSE_POINT Attribute
+#[derive(Serialize, Debug, Clone)]
+pub struct ReparsePointAttr {
+    pub reparse_tag: ReparseTag,
+    /// The tag as stored, which (unlike `reparse_tag`) tells the cloud files tags apart.
+    #[serde(skip_serializing)]
+    pub raw_tag: u32,
+    pub data: ReparseData,
+}
+
+impl ReparsePointAttr {
+    /// Parses a reparse point, which is expected to be `data_size` bytes long.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use mft::attribute::xc0::{ReparseData, ReparsePointAttr, ReparseTag};
+    /// # use std::io::Cursor;
+    ///
+    /// let attribute_buffer: &[u8] = &[
+    ///     0x17, 0x00, 0x00, 0x80, 0x04, 0x00, 0x00, 0x00, 0x01, 0x02, 0x03, 0x04,
+    /// ];
+    ///
+    /// let attribute = ReparsePointAttr::from_stream(&mut Cursor::new(attribute_buffer), 12).unwrap();
+    ///
+    /// assert_eq!(attribute.reparse_tag, ReparseTag::Wof);
+    /// assert_eq!(attribute.data, ReparseData::Raw(vec![0x01, 0x02, 0x03, 0x04]));
+    /// ```
+    pub fn from_stream<S: Read + Seek>(
+        stream: &mut S,
+        data_size: usize,
+    ) -> Result<ReparsePointAttr> {
+        let mut buffer = vec![0_u8; data_size];
+        stream.read_exact(&mut buffer)?;
+
+        let mut cursor = Cursor::new(buffer.as_slice());
+        let raw_tag = cursor.read_u32::<LittleEndian>()?;
+        let reparse_tag = ReparseTag::from(raw_tag);
+        let data_length = usize::from(cursor.read_u16::<LittleEndian>()?);
+        let _reserved = cursor.read_u16::<LittleEndian>()?;
+
+        let end = (REPARSE_HEADER_SIZE + data_length).min(buffer.len());
+        let reparse_data = &buffer[REPARSE_HEADER_SIZE.min(end)..end];
+
+        let parsed = match reparse_tag {
+            ReparseTag::MountPoint => parse_link(reparse_data, false).ok(),
+            ReparseTag::SymbolicLink => parse_link(reparse_data, true).ok(),
+            ReparseTag::AppExecLink => parse_app_exec_link(reparse_data).ok(),
+            _ => None,
+        };
+
+        Ok(ReparsePointAttr {
+            reparse_tag,
+            raw_tag,
+            data: parsed.unwrap_or_else(|| ReparseData::Raw(reparse_data.to_vec())),
+        })
+    }
+}
+
+/// Parses the reparse data of a mount point or a symbolic link, which only differ by the flags of the symbolic link.
+fn parse_link(data: &[u8], is_symbolic_link: bool) -> Result<ReparseData> {
+    let mut cursor = Cursor::new(data);
+
+    let substitute_name_offset = cursor.read_u16::<LittleEndian>()?;
+    let substitute_name_length = cursor.read_u16::<LittleEndian>()?;
+    let print_name_offset = cursor.read_u16::<LittleEndian>()?;
+    let print_name_length = cursor.read_u16::<LittleEndian>()?;
+
+    let is_relative = if is_symbolic_link {
+        cursor.read_u32::<LittleEndian>()? & SYMLINK_FLAG_RELATIVE != 0
+    } else {
+        false
+    };
+
+    // The offsets are relative to the start of the path buffer.
+    let path_buffer_offset = cursor.position();
+    let mut read_name = |offset: u16, length: u16| {
+        cursor.seek(SeekFrom::Start(path_buffer_offset + u64::from(offset)))?;
+        utils::read_utf16_string(&mut cursor, Some(usize::from(length) / 2))
+    };
+
+    Ok(ReparseData::Link {
+        substitute_name: read_name(substitute_name_offset, substitute_name_length)?,
+        print_name: read_name(print_name_offset, print_name_length)?,
+        is_relative,
+    })
+}
+
+/// Parses the reparse data of an app execution alias,
+/// which is a version followed by a number of null terminated strings.
+fn parse_app_exec_link(data: &[u8]) -> Result<ReparseData> {
+    let mut cursor = Cursor::new(data);
+
+    let _version = cursor.read_u32::<LittleEndian>()?;
+
+    Ok(ReparseData::AppExecLink {
+        package_id: utils::read_utf16_string(&mut cursor, None)?,
+        app_user_model_id: utils::read_utf16_string(&mut cursor, None)?,
+        target_path: utils::read_utf16_string(&mut cursor, None)?,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{ReparseData, ReparsePointAttr, ReparseTag};
+    use std::io::Cursor;
+
+    fn utf16(s: &str) -> Vec<u8> {
+        s.encode_utf16().flat_map(u16::to_le_bytes).collect()
+    }
+
+    fn reparse_point(tag: u32, data: &[u8]) -> Vec<u8> {
+        let mut buffer = tag.to_le_bytes().to_vec();
+        buffer.extend((data.len() as u16).to_le_bytes());
+        buffer.extend([0, 0]);
+        buffer.extend(data);
+        buffer
+    }
+
+    fn parse(buffer: &[u8]) -> ReparsePointAttr {
+        ReparsePointAttr::from_stream(&mut Cursor::new(buffer), buffer.len()).unwrap()
+    }
+
+    #[test]
+    fn test_symbolic_link() {
+        let substitute_name = utf16("\\??\\C:\\target");
+        let print_name = utf16("C:\\target");
+
+        let mut data = vec![];
+        data.extend(0_u16.to_le_bytes());
+        data.extend((substitute_name.len() as u16).to_le_bytes());
+        data.extend((substitute_name.len() as u16).to_le_bytes());
+        data.extend((print_name.len() as u16).to_le_bytes());
+        // Flags
+        data.extend(0_u32.to_le_bytes());
+        data.extend(&substitute_name);
+        data.extend(&print_name);
+
+        let attribute = parse(&reparse_point(0xA000_000C, &data));
+
+        assert_eq!(attribute.reparse_tag, ReparseTag::SymbolicLink);
+        assert_eq!(
+            attribute.data,
+            ReparseData::Link {
+                substitute_name: "\\??\\C:\\target".to_string(),
+                print_name: "C:\\target".to_string(),
+                is_relative: false,
+            }
+        );
+    }
+
+    #[test]
+    fn test_junction() {
+        let substitute_name = utf16("\\??\\C:\\target");
+        let print_name = utf16("C:\\target");
+
+        // Unlike symbolic links, mount points have no flags.
+        let mut data = vec![];
+        data.extend(0_u16.to_le_bytes());
+        data.extend((substitute_name.len() as u16).to_le_bytes());
+        data.extend((substitute_name.len() as u16 + 2).to_le_bytes());
+        data.extend((print_name.len() as u16).to_le_bytes());
+        data.extend(&substitute_name);
+        data.extend([0, 0]);
+        data.extend(&print_name);
+        data.extend([0, 0]);
+
+        let attribute = parse(&reparse_point(0xA000_0003, &data));
+
+        assert_eq!(attribute.reparse_tag, ReparseTag::MountPoint);
+        assert_eq!(
+            attribute.data,
+            ReparseData::Link {
+                substitute_name: "\\??\\C:\\target".to_string(),
+                print_name: "C:\\target".to_string(),
+                is_relative: false,
+            }
+        );
+    }
+
+    #[test]
+    fn test_non_microsoft_tag_is_kept_raw() {
+        let attribute = parse(&reparse_point(0x0000_1234, &[0x01, 0x02, 0x03]));
+
+        assert_eq!(attribute.reparse_tag, ReparseTag::Unknown(0x0000_1234));
+        assert_eq!(attribute.raw_tag, 0x0000_1234);
+        assert_eq!(attribute.data, ReparseData::Raw(vec![0x01, 0x02, 0x03]));
+    }
+
+    #[test]
+    fn test_app_exec_link() {
+        let mut data = 3_u32.to_le_bytes().to_vec();
+        for s in [
+            "Microsoft.WindowsTerminal_8wekyb3d8bbwe",
+            "Microsoft.WindowsTerminal_8wekyb3d8bbwe!App",
+            "C:\\Program Files\\WindowsApps\\wt.exe",
+        ] {
+            data.extend(utf16(s));
+            data.extend([0, 0]);
+        }
+
+        let attribute = parse(&reparse_point(0x8000_001B, &data));
+
+        assert_eq!(attribute.reparse_tag, ReparseTag::AppExecLink);
+        assert_eq!(
+            attribute.data,
+            ReparseData::AppExecLink {
+                package_id: "Microsoft.WindowsTerminal_8wekyb3d8bbwe".to_string(),
+                app_user_model_id: "Microsoft.WindowsTerminal_8wekyb3d8bbwe!App".to_string(),
+                target_path: "C:\\Program Files\\WindowsApps\\wt.exe".to_string(),
+            }
+        );
+    }
+
+    #[test]
+    fn test_cloud_tags() {
+        assert_eq!(ReparseTag::from(0x9000_001A), ReparseTag::Cloud);
+        assert_eq!(ReparseTag::from(0x9000_F01A), ReparseTag::Cloud);
+        assert_eq!(
+            ReparseTag::from(0x9000_001B),
+            ReparseTag::Unknown(0x9000_001B)
+        );
+    }
+
+    #[test]
+    fn test_malformed_data_is_kept_raw() {
+        // A mount point with a truncated header.
+        let attribute = parse(&reparse_point(0xA000_0003, &[0x01, 0x02]));
+
+        assert_eq!(attribute.reparse_tag, ReparseTag::MountPoint);
+        assert_eq!(attribute.data, ReparseData::Raw(vec![0x01, 0x02]));
+    }
+}
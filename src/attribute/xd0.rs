@@ -0,0 +1,40 @@
+use std::io::Read;
+
+use crate::err::Result;
+
+use byteorder::{LittleEndian, ReadBytesExt};
+use serde::Serialize;
+
+/// $EA_INFORMATION Attribute, the sizes of the extended attributes of the file (stored in the $EA attribute).
+#[derive(Serialize, Debug, Clone, PartialEq, Eq)]
+pub struct EaInformationAttr {
+    /// The size of the extended attributes in their packed (`FILE_GET_EA_INFORMATION`) form.
+    pub packed_size: u16,
+    /// The number of extended attributes with the `NEED_EA` flag.
+    pub need_ea_count: u16,
+    /// The size of the $EA attribute content.
+    pub unpacked_size: u32,
+}
+
+impl EaInformationAttr {
+    /// # Example
+    ///
+    /// ```
+    /// use mft::attribute::xd0::EaInformationAttr;
+    /// # use std::io::Cursor;
+    ///
+    /// let attribute_buffer: &[u8] = &[0x2C, 0x00, 0x00, 0x00, 0x38, 0x00, 0x00, 0x00];
+    ///
+    /// let attribute = EaInformationAttr::from_stream(&mut Cursor::new(attribute_buffer)).unwrap();
+    ///
+    /// assert_eq!(attribute.packed_size, 44);
+    /// assert_eq!(attribute.unpacked_size, 56);
+    /// ```
+    pub fn from_stream<S: Read>(stream: &mut S) -> Result<EaInformationAttr> {
+        Ok(EaInformationAttr {
+            packed_size: stream.read_u16::<LittleEndian>()?,
+            need_ea_count: stream.read_u16::<LittleEndian>()?,
+            unpacked_size: stream.read_u32::<LittleEndian>()?,
+        })
+    }
+}
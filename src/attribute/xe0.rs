@@ -0,0 +1,173 @@
+use std::io::{Cursor, Read, Seek, SeekFrom};
+
+use crate::attribute::data_as_hex;
+use crate::err::Result;
+
+use byteorder::{LittleEndian, ReadBytesExt};
+use serde::Serialize;
+
+/// The extended attributes in which WSL stores the owner, group and mode of files created from Linux.
+pub const LXSS_UID_EA_NAME: &str = "$LXUID";
+pub const LXSS_GID_EA_NAME: &str = "$LXGID";
+pub const LXSS_MODE_EA_NAME: &str = "$LXMOD";
+
+/// A single extended attribute, stored as a `FILE_FULL_EA_INFORMATION` structure.
+/// <https://learn.microsoft.com/en-us/windows-hardware/drivers/ddi/wdm/ns-wdm-_file_full_ea_information>
+#[derive(Serialize, Debug, Clone, PartialEq, Eq)]
+pub struct ExtendedAttribute {
+    pub name: String,
+    #[serde(serialize_with = "data_as_hex")]
+    pub value: Vec<u8>,
+    /// 0x80 (`FILE_NEED_EA`) if the file cannot be interpreted without this extended attribute.
+    pub flags: u8,
+}
+
+/// $EA Attribute, the extended attributes of the file.
+#[derive(Serialize, Debug, Clone, PartialEq, Eq)]
+pub struct EaAttr {
+    pub entries: Vec<ExtendedAttribute>,
+}
+
+impl EaAttr {
+    /// Parses the list of extended attributes, which is expected to be `data_size` bytes long.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use mft::attribute::xe0::EaAttr;
+    /// # use std::io::Cursor;
+    ///
+    /// let attribute_buffer: &[u8] = &[
+    ///     0x00, 0x00, 0x00, 0x00, 0x00, 0x06, 0x04, 0x00, b'$', b'L', b'X', b'U', b'I', b'D',
+    ///     0x00, 0xE8, 0x03, 0x00, 0x00,
+    /// ];
+    ///
+    /// let attribute =
+    ///     EaAttr::from_stream(&mut Cursor::new(attribute_buffer), attribute_buffer.len()).unwrap();
+    ///
+    /// assert_eq!(attribute.entries[0].name, "$LXUID");
+    /// assert_eq!(attribute.lxss_uid(), Some(1000));
+    /// ```
+    pub fn from_stream<S: Read + Seek>(stream: &mut S, data_size: usize) -> Result<EaAttr> {
+        let mut buffer = vec![0_u8; data_size];
+        stream.read_exact(&mut buffer)?;
+
+        let mut cursor = Cursor::new(buffer.as_slice());
+        let mut entries = vec![];
+
+        // An empty attribute holds no extended attributes (not even a terminating entry).
+        if buffer.is_empty() {
+            return Ok(EaAttr { entries });
+        }
+
+        loop {
+            let entry_start = cursor.position();
+
+            let next_entry_offset = cursor.read_u32::<LittleEndian>()?;
+            let flags = cursor.read_u8()?;
+            let name_length = cursor.read_u8()?;
+            let value_length = cursor.read_u16::<LittleEndian>()?;
+
+            let mut name = vec![0_u8; usize::from(name_length)];
+            cursor.read_exact(&mut name)?;
+            // The name is null terminated, the terminator is not included in its length.
+            cursor.seek(SeekFrom::Current(1))?;
+
+            let mut value = vec![0_u8; usize::from(value_length)];
+            cursor.read_exact(&mut value)?;
+
+            entries.push(ExtendedAttribute {
+                name: String::from_utf8_lossy(&name).into_owned(),
+                value,
+                flags,
+            });
+
+            // The offset is relative to the start of the entry, 0 for the last entry.
+            if next_entry_offset == 0 {
+                break;
+            }
+
+            cursor.seek(SeekFrom::Start(entry_start + u64::from(next_entry_offset)))?;
+        }
+
+        Ok(EaAttr { entries })
+    }
+
+    /// Returns the value of the extended attribute named `name`.
+    pub fn value(&self, name: &str) -> Option<&[u8]> {
+        self.entries
+            .iter()
+            .find(|entry| entry.name == name)
+            .map(|entry| entry.value.as_slice())
+    }
+
+    /// Returns the Linux owner of a file created by WSL (from the `$LXUID` extended attribute).
+    pub fn lxss_uid(&self) -> Option<u32> {
+        self.u32_value(LXSS_UID_EA_NAME)
+    }
+
+    /// Returns the Linux group of a file created by WSL (from the `$LXGID` extended attribute).
+    pub fn lxss_gid(&self) -> Option<u32> {
+        self.u32_value(LXSS_GID_EA_NAME)
+    }
+
+    /// Returns the Linux mode (file type and permissions) of a file created by WSL
+    /// (from the `$LXMOD` extended attribute).
+    pub fn lxss_mode(&self) -> Option<u32> {
+        self.u32_value(LXSS_MODE_EA_NAME)
+    }
+
+    fn u32_value(&self, name: &str) -> Option<u32> {
+        let value = self.value(name)?;
+
+        Some(u32::from_le_bytes(value.get(..4)?.try_into().ok()?))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::EaAttr;
+    use std::io::Cursor;
+
+    /// A `FILE_FULL_EA_INFORMATION` entry, padded to 4 bytes if it is not the last one.
+    fn ea_entry(name: &str, value: &[u8], flags: u8, is_last: bool) -> Vec<u8> {
+        let length = 8 + name.len() + 1 + value.len();
+        let next_entry_offset = if is_last { 0 } else { (length + 3) & !3 };
+
+        let mut buffer = vec![];
+        buffer.extend((next_entry_offset as u32).to_le_bytes());
+        buffer.push(flags);
+        buffer.push(name.len() as u8);
+        buffer.extend((value.len() as u16).to_le_bytes());
+        buffer.extend(name.as_bytes());
+        buffer.push(0);
+        buffer.extend(value);
+        buffer.resize(length.max(next_entry_offset), 0);
+        buffer
+    }
+
+    #[test]
+    fn test_chained_extended_attributes() {
+        let mut buffer = ea_entry("$LXUID", &1000_u32.to_le_bytes(), 0, false);
+        buffer.extend(ea_entry("$LXMOD", &0o100644_u32.to_le_bytes(), 0x80, true));
+
+        let attribute = EaAttr::from_stream(&mut Cursor::new(&buffer), buffer.len()).unwrap();
+
+        assert_eq!(attribute.entries.len(), 2);
+        assert_eq!(attribute.entries[0].name, "$LXUID");
+        assert_eq!(attribute.entries[0].flags, 0);
+        assert_eq!(attribute.entries[1].name, "$LXMOD");
+        assert_eq!(attribute.entries[1].flags, 0x80);
+
+        assert_eq!(attribute.lxss_uid(), Some(1000));
+        assert_eq!(attribute.lxss_mode(), Some(0o100644));
+        assert_eq!(attribute.lxss_gid(), None);
+    }
+
+    #[test]
+    fn test_empty_extended_attributes() {
+        let attribute = EaAttr::from_stream(&mut Cursor::new(&[]), 0).unwrap();
+
+        assert!(attribute.entries.is_empty());
+    }
+}
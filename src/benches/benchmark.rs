@@ -2,8 +2,10 @@
 extern crate criterion;
 extern crate mft;
 
-use criterion::Criterion;
-use mft::{MftEntry, MftParser, ReadSeek};
+use criterion::{black_box, BatchSize, Criterion};
+use mft::{MftEntry, MftParser};
+use std::io::{Read, Seek};
+use std::num::NonZeroUsize;
 
 fn process_1000_mft_records(sample: &[u8]) {
     let mut parser = MftParser::from_buffer(sample.to_vec()).unwrap();
@@ -14,11 +16,53 @@ fn process_1000_mft_records(sample: &[u8]) {
             count += 1;
         }
     }
+
+    black_box(count);
+}
+
+fn process_1000_mft_records_by_ref(sample: &[u8]) {
+    let mut parser = MftParser::from_buffer(sample.to_vec()).unwrap();
+
+    let mut count = 0;
+    for i in 0..1000 {
+        if let Ok(entry) = parser.get_entry_ref(i) {
+            for _attr in entry.iter_attributes() {
+                count += 1;
+            }
+        }
+    }
+
+    black_box(count);
+}
+
+fn timeline_attributes_of_1000_mft_records(sample: &[u8]) {
+    let mut parser = MftParser::from_buffer(sample.to_vec()).unwrap();
+
+    let mut count = 0;
+    for entry in parser.iter_entries().take(1000).filter_map(|a| a.ok()) {
+        let (standard_info, file_names) = entry.timeline_attributes();
+        count += usize::from(standard_info.is_some()) + file_names.len();
+    }
+
+    black_box(count);
 }
 
-fn get_full_path(parser: &mut MftParser<impl ReadSeek>, entries: &[MftEntry]) {
+fn read_all_mft_records(sample: &[u8]) {
+    let mut parser = MftParser::from_buffer(sample.to_vec()).unwrap();
+
+    black_box(parser.iter_entries().filter_map(|a| a.ok()).count());
+}
+
+#[cfg(feature = "rayon")]
+fn read_all_mft_records_in_parallel(sample: &[u8]) {
+    let mut parser = MftParser::from_buffer(sample.to_vec()).unwrap();
+
+    black_box(parser.par_iter_entries().filter_map(|a| a.ok()).count());
+}
+
+fn get_full_path(parser: &mut MftParser<impl Read + Seek>, entries: &[MftEntry]) {
     for entry in entries {
-        parser.get_full_path_for_entry(&entry).unwrap();
+        parser.get_full_path_for_entry(entry).unwrap();
     }
 }
 
@@ -30,14 +74,61 @@ fn criterion_benchmark(c: &mut Criterion) {
         b.iter(|| process_1000_mft_records(sample))
     });
 
-    c.bench_function("get_full_path", move |b| {
-        let mut parser = MftParser::from_buffer(sample.to_vec()).unwrap();
+    c.bench_function("read 1000 records (get_entry_ref)", move |b| {
+        b.iter(|| process_1000_mft_records_by_ref(sample))
+    });
 
-        let entries: Vec<MftEntry> = parser
-            .iter_entries()
-            .take(10000)
-            .filter_map(Result::ok)
-            .collect();
+    c.bench_function("timeline attributes of 1000 records", move |b| {
+        b.iter(|| timeline_attributes_of_1000_mft_records(sample))
+    });
+
+    c.bench_function("read all records", move |b| {
+        b.iter(|| read_all_mft_records(sample))
+    });
+
+    #[cfg(feature = "rayon")]
+    c.bench_function("read all records (par_iter_entries)", move |b| {
+        b.iter(|| read_all_mft_records_in_parallel(sample))
+    });
+
+    let entries: Vec<MftEntry> = MftParser::from_buffer(sample.to_vec())
+        .unwrap()
+        .iter_entries()
+        .take(10000)
+        .filter_map(Result::ok)
+        .collect();
+
+    // Every iteration resolves the paths with a new parser, so all the parents are read from the MFT.
+    c.bench_function("get_full_path (cold cache)", |b| {
+        b.iter_batched_ref(
+            || MftParser::from_buffer(sample.to_vec()).unwrap(),
+            |parser| get_full_path(parser, &entries),
+            BatchSize::LargeInput,
+        )
+    });
+
+    // A larger cache trades memory for fewer re-parses of the parents.
+    for cache_size in [10, 1000, 100_000] {
+        c.bench_function(
+            &format!("get_full_path (cold cache, cache size {})", cache_size),
+            |b| {
+                b.iter_batched_ref(
+                    || {
+                        MftParser::from_buffer(sample.to_vec())
+                            .unwrap()
+                            .with_cache_size(NonZeroUsize::new(cache_size).unwrap())
+                    },
+                    |parser| get_full_path(parser, &entries),
+                    BatchSize::LargeInput,
+                )
+            },
+        );
+    }
+
+    // The paths of the parents are resolved once before measuring, so they are (mostly) found in the cache.
+    c.bench_function("get_full_path (warm cache)", |b| {
+        let mut parser = MftParser::from_buffer(sample.to_vec()).unwrap();
+        get_full_path(&mut parser, &entries);
 
         b.iter(|| get_full_path(&mut parser, &entries))
     });
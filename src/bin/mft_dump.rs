@@ -2,30 +2,57 @@ use clap::{Arg, ArgAction, ArgMatches};
 use indoc::indoc;
 use log::Level;
 
-use mft::attribute::MftAttributeType;
+use mft::attribute::data_run::RunType;
+use mft::attribute::header::{MftAttributeHeader, ResidentialHeader};
+use mft::attribute::x30::FileNamespace;
+use mft::attribute::{MftAttributeContent, MftAttributeType};
 use mft::mft::MftParser;
-use mft::MftEntry;
+use mft::{EntryHeader, MftAttribute, MftEntry};
 
 use dialoguer::Confirm;
+#[cfg(feature = "parquet")]
+use mft::arrow::FlatMftEntryParquetWriter;
+#[cfg(feature = "arrow")]
+use mft::arrow::FlatMftEntryStreamWriter;
 use mft::csv::FlatMftEntryWithName;
+use mft::err::Error as MftError;
 
 use anyhow::{anyhow, Context, Error, Result};
+use serde::ser::{Error as _, Impossible, SerializeStruct};
+use serde::{Serialize, Serializer};
 use std::fs::File;
 use std::io::{Read, Seek, Write};
 use std::path::{Path, PathBuf};
 
 use mft::entry::ZERO_HEADER;
-use std::fmt::Write as FmtWrite;
+use sha1::{Digest, Sha1};
+use std::borrow::Cow;
+use std::cmp::Reverse;
+use std::collections::{BTreeMap, BinaryHeap};
+use std::ffi::OsString;
+use std::fmt;
 use std::ops::RangeInclusive;
 use std::str::FromStr;
-use std::{fs, io, path};
+use std::time::{Duration, Instant};
+use std::{fs, io, path, process};
 
 #[derive(Debug, PartialOrd, PartialEq)]
 #[allow(clippy::upper_case_acronyms)]
 enum OutputFormat {
     JSON,
     JSONL,
+    /// A single JSON array of all the entries.
+    JSONArray,
     CSV,
+    Tree,
+    Paths,
+    Arrow,
+    /// The bodyfile format of The Sleuth Kit, for building a timeline with `mactime`.
+    Bodyfile,
+    /// An `<Mft>` element holding an `<Entry>` element per entry.
+    XML,
+    /// The CSV columns as a Parquet file, which can only be written to a file.
+    Parquet,
 }
 
 impl OutputFormat {
@@ -33,7 +60,63 @@ impl OutputFormat {
         match s {
             "json" => Some(OutputFormat::JSON),
             "jsonl" => Some(OutputFormat::JSONL),
+            "json-array" => Some(OutputFormat::JSONArray),
             "csv" => Some(OutputFormat::CSV),
+            "tree" => Some(OutputFormat::Tree),
+            "paths" => Some(OutputFormat::Paths),
+            "arrow" => Some(OutputFormat::Arrow),
+            "bodyfile" => Some(OutputFormat::Bodyfile),
+            "xml" => Some(OutputFormat::XML),
+            "parquet" => Some(OutputFormat::Parquet),
+            _ => None,
+        }
+    }
+}
+
+/// How record numbers are rendered in CSV and JSON output.
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum RecordNumberFormat {
+    Decimal,
+    /// Hexadecimal, with a `0x` prefix.
+    Hex,
+    /// Decimal, zero-padded to the number of digits of the last record number, so it sorts lexically.
+    Padded,
+}
+
+impl RecordNumberFormat {
+    pub fn from_str(s: &str) -> Option<Self> {
+        match s {
+            "dec" => Some(RecordNumberFormat::Decimal),
+            "hex" => Some(RecordNumberFormat::Hex),
+            "padded" => Some(RecordNumberFormat::Padded),
+            _ => None,
+        }
+    }
+
+    pub fn format(&self, record_number: u64, width: usize) -> String {
+        match self {
+            RecordNumberFormat::Decimal => record_number.to_string(),
+            RecordNumberFormat::Hex => format!("{:#x}", record_number),
+            RecordNumberFormat::Padded => format!("{:0width$}", record_number, width = width),
+        }
+    }
+}
+
+/// Which attribute the timestamps of bodyfile lines are taken from.
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum TimestampSource {
+    StandardInformation,
+    FileName,
+    /// A line for each attribute, with the `$FILE_NAME` lines annotated so they can be told apart.
+    Both,
+}
+
+impl TimestampSource {
+    pub fn from_str(s: &str) -> Option<Self> {
+        match s {
+            "si" => Some(TimestampSource::StandardInformation),
+            "fn" => Some(TimestampSource::FileName),
+            "both" => Some(TimestampSource::Both),
             _ => None,
         }
     }
@@ -74,9 +157,39 @@ impl FromStr for Ranges {
     }
 }
 
+/// A glob which full paths are matched against, with `\\` and `/` treated as the same separator.
+struct PathFilter {
+    pattern: glob::Pattern,
+    options: glob::MatchOptions,
+}
+
+impl PathFilter {
+    pub fn new(pattern: &str, case_sensitive: bool) -> Result<Self> {
+        let pattern = glob::Pattern::new(&pattern.replace('\\', "/"))
+            .with_context(|| format!("Invalid path filter `{}`", pattern))?;
+
+        Ok(PathFilter {
+            pattern,
+            options: glob::MatchOptions {
+                case_sensitive,
+                require_literal_separator: false,
+                require_literal_leading_dot: false,
+            },
+        })
+    }
+
+    pub fn matches(&self, path: &Path) -> bool {
+        self.pattern
+            .matches_with(&path.to_string_lossy().replace('\\', "/"), self.options)
+    }
+}
+
 #[cfg(test)]
 mod tests {
-    use super::Ranges;
+    use super::{
+        data_stream_file_name, shortened_path, PathFilter, Ranges, MAX_STREAM_PATH_LENGTH,
+    };
+    use std::path::Path;
     use std::str::FromStr;
 
     #[test]
@@ -126,16 +239,284 @@ mod tests {
         let ranges = Ranges::from_str("1-5-8");
         assert!(ranges.is_err())
     }
+
+    #[test]
+    fn path_filter_ignores_case_by_default() {
+        let filter = PathFilter::new("windows\\system32\\*.DLL", false).unwrap();
+        assert!(filter.matches(Path::new("Windows/System32/kernel32.dll")));
+        assert!(!filter.matches(Path::new("Windows/notepad.exe")));
+    }
+
+    #[test]
+    fn path_filter_can_be_case_sensitive() {
+        let filter = PathFilter::new("Windows/*", true).unwrap();
+        assert!(filter.matches(Path::new("Windows/notepad.exe")));
+        assert!(!filter.matches(Path::new("windows/notepad.exe")));
+    }
+
+    #[test]
+    fn path_filter_errors_on_an_invalid_glob() {
+        assert!(PathFilter::new("[", false).is_err())
+    }
+
+    #[test]
+    fn stream_file_names_keep_short_paths() {
+        assert_eq!(
+            data_stream_file_name(
+                &shortened_path("Users_a_file.txt"),
+                42,
+                1,
+                "Zone.Identifier"
+            ),
+            "Users_a_file.txt__42_1_Zone.Identifier.dontrun"
+        );
+    }
+
+    #[test]
+    fn stream_file_names_hash_long_paths() {
+        let prefix = "a_".repeat(MAX_STREAM_PATH_LENGTH);
+        let (first, second) = (
+            format!("{}first.txt", prefix),
+            format!("{}second.txt", prefix),
+        );
+
+        let name = data_stream_file_name(&shortened_path(&first), 42, 0, "");
+
+        // The SHA1 of the path, the names are the same across runs.
+        assert_eq!(name.len(), "__42_0_.dontrun".len() + 40);
+        assert_eq!(
+            name,
+            data_stream_file_name(&shortened_path(&first), 42, 0, "")
+        );
+        // Paths which only differ after the limit still get different names.
+        assert_ne!(shortened_path(&first), shortened_path(&second));
+    }
+}
+
+/// The XML representation of an `MftEntry`, where every attribute is an `<Attribute>` element.
+#[derive(Serialize)]
+#[serde(rename = "Entry")]
+struct XmlEntry<'a> {
+    header: &'a EntryHeader,
+    #[serde(rename = "Attribute")]
+    attributes: Vec<MftAttribute>,
+    valid_fixup: Option<bool>,
+}
+
+impl<'a> XmlEntry<'a> {
+    pub fn from_entry(entry: &'a MftEntry) -> Self {
+        XmlEntry {
+            header: &entry.header,
+            attributes: entry.iter_attributes().filter_map(Result::ok).collect(),
+            valid_fixup: entry.valid_fixup,
+        }
+    }
+}
+
+/// An `MftEntry` where bulky resident content is replaced with its length.
+#[derive(Serialize)]
+struct SummarizedEntry<'a> {
+    header: &'a EntryHeader,
+    attributes: Vec<SummarizedAttribute>,
+    valid_fixup: Option<bool>,
+}
+
+#[derive(Serialize)]
+struct SummarizedAttribute {
+    header: MftAttributeHeader,
+    /// Like `MftAttribute`, the type is repeated next to the (untagged) content.
+    type_name: MftAttributeType,
+    data: SummarizedContent,
+    /// Flags the volume sized `$BadClus:$Bad` sparse stream, see `MftEntry::is_badclus_bad`.
+    #[serde(skip_serializing_if = "is_false")]
+    is_badclus_bad: bool,
+}
+
+fn is_false(value: &bool) -> bool {
+    !value
+}
+
+#[derive(Serialize)]
+#[serde(untagged)]
+enum SummarizedContent {
+    Content(MftAttributeContent),
+    Omitted {
+        #[serde(rename = "type")]
+        attribute_type: MftAttributeType,
+        length: u32,
+        content_omitted: bool,
+    },
+}
+
+impl<'a> SummarizedEntry<'a> {
+    pub fn from_entry(entry: &'a MftEntry) -> Self {
+        let attributes = entry
+            .iter_attributes()
+            .filter_map(Result::ok)
+            .map(|attribute| {
+                let is_bulky = matches!(
+                    attribute.data,
+                    MftAttributeContent::AttrX80(_)
+                        | MftAttributeContent::AttrX90(_)
+                        | MftAttributeContent::Raw(_)
+                );
+
+                let is_badclus_bad = entry.is_badclus_bad(&attribute);
+
+                let data = match &attribute.header.residential_header {
+                    ResidentialHeader::Resident(resident) if is_bulky => {
+                        SummarizedContent::Omitted {
+                            attribute_type: attribute.header.type_code.clone(),
+                            length: resident.data_size,
+                            content_omitted: true,
+                        }
+                    }
+                    _ => SummarizedContent::Content(attribute.data),
+                };
+
+                SummarizedAttribute {
+                    is_badclus_bad,
+                    type_name: attribute.header.type_code.clone(),
+                    header: attribute.header,
+                    data,
+                }
+            })
+            .collect();
+
+        SummarizedEntry {
+            header: &entry.header,
+            attributes,
+            valid_fixup: entry.valid_fixup,
+        }
+    }
+}
+
+/// The `$Volume` file holds the volume label and version.
+const VOLUME_ENTRY_NUMBER: u64 = 3;
+
+/// A single line of `tree` output.
+struct TreeNode {
+    path: PathBuf,
+    is_dir: bool,
+    is_deleted: bool,
+}
+
+impl fmt::Display for TreeNode {
+    /// Displays the name of the node, indented by its depth.
+    /// Directories have a trailing slash, and deleted entries are marked with `[x]`.
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let depth = self.path.components().count();
+        let name = self
+            .path
+            .file_name()
+            .unwrap_or(self.path.as_os_str())
+            .to_string_lossy();
+
+        write!(f, "{}{}", "    ".repeat(depth.saturating_sub(1)), name)?;
+
+        if self.is_dir {
+            write!(f, "/")?;
+        }
+
+        if self.is_deleted {
+            write!(f, " [x]")?;
+        }
+
+        Ok(())
+    }
+}
+
+/// Keeps track of the entries which took the longest to parse, for `--time-entries`.
+struct SlowestEntries {
+    capacity: usize,
+    // A min-heap, so the fastest of the slowest entries is the one to be evicted.
+    entries: BinaryHeap<Reverse<(Duration, u64)>>,
+}
+
+impl SlowestEntries {
+    pub fn new(capacity: usize) -> Self {
+        SlowestEntries {
+            capacity,
+            entries: BinaryHeap::with_capacity(capacity + 1),
+        }
+    }
+
+    pub fn record(&mut self, entry_id: u64, duration: Duration) {
+        self.entries.push(Reverse((duration, entry_id)));
+
+        if self.entries.len() > self.capacity {
+            self.entries.pop();
+        }
+    }
+}
+
+impl fmt::Display for SlowestEntries {
+    /// Displays the entries from the slowest to the fastest.
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        writeln!(f, "Slowest entries:")?;
+
+        // Sorting the reversed entries in ascending order puts the slowest first.
+        for Reverse((duration, entry_id)) in self.entries.clone().into_sorted_vec() {
+            writeln!(f, "    entry {}: {:?}", entry_id, duration)?;
+        }
+
+        Ok(())
+    }
+}
+
+/// A single row of `paths` output.
+#[derive(Serialize)]
+#[serde(rename_all = "PascalCase")]
+struct PathRecord {
+    record_number: u64,
+    full_path: PathBuf,
+}
+
+/// A record of a single extracted stream, written to the `--manifest` file.
+#[derive(Serialize)]
+struct ManifestRecord {
+    output_filename: String,
+    entry_id: u64,
+    sequence: u16,
+    stream_name: String,
+    size: usize,
+    full_path: String,
 }
 
 struct MftDump {
     filepath: PathBuf,
     // We use an option here to be able to move the output out of mftdump from a mutable reference.
-    output: Option<Box<dyn Write>>,
+    output: Option<Box<dyn Write + Send>>,
     data_streams_output: Option<PathBuf>,
+    // The archive which resident data streams are written to, only set with the `zip` feature.
+    data_streams_zip_output: Option<File>,
+    // The manifest is written as CSV if the file has a `.csv` extension, and as JSON otherwise.
+    manifest_output: Option<(File, OutputFormat)>,
     verbosity_level: Option<Level>,
     output_format: OutputFormat,
+    // Whether an entry was already written to the JSON array, and needs to be followed by a comma.
+    json_array_has_entries: bool,
+    // Whether JSON (and JSON array) output is written without whitespace, see `to_json_vec`.
+    compact_json: bool,
     ranges: Option<Ranges>,
+    summarize_content: bool,
+    fail_fast: bool,
+    only_nonresident_data: bool,
+    volume_info: bool,
+    filetime_raw: bool,
+    record_number_format: RecordNumberFormat,
+    // The number of digits of the last record number, for `RecordNumberFormat::Padded`.
+    record_number_width: usize,
+    // Whether CSV rows end with an `Md5` column, see `resident_data_md5`.
+    hash_resident_data: bool,
+    // Whether CSV output has a row for each alternate data stream, see `named_data_streams`.
+    emit_ads: bool,
+    timestamp_source: TimestampSource,
+    // The number of slowest entries to report, if set.
+    time_entries: Option<usize>,
+    mirror_path: Option<PathBuf>,
+    // When set, only entries with a full path matching the filter are written.
+    path_filter: Option<PathFilter>,
 }
 
 impl MftDump {
@@ -143,16 +524,58 @@ impl MftDump {
         let output_format: &String = matches.get_one("output-format").expect("has default");
         let output_target: Option<&String> = matches.get_one("output-target");
         let data_streams_target: Option<&String> = matches.get_one("data-streams-target");
+        let manifest_target: Option<&String> = matches.get_one("manifest");
         let input: &String = matches.get_one("INPUT").expect("required");
 
         let output_format =
             OutputFormat::from_str(output_format).expect("Validated with clap default values");
 
+        // Compact entries without an enclosing array would be the same as `jsonl`, so compact JSON is an array.
+        let compact_json = matches.get_flag("compact");
+        let output_format = match output_format {
+            OutputFormat::JSON if compact_json => OutputFormat::JSONArray,
+            output_format => output_format,
+        };
+
+        if output_format == OutputFormat::Arrow && !cfg!(feature = "arrow") {
+            return Err(anyhow!(
+                "Arrow output is not supported, rebuild `mft_dump` with the `arrow` feature"
+            ));
+        }
+
+        if output_format == OutputFormat::Parquet {
+            if !cfg!(feature = "parquet") {
+                return Err(anyhow!(
+                    "Parquet output is not supported, rebuild `mft_dump` with the `parquet` feature"
+                ));
+            }
+
+            // The metadata of a Parquet file is written after the data, so it is not meant to be streamed.
+            if output_target.is_none() {
+                return Err(anyhow!(
+                    "Parquet output can't be written to stdout, pass a file with `--output`"
+                ));
+            }
+        }
+
+        let data_streams_zip_target: Option<&String> = matches.get_one("data-streams-zip-target");
+        if data_streams_zip_target.is_some() && !cfg!(feature = "zip") {
+            return Err(anyhow!(
+                "Extracting resident streams to a zip archive is not supported, rebuild `mft_dump` with the `zip` feature"
+            ));
+        }
+
+        if matches.get_flag("hash") && !cfg!(feature = "hash") {
+            return Err(anyhow!(
+                "Hashing resident data is not supported, rebuild `mft_dump` with the `hash` feature"
+            ));
+        }
+
         if matches.get_flag("backtraces") {
             std::env::set_var("RUST_LIB_BACKTRACE", "1");
         }
 
-        let output: Option<Box<dyn Write>> = if let Some(path) = output_target {
+        let output: Option<Box<dyn Write + Send>> = if let Some(path) = output_target {
             match Self::create_output_file(path, !matches.get_flag("no-confirm-overwrite")) {
                 Ok(f) => Some(Box::new(f)),
                 Err(e) => {
@@ -167,6 +590,14 @@ impl MftDump {
             Some(Box::new(io::stdout()))
         };
 
+        let output = match matches.get_one::<String>("compress") {
+            Some(codec) => Some(Self::compressed_output(
+                output.expect("Output was just created"),
+                codec,
+            )?),
+            None => output,
+        };
+
         let data_streams_output = if let Some(path) = data_streams_target {
             let path = PathBuf::from(path);
             Self::create_output_dir(&path)?;
@@ -175,6 +606,36 @@ impl MftDump {
             None
         };
 
+        let data_streams_zip_output = match data_streams_zip_target {
+            Some(path) => Some(
+                Self::create_output_file(path, !matches.get_flag("no-confirm-overwrite"))
+                    .with_context(|| {
+                        format!("An error occurred while creating zip archive at `{}`", path)
+                    })?,
+            ),
+            None => None,
+        };
+
+        let manifest_output = if let Some(path) = manifest_target {
+            let format = match Path::new(path).extension() {
+                Some(extension) if extension.eq_ignore_ascii_case("csv") => OutputFormat::CSV,
+                _ => OutputFormat::JSON,
+            };
+
+            match Self::create_output_file(path, !matches.get_flag("no-confirm-overwrite")) {
+                Ok(f) => Some((f, format)),
+                Err(e) => {
+                    return Err(anyhow!(
+                        "An error occurred while creating manifest file at `{}` - `{}`",
+                        path,
+                        e
+                    ));
+                }
+            }
+        } else {
+            None
+        };
+
         let verbosity_level = match matches.get_count("verbose") {
             0 => None,
             1 => Some(Level::Info),
@@ -186,21 +647,78 @@ impl MftDump {
             }
         };
 
-        let ranges = match matches.get_one::<&String>("entry-range") {
+        let ranges = match matches.get_one::<String>("entry-range") {
             Some(range) => Some(Ranges::from_str(range)?),
             None => None,
         };
 
+        let path_filter = match matches.get_one::<String>("path-filter") {
+            Some(pattern) => Some(PathFilter::new(
+                pattern,
+                matches.get_flag("case-sensitive"),
+            )?),
+            None => None,
+        };
+
         Ok(MftDump {
             filepath: PathBuf::from(input),
             output,
             data_streams_output,
+            data_streams_zip_output,
+            manifest_output,
             verbosity_level,
             output_format,
+            json_array_has_entries: false,
+            compact_json,
             ranges,
+            summarize_content: matches.get_flag("summarize-content"),
+            fail_fast: matches.get_flag("fail-fast"),
+            only_nonresident_data: matches.get_flag("only-nonresident-data"),
+            volume_info: matches.get_flag("volume-info"),
+            filetime_raw: matches.get_flag("filetime-raw"),
+            record_number_format: RecordNumberFormat::from_str(
+                matches
+                    .get_one::<String>("record-number-format")
+                    .expect("has default"),
+            )
+            .expect("Validated with clap default values"),
+            record_number_width: 0,
+            hash_resident_data: matches.get_flag("hash"),
+            emit_ads: matches.get_flag("emit-ads"),
+            timestamp_source: TimestampSource::from_str(
+                matches
+                    .get_one::<String>("timestamp-source")
+                    .expect("has default"),
+            )
+            .expect("Validated with clap default values"),
+            time_entries: matches.get_one::<usize>("time-entries").copied(),
+            mirror_path: matches.get_one::<String>("check-mirror").map(PathBuf::from),
+            path_filter,
         })
     }
 
+    /// Wraps the output with a streaming encoder, codecs are only available if the matching feature is enabled.
+    #[cfg_attr(not(any(feature = "gzip", feature = "zstd")), allow(unused_variables))]
+    fn compressed_output(
+        output: Box<dyn Write + Send>,
+        codec: &str,
+    ) -> Result<Box<dyn Write + Send>> {
+        match codec {
+            #[cfg(feature = "gzip")]
+            "gzip" => Ok(Box::new(flate2::write::GzEncoder::new(
+                output,
+                flate2::Compression::default(),
+            ))),
+            #[cfg(feature = "zstd")]
+            "zstd" => Ok(Box::new(zstd::Encoder::new(output, 0)?.auto_finish())),
+            _ => Err(anyhow!(
+                "Compressing output with {} is not supported, rebuild `mft_dump` with the `{}` feature",
+                codec,
+                codec
+            )),
+        }
+    }
+
     fn create_output_dir(path: impl AsRef<Path>) -> Result<()> {
         let p = path.as_ref();
 
@@ -272,7 +790,23 @@ impl MftDump {
     pub fn run(&mut self) -> Result<()> {
         self.try_to_initialize_logging();
 
-        let mut parser = MftParser::from_path(&self.filepath)?;
+        let mut parser = MftParser::from_path(&self.filepath)
+            .map_err(|error| match error {
+                MftError::FileTooSmall { .. }
+                | MftError::UnsupportedEntrySize { .. }
+                | MftError::InvalidEntrySignature { .. }
+                | MftError::WofCompressedMft => anyhow!(error).context(NotAnMft),
+                error => anyhow!(error),
+            })?
+            .with_fail_fast(self.fail_fast);
+
+        if self.volume_info {
+            return self.print_volume_info(&mut parser);
+        }
+
+        if let Some(mirror_path) = &self.mirror_path {
+            Self::check_mirror(&mut parser, mirror_path)?;
+        }
 
         // Since the JSON parser can do away with a &mut Write, but the csv parser needs ownership
         // of `Write`, we eagerly create the csv writer here, moving the Box<Write> out from
@@ -286,38 +820,112 @@ impl MftDump {
             _ => None,
         };
 
+        #[cfg(feature = "arrow")]
+        let mut arrow_writer = match self.output_format {
+            OutputFormat::Arrow => {
+                Some(FlatMftEntryStreamWriter::new(self.output.take().expect(
+                    "There can only be one flow accessing the output at a time",
+                ))?)
+            }
+            _ => None,
+        };
+
+        #[cfg(feature = "parquet")]
+        let mut parquet_writer = match self.output_format {
+            OutputFormat::Parquet => {
+                Some(FlatMftEntryParquetWriter::new(self.output.take().expect(
+                    "There can only be one flow accessing the output at a time",
+                ))?)
+            }
+            _ => None,
+        };
+
+        let extract_streams =
+            self.data_streams_output.is_some() || self.data_streams_zip_output.is_some();
+
+        #[cfg(feature = "zip")]
+        let mut data_streams_zip = self.data_streams_zip_output.take().map(zip::ZipWriter::new);
+
         let number_of_entries = parser.get_entry_count();
+        self.record_number_width = number_of_entries.saturating_sub(1).to_string().len();
 
         // Move ranges out of self here to avoid immutably locking self during
         // the `for i in entries` loop.
         let take_ranges = self.ranges.take();
 
+        if self.output_format == OutputFormat::Tree {
+            return self.print_tree(&mut parser, take_ranges.as_ref());
+        }
+
+        if self.output_format == OutputFormat::Paths {
+            return self.print_paths(&mut parser, take_ranges.as_ref());
+        }
+
+        if self.output_format == OutputFormat::JSONArray {
+            self.output
+                .as_mut()
+                .expect("CSV Flow cannot occur, so `Mftdump` should still Own `output`")
+                .write_all(b"[")?;
+        }
+
+        if self.output_format == OutputFormat::XML {
+            self.output
+                .as_mut()
+                .expect("CSV Flow cannot occur, so `Mftdump` should still Own `output`")
+                .write_all(b"<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n<Mft>\n")?;
+        }
+
+        let mut manifest = vec![];
+        let mut slowest_entries = self.time_entries.map(SlowestEntries::new);
+
         let entries = match take_ranges {
             Some(ref ranges) => Box::new(ranges.chain()),
             None => Box::new(0..number_of_entries as usize) as Box<dyn Iterator<Item = usize>>,
         };
 
         for i in entries {
+            let started = Instant::now();
             let entry = parser.get_entry(i as u64);
 
+            if let (Some(slowest_entries), Ok(entry)) = (slowest_entries.as_mut(), &entry) {
+                // Attributes are parsed lazily, so we parse them here to include them in the timing.
+                entry.iter_attributes().for_each(drop);
+                slowest_entries.record(i as u64, started.elapsed());
+            }
+
             let entry = match entry {
                 Ok(entry) => match &entry.header.signature {
                     ZERO_HEADER => continue,
                     _ => entry,
                 },
+                Err(error) if self.fail_fast => {
+                    return Err(anyhow!(error).context(format!("Failed to parse entry {}", i)));
+                }
                 Err(error) => {
                     eprintln!("{}", error);
                     continue;
                 }
             };
 
-            if let Some(data_streams_dir) = &self.data_streams_output {
+            if self.only_nonresident_data && !has_nonresident_data(&entry) {
+                continue;
+            }
+
+            if let Some(path_filter) = &self.path_filter {
+                match parser.get_full_path_for_entry(&entry) {
+                    Ok(Some(path)) if path_filter.matches(&path) => {}
+                    _ => continue,
+                }
+            }
+
+            if extract_streams {
                 if let Ok(Some(path)) = parser.get_full_path_for_entry(&entry) {
                     let sanitized_path = sanitized(&path.to_string_lossy());
 
                     for (i, (name, stream)) in entry
                         .iter_attributes()
                         .filter_map(|a| a.ok())
+                        .filter(|a| !entry.is_badclus_bad(a))
                         .filter_map(|a| {
                             if a.header.type_code == MftAttributeType::DATA {
                                 // resident
@@ -329,40 +937,60 @@ impl MftDump {
                         })
                         .enumerate()
                     {
-                        let orig_path_component: String = data_streams_dir
-                            .join(&sanitized_path)
+                        // Archives have no path length limits, so the path is kept whole.
+                        #[cfg(feature = "zip")]
+                        if let Some(zip_writer) = data_streams_zip.as_mut() {
+                            let zip_entry_name = data_stream_file_name(
+                                &sanitized_path,
+                                entry.header.record_number,
+                                i,
+                                &name,
+                            );
+
+                            zip_writer.start_file(
+                                zip_entry_name,
+                                zip::write::SimpleFileOptions::default(),
+                            )?;
+                            zip_writer.write_all(stream.data())?;
+                        }
+
+                        let data_streams_dir = match &self.data_streams_output {
+                            Some(data_streams_dir) => data_streams_dir,
+                            None => continue,
+                        };
+
+                        let data_stream_path = data_streams_dir
+                            .join(data_stream_file_name(
+                                &shortened_path(&sanitized_path),
+                                entry.header.record_number,
+                                i,
+                                &name,
+                            ))
                             .to_string_lossy()
                             .to_string();
 
-                        // Add some random bits to prevent collisions
-                        let random: [u8; 6] = rand::random();
-                        let rando_string: String = to_hex_string(&random);
-
-                        let truncated: String = orig_path_component.chars().take(150).collect();
-                        let data_stream_path = format!(
-                            "{path}__{random}_{stream_number}_{stream_name}.dontrun",
-                            path = truncated,
-                            random = rando_string,
-                            stream_number = i,
-                            stream_name = name
-                        );
-
-                        if PathBuf::from(&data_stream_path).exists() {
-                            return Err(anyhow!(
-                                "Tried to override an existing stream {} already exists!\
-                                 This is a bug, please report to github!",
-                                data_stream_path
-                            ));
-                        }
-
+                        // The names are unique, so an existing file is left over from a previous extraction.
                         let mut f = File::create(&data_stream_path)?;
                         f.write_all(stream.data())?;
+
+                        if self.manifest_output.is_some() {
+                            manifest.push(ManifestRecord {
+                                output_filename: data_stream_path,
+                                entry_id: entry.header.record_number,
+                                sequence: entry.header.sequence,
+                                stream_name: name,
+                                size: stream.data().len(),
+                                full_path: path.to_string_lossy().to_string(),
+                            });
+                        }
                     }
                 }
             }
 
             match self.output_format {
-                OutputFormat::JSON | OutputFormat::JSONL => self.print_json_entry(&entry)?,
+                OutputFormat::JSON | OutputFormat::JSONL | OutputFormat::JSONArray => {
+                    self.print_json_entry(&entry)?
+                }
                 OutputFormat::CSV => self.print_csv_entry(
                     &entry,
                     &mut parser,
@@ -370,7 +998,95 @@ impl MftDump {
                         .as_mut()
                         .expect("CSV Writer is for OutputFormat::CSV"),
                 )?,
+                OutputFormat::Tree => unreachable!("Tree output is handled by `print_tree`"),
+                OutputFormat::Paths => unreachable!("Paths output is handled by `print_paths`"),
+                #[cfg(feature = "arrow")]
+                OutputFormat::Arrow => arrow_writer
+                    .as_mut()
+                    .expect("Arrow Writer is for OutputFormat::Arrow")
+                    .write(FlatMftEntryWithName::from_entry(&entry, &mut parser))?,
+                #[cfg(not(feature = "arrow"))]
+                OutputFormat::Arrow => unreachable!("Arrow output requires the `arrow` feature"),
+                OutputFormat::Bodyfile => self.print_bodyfile_entry(&entry, &mut parser)?,
+                OutputFormat::XML => self.print_xml_entry(&entry)?,
+                #[cfg(feature = "parquet")]
+                OutputFormat::Parquet => parquet_writer
+                    .as_mut()
+                    .expect("Parquet Writer is for OutputFormat::Parquet")
+                    .write(FlatMftEntryWithName::from_entry(&entry, &mut parser))?,
+                #[cfg(not(feature = "parquet"))]
+                OutputFormat::Parquet => {
+                    unreachable!("Parquet output requires the `parquet` feature")
+                }
+            }
+        }
+
+        if self.output_format == OutputFormat::XML {
+            self.output
+                .as_mut()
+                .expect("CSV Flow cannot occur, so `Mftdump` should still Own `output`")
+                .write_all(b"</Mft>\n")?;
+        }
+
+        if self.output_format == OutputFormat::JSONArray {
+            self.output
+                .as_mut()
+                .expect("CSV Flow cannot occur, so `Mftdump` should still Own `output`")
+                .write_all(if self.json_array_has_entries && !self.compact_json {
+                    b"\n]\n"
+                } else {
+                    b"]\n"
+                })?;
+        }
+
+        #[cfg(feature = "arrow")]
+        if let Some(arrow_writer) = arrow_writer {
+            arrow_writer.finish()?.flush()?;
+        }
+
+        #[cfg(feature = "parquet")]
+        if let Some(parquet_writer) = parquet_writer {
+            parquet_writer.finish()?.flush()?;
+        }
+
+        #[cfg(feature = "zip")]
+        if let Some(zip_writer) = data_streams_zip {
+            zip_writer.finish()?;
+        }
+
+        if let Some((f, format)) = self.manifest_output.take() {
+            Self::write_manifest(f, &format, &manifest)?;
+        }
+
+        if let Some(slowest_entries) = slowest_entries {
+            eprint!("{}", slowest_entries);
+        }
+
+        Ok(())
+    }
+
+    /// Reports the entries which differ between the MFT and the given `$MFTMirr` to stderr.
+    fn check_mirror(parser: &mut MftParser<impl Read + Seek>, mirror_path: &Path) -> Result<()> {
+        let mut mirror = MftParser::from_path(mirror_path)
+            .with_context(|| format!("Failed to parse $MFTMirr at `{}`", mirror_path.display()))?;
+
+        for entry_id in parser.mismatched_mirror_entries(&mut mirror) {
+            eprintln!("Entry {} differs from its copy in $MFTMirr", entry_id);
+        }
+
+        Ok(())
+    }
+
+    fn write_manifest(f: File, format: &OutputFormat, manifest: &[ManifestRecord]) -> Result<()> {
+        match format {
+            OutputFormat::CSV => {
+                let mut writer = csv::Writer::from_writer(f);
+                for record in manifest {
+                    writer.serialize(record)?;
+                }
+                writer.flush()?;
             }
+            _ => serde_json::to_writer_pretty(f, manifest)?,
         }
 
         Ok(())
@@ -390,47 +1106,725 @@ impl MftDump {
     }
 
     pub fn print_json_entry(&mut self, entry: &MftEntry) -> Result<()> {
+        let json_str =
+            if self.filetime_raw || self.record_number_format != RecordNumberFormat::Decimal {
+                let mut value = if self.summarize_content {
+                    serde_json::to_value(SummarizedEntry::from_entry(entry))?
+                } else {
+                    serde_json::to_value(entry)?
+                };
+
+                if self.filetime_raw {
+                    add_raw_filetimes(&mut value, entry);
+                }
+
+                if self.record_number_format != RecordNumberFormat::Decimal {
+                    value["header"]["record_number"] = serde_json::Value::from(
+                        self.record_number_format
+                            .format(entry.header.record_number, self.record_number_width),
+                    );
+                }
+
+                self.to_json_vec(&value)
+            } else if self.summarize_content {
+                self.to_json_vec(&SummarizedEntry::from_entry(entry))
+            } else {
+                self.to_json_vec(entry)
+            };
+
         let out = self
             .output
             .as_mut()
             .expect("CSV Flow cannot occur, so `Mftdump` should still Own `output`");
 
-        let json_str = if self.output_format == OutputFormat::JSON {
-            serde_json::to_vec_pretty(&entry).expect("It should be valid UTF-8")
+        if self.output_format == OutputFormat::JSONArray {
+            // Entries are separated by commas, the closing bracket is written at the end of `run`.
+            out.write_all(match (self.json_array_has_entries, self.compact_json) {
+                (true, true) => b",",
+                (true, false) => b",\n",
+                (false, true) => b"",
+                (false, false) => b"\n",
+            })?;
+            out.write_all(&json_str)?;
+            self.json_array_has_entries = true;
+        } else {
+            out.write_all(&json_str)?;
+            out.write_all(b"\n")?;
+        }
+
+        Ok(())
+    }
+
+    /// Writes a single `<Entry>` element, the enclosing `<Mft>` element is written by `run`,
+    /// so entries are streamed without building the whole document.
+    pub fn print_xml_entry(&mut self, entry: &MftEntry) -> Result<()> {
+        let mut xml = String::new();
+        quick_xml::se::to_writer(&mut xml, &XmlEntry::from_entry(entry))?;
+        xml.push('\n');
+
+        self.output
+            .as_mut()
+            .expect("CSV Flow cannot occur, so `Mftdump` should still Own `output`")
+            .write_all(xml.as_bytes())?;
+
+        Ok(())
+    }
+
+    fn to_json_vec(&self, value: &impl Serialize) -> Vec<u8> {
+        if matches!(
+            self.output_format,
+            OutputFormat::JSON | OutputFormat::JSONArray
+        ) && !self.compact_json
+        {
+            serde_json::to_vec_pretty(value).expect("It should be valid UTF-8")
         } else {
-            serde_json::to_vec(&entry).expect("It should be valid UTF-8")
+            serde_json::to_vec(value).expect("It should be valid UTF-8")
+        }
+    }
+
+    /// Prints the volume label, NTFS version and volume flags from the `$Volume` entry.
+    pub fn print_volume_info(&mut self, parser: &mut MftParser<impl Read + Seek>) -> Result<()> {
+        let volume = parser
+            .get_entry(VOLUME_ENTRY_NUMBER)
+            .context("Failed to read the $Volume entry")?;
+
+        let mut label = None;
+        let mut version = None;
+
+        for attribute in volume.iter_attributes().filter_map(|a| a.ok()) {
+            match attribute.data {
+                MftAttributeContent::AttrX60(volume_name) => label = Some(volume_name.name),
+                MftAttributeContent::AttrX70(information) => version = Some(information),
+                _ => {}
+            }
+        }
+
+        let out = self
+            .output
+            .as_mut()
+            .expect("Volume info flow does not take output");
+
+        writeln!(out, "Volume label: {}", label.unwrap_or_default())?;
+        match version {
+            Some(information) => {
+                writeln!(
+                    out,
+                    "NTFS version: {}.{}",
+                    information.major_version, information.minor_version
+                )?;
+                writeln!(out, "Volume flags: {:#06x}", information.flags.bits())?;
+            }
+            None => writeln!(out, "NTFS version: unknown")?,
+        }
+
+        Ok(())
+    }
+
+    /// Writes the record number and full path of each entry as CSV, without serializing the attributes.
+    pub fn print_paths(
+        &mut self,
+        parser: &mut MftParser<impl Read + Seek>,
+        ranges: Option<&Ranges>,
+    ) -> Result<()> {
+        let mut writer = csv::Writer::from_writer(
+            self.output
+                .as_mut()
+                .expect("Paths flow does not take output"),
+        );
+
+        let entries: Box<dyn Iterator<Item = mft::err::Result<(MftEntry, Option<PathBuf>)>>> =
+            match ranges {
+                Some(ranges) => Box::new(ranges.chain().filter_map(|i| {
+                    let entry = match parser.get_entry(i as u64) {
+                        Ok(entry) if &entry.header.signature == ZERO_HEADER => return None,
+                        Ok(entry) => entry,
+                        Err(e) => return Some(Err(e)),
+                    };
+
+                    Some(
+                        parser
+                            .get_full_path_for_entry(&entry)
+                            .map(|path| (entry, path)),
+                    )
+                })),
+                None => Box::new(parser.iter_entries_with_paths()),
+            };
+
+        for result in entries {
+            match result {
+                Ok((_, path))
+                    if !passes_path_filter(self.path_filter.as_ref(), path.as_deref()) =>
+                {
+                    continue
+                }
+                Ok((entry, path)) => writer.serialize(PathRecord {
+                    record_number: entry.header.record_number,
+                    full_path: path.unwrap_or_default(),
+                })?,
+                Err(error) if self.fail_fast => return Err(anyhow!(error)),
+                Err(error) => eprintln!("{}", error),
+            }
+        }
+
+        writer.flush()?;
+
+        Ok(())
+    }
+
+    /// Prints the resolved paths of the entries as an indented tree, sorted by path.
+    ///
+    /// To bound memory usage, only a single top-level directory is buffered (and sorted) at a time.
+    /// The first pass over the entries only keeps the record numbers under each top-level directory,
+    /// and the entries of each directory are read (and their paths resolved) again when it is printed,
+    /// so every entry is read at most twice.
+    pub fn print_tree(
+        &mut self,
+        parser: &mut MftParser<impl Read + Seek>,
+        ranges: Option<&Ranges>,
+    ) -> Result<()> {
+        let number_of_entries = parser.get_entry_count() as usize;
+        let entry_numbers = match ranges {
+            Some(ranges) => Box::new(ranges.chain()) as Box<dyn Iterator<Item = usize>>,
+            None => Box::new(0..number_of_entries),
         };
 
-        out.write_all(&json_str)?;
-        out.write_all(b"\n")?;
+        // The first pass collects the entries directly under the root, which are the top of each subtree,
+        // and the record numbers of the entries inside each subtree.
+        let mut top_level: BTreeMap<OsString, (Vec<TreeNode>, Vec<usize>)> = BTreeMap::new();
+        self.for_each_tree_node(parser, entry_numbers, true, |record_number, node| {
+            let mut components = node.path.components();
+            let name = components
+                .next()
+                .expect("Paths of tree nodes are not empty")
+                .as_os_str()
+                .to_os_string();
+
+            let (top_level_nodes, subtree_records) = top_level.entry(name).or_default();
+            if components.next().is_none() {
+                top_level_nodes.push(node);
+            } else {
+                subtree_records.push(record_number);
+            }
+        })?;
+
+        for (name, (nodes, subtree_records)) in top_level {
+            let out = self
+                .output
+                .as_mut()
+                .expect("Tree flow does not take output");
+
+            // Sentinels such as `[Orphaned]` have no entry of their own.
+            if nodes.is_empty() {
+                writeln!(out, "{}/", name.to_string_lossy())?;
+            }
+
+            for node in &nodes {
+                writeln!(out, "{}", node)?;
+            }
+
+            let mut subtree = Vec::with_capacity(subtree_records.len());
+            self.for_each_tree_node(parser, subtree_records.into_iter(), false, |_, node| {
+                subtree.push(node);
+            })?;
+
+            subtree.sort_by(|a, b| a.path.cmp(&b.path));
+
+            let out = self
+                .output
+                .as_mut()
+                .expect("Tree flow does not take output");
+            for node in subtree {
+                writeln!(out, "{}", node)?;
+            }
+        }
 
         Ok(())
     }
 
-    pub fn print_csv_entry<W: Write>(
+    /// Calls `f` with the record number and tree node of every entry which has a resolved path.
+    /// Errors are only printed if `report_errors` is set, to avoid reporting them on every pass.
+    fn for_each_tree_node(
         &self,
+        parser: &mut MftParser<impl Read + Seek>,
+        entry_numbers: impl Iterator<Item = usize>,
+        report_errors: bool,
+        mut f: impl FnMut(usize, TreeNode),
+    ) -> Result<()> {
+        for i in entry_numbers {
+            let entry = match parser.get_entry(i as u64) {
+                Ok(entry) => entry,
+                Err(error) if self.fail_fast => {
+                    return Err(anyhow!(error).context(format!("Failed to parse entry {}", i)));
+                }
+                Err(error) => {
+                    if report_errors {
+                        eprintln!("{}", error);
+                    }
+                    continue;
+                }
+            };
+
+            // Skip empty entries, and the root directory itself.
+            if &entry.header.signature == ZERO_HEADER || entry.header.record_number == 5 {
+                continue;
+            }
+
+            if let Ok(Some(path)) = parser.get_full_path_for_entry(&entry) {
+                if !passes_path_filter(self.path_filter.as_ref(), Some(&path)) {
+                    continue;
+                }
+
+                f(
+                    i,
+                    TreeNode {
+                        path,
+                        is_dir: entry.is_dir(),
+                        is_deleted: !entry.is_allocated(),
+                    },
+                );
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Prints a bodyfile line (`MD5|name|inode|mode|UID|GID|size|atime|mtime|ctime|crtime`) for every name of the entry,
+    /// so hard links get a line each.
+    ///
+    /// The timestamps are taken from the `$STANDARD_INFORMATION` attribute, the `$FILE_NAME` attribute of each name,
+    /// or both (as separate lines), according to `--timestamp-source`.
+    pub fn print_bodyfile_entry(
+        &mut self,
+        entry: &MftEntry,
+        parser: &mut MftParser<impl Read + Seek>,
+    ) -> Result<()> {
+        let standard_info = entry
+            .iter_attributes_matching(Some(vec![MftAttributeType::StandardInformation]))
+            .filter_map(Result::ok)
+            .find_map(|a| a.data.into_standard_info());
+
+        let si_timestamps = match standard_info {
+            Some(si) => [si.accessed, si.modified, si.mft_modified, si.created]
+                .map(|timestamp| timestamp.timestamp()),
+            None => [0; 4],
+        };
+
+        let size = entry
+            .data_layout()
+            .map(|layout| layout.logical_size)
+            .unwrap_or(0);
+        let mode = if entry.is_dir() {
+            "d/drwxrwxrwx"
+        } else {
+            "r/rrwxrwxrwx"
+        };
+
+        for file_name in entry.file_names() {
+            // Short (DOS) names are aliases of the long name, so they would only duplicate its line.
+            if file_name.namespace == FileNamespace::DOS {
+                continue;
+            }
+
+            let fn_timestamps = [
+                file_name.accessed,
+                file_name.modified,
+                file_name.mft_modified,
+                file_name.created,
+            ]
+            .map(|timestamp| timestamp.timestamp());
+
+            let path = parser.get_full_path_for_file_name(entry, file_name);
+            // Like `fls`, names of deleted entries are suffixed with `(deleted)`.
+            let deleted = if entry.is_allocated() {
+                ""
+            } else {
+                " (deleted)"
+            };
+
+            let lines = match self.timestamp_source {
+                TimestampSource::StandardInformation => vec![("", si_timestamps)],
+                TimestampSource::FileName => vec![("", fn_timestamps)],
+                TimestampSource::Both => {
+                    vec![("", si_timestamps), (" ($FILE_NAME)", fn_timestamps)]
+                }
+            };
+
+            for (annotation, [atime, mtime, ctime, crtime]) in lines {
+                writeln!(
+                    self.output
+                        .as_mut()
+                        .expect("Bodyfile flow does not take output"),
+                    "0|{}{}{}|{}|{}|0|0|{}|{}|{}|{}|{}",
+                    path.display(),
+                    deleted,
+                    annotation,
+                    entry.header.record_number,
+                    mode,
+                    size,
+                    atime,
+                    mtime,
+                    ctime,
+                    crtime
+                )?;
+            }
+        }
+
+        Ok(())
+    }
+
+    pub fn print_csv_entry<W: Write>(
+        &mut self,
         entry: &MftEntry,
         parser: &mut MftParser<impl Read + Seek>,
         writer: &mut csv::Writer<W>,
     ) -> Result<()> {
-        let flat_entry = FlatMftEntryWithName::from_entry(entry, parser);
+        let mut flat_entry = FlatMftEntryWithName::from_entry(entry, parser);
+
+        if self.record_number_format == RecordNumberFormat::Decimal
+            && !self.hash_resident_data
+            && !self.emit_ads
+        {
+            writer.serialize(flat_entry)?;
+            return Ok(());
+        }
+
+        let entry_id = match self.record_number_format {
+            RecordNumberFormat::Decimal => None,
+            _ => Some(
+                self.record_number_format
+                    .format(entry.header.record_number, self.record_number_width),
+            ),
+        };
+        let md5 = self
+            .hash_resident_data
+            .then(|| resident_data_md5(entry).unwrap_or_default());
+
+        writer.serialize(CsvRow(
+            CsvEntry {
+                entry: &flat_entry,
+                entry_id: entry_id.as_deref(),
+            },
+            StreamColumns {
+                stream_name: self.emit_ads.then_some(""),
+                md5,
+            },
+        ))?;
+
+        if !self.emit_ads {
+            return Ok(());
+        }
 
-        writer.serialize(flat_entry)?;
+        // Each alternate data stream is a copy of the row, with the size (and hash) of the stream.
+        for (attribute, size) in named_data_streams(entry) {
+            flat_entry.file_size = size;
+            let md5 = self
+                .hash_resident_data
+                .then(|| resident_attribute_md5(&attribute).unwrap_or_default());
+
+            writer.serialize(CsvRow(
+                CsvEntry {
+                    entry: &flat_entry,
+                    entry_id: entry_id.as_deref(),
+                },
+                StreamColumns {
+                    stream_name: Some(&attribute.header.name),
+                    md5,
+                },
+            ))?;
+        }
 
         Ok(())
     }
 }
 
-fn to_hex_string(bytes: &[u8]) -> String {
-    let len = bytes.len();
-    // Each byte is represented by 2 ascii bytes.
-    let mut s = String::with_capacity(len * 2);
+/// A CSV row, with the columns added by `--emit-ads` and `--hash` after the columns of the entry.
+#[derive(Serialize)]
+struct CsvRow<'a>(CsvEntry<'a>, StreamColumns<'a>);
+
+/// The columns of an entry, with the `EntryId` column replaced by `entry_id` if it is set.
+struct CsvEntry<'a> {
+    entry: &'a FlatMftEntryWithName,
+    entry_id: Option<&'a str>,
+}
+
+impl Serialize for CsvEntry<'_> {
+    fn serialize<S: Serializer>(&self, serializer: S) -> std::result::Result<S::Ok, S::Error> {
+        match self.entry_id {
+            Some(entry_id) => self.entry.serialize(WithEntryId {
+                inner: serializer,
+                entry_id,
+            }),
+            None => self.entry.serialize(serializer),
+        }
+    }
+}
+
+/// The columns which are only written with `--emit-ads` (`StreamName`) and `--hash` (`Md5`).
+#[derive(Serialize)]
+#[serde(rename_all = "PascalCase")]
+struct StreamColumns<'a> {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    stream_name: Option<&'a str>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    md5: Option<String>,
+}
+
+/// Serializes a struct with `inner`, writing `entry_id` in place of its `EntryId` field.
+///
+/// The `EntryId` column of `FlatMftEntryWithName` is numeric, so this is how `--record-number-format`
+/// applies to CSV rows. Only structs are supported.
+struct WithEntryId<'a, S> {
+    inner: S,
+    entry_id: &'a str,
+}
+
+macro_rules! only_structs {
+    ($($method:ident($($ty:ty),*) -> $ok:ty;)*) => {
+        $(
+            fn $method(self, $(_: $ty),*) -> std::result::Result<$ok, S::Error> {
+                Err(S::Error::custom("Only structs can be serialized with an `EntryId`"))
+            }
+        )*
+    };
+}
+
+impl<'a, S: Serializer> Serializer for WithEntryId<'a, S> {
+    type Ok = S::Ok;
+    type Error = S::Error;
+    type SerializeSeq = Impossible<S::Ok, S::Error>;
+    type SerializeTuple = Impossible<S::Ok, S::Error>;
+    type SerializeTupleStruct = Impossible<S::Ok, S::Error>;
+    type SerializeTupleVariant = Impossible<S::Ok, S::Error>;
+    type SerializeMap = Impossible<S::Ok, S::Error>;
+    type SerializeStruct = WithEntryId<'a, S::SerializeStruct>;
+    type SerializeStructVariant = Impossible<S::Ok, S::Error>;
+
+    fn serialize_struct(
+        self,
+        name: &'static str,
+        len: usize,
+    ) -> std::result::Result<Self::SerializeStruct, S::Error> {
+        Ok(WithEntryId {
+            inner: self.inner.serialize_struct(name, len)?,
+            entry_id: self.entry_id,
+        })
+    }
+
+    only_structs! {
+        serialize_bool(bool) -> S::Ok;
+        serialize_i8(i8) -> S::Ok;
+        serialize_i16(i16) -> S::Ok;
+        serialize_i32(i32) -> S::Ok;
+        serialize_i64(i64) -> S::Ok;
+        serialize_u8(u8) -> S::Ok;
+        serialize_u16(u16) -> S::Ok;
+        serialize_u32(u32) -> S::Ok;
+        serialize_u64(u64) -> S::Ok;
+        serialize_f32(f32) -> S::Ok;
+        serialize_f64(f64) -> S::Ok;
+        serialize_char(char) -> S::Ok;
+        serialize_str(&str) -> S::Ok;
+        serialize_bytes(&[u8]) -> S::Ok;
+        serialize_none() -> S::Ok;
+        serialize_unit() -> S::Ok;
+        serialize_unit_struct(&'static str) -> S::Ok;
+        serialize_unit_variant(&'static str, u32, &'static str) -> S::Ok;
+        serialize_seq(Option<usize>) -> Self::SerializeSeq;
+        serialize_tuple(usize) -> Self::SerializeTuple;
+        serialize_tuple_struct(&'static str, usize) -> Self::SerializeTupleStruct;
+        serialize_tuple_variant(&'static str, u32, &'static str, usize) -> Self::SerializeTupleVariant;
+        serialize_map(Option<usize>) -> Self::SerializeMap;
+        serialize_struct_variant(&'static str, u32, &'static str, usize) -> Self::SerializeStructVariant;
+    }
+
+    fn serialize_some<T: ?Sized + Serialize>(self, _: &T) -> std::result::Result<S::Ok, S::Error> {
+        Err(S::Error::custom(
+            "Only structs can be serialized with an `EntryId`",
+        ))
+    }
+
+    fn serialize_newtype_struct<T: ?Sized + Serialize>(
+        self,
+        _: &'static str,
+        _: &T,
+    ) -> std::result::Result<S::Ok, S::Error> {
+        Err(S::Error::custom(
+            "Only structs can be serialized with an `EntryId`",
+        ))
+    }
+
+    fn serialize_newtype_variant<T: ?Sized + Serialize>(
+        self,
+        _: &'static str,
+        _: u32,
+        _: &'static str,
+        _: &T,
+    ) -> std::result::Result<S::Ok, S::Error> {
+        Err(S::Error::custom(
+            "Only structs can be serialized with an `EntryId`",
+        ))
+    }
+}
+
+impl<S: SerializeStruct> SerializeStruct for WithEntryId<'_, S> {
+    type Ok = S::Ok;
+    type Error = S::Error;
+
+    fn serialize_field<T: ?Sized + Serialize>(
+        &mut self,
+        key: &'static str,
+        value: &T,
+    ) -> std::result::Result<(), S::Error> {
+        if key == "EntryId" {
+            self.inner.serialize_field(key, self.entry_id)
+        } else {
+            self.inner.serialize_field(key, value)
+        }
+    }
+
+    fn end(self) -> std::result::Result<S::Ok, S::Error> {
+        self.inner.end()
+    }
+}
+
+/// Adds a `<key>_filetime` field with the raw FILETIME value next to the timestamps of the
+/// `$STANDARD_INFORMATION` and `$FILE_NAME` attributes of `entry` (serialized as `value`).
+fn add_raw_filetimes(value: &mut serde_json::Value, entry: &MftEntry) {
+    // The attributes are serialized in the same order, skipping the ones which fail to parse.
+    for (index, attribute) in entry.iter_attributes().filter_map(Result::ok).enumerate() {
+        let raw_timestamps = match attribute.data {
+            MftAttributeContent::AttrX10(standard_info) => standard_info.raw_timestamps,
+            MftAttributeContent::AttrX30(file_name) => file_name.raw_timestamps,
+            _ => continue,
+        };
 
-    for byte in bytes {
-        write!(s, "{:02X}", byte).expect("Writing to an allocated string cannot fail");
+        let data = value
+            .get_mut("attributes")
+            .and_then(|attributes| attributes.get_mut(index))
+            .and_then(|attribute| attribute.get_mut("data"))
+            .and_then(serde_json::Value::as_object_mut);
+
+        if let Some(data) = data {
+            for (key, filetime) in [
+                ("created_filetime", raw_timestamps.created),
+                ("modified_filetime", raw_timestamps.modified),
+                ("mft_modified_filetime", raw_timestamps.mft_modified),
+                ("accessed_filetime", raw_timestamps.accessed),
+            ] {
+                data.insert(key.to_string(), serde_json::Value::from(filetime));
+            }
+        }
     }
+}
 
-    s
+/// Whether an entry with the given full path passes `--path-filter`,
+/// entries without a path only pass when there is no filter.
+fn passes_path_filter(path_filter: Option<&PathFilter>, path: Option<&Path>) -> bool {
+    match (path_filter, path) {
+        (None, _) => true,
+        (Some(path_filter), Some(path)) => path_filter.matches(path),
+        (Some(_), None) => false,
+    }
+}
+
+/// Returns true if the entry is a file with a non-resident $DATA attribute,
+/// which has at least one run backed by clusters (not sparse).
+/// The `$BadClus:$Bad` stream is not counted, since its runs are the bad clusters of the volume.
+fn has_nonresident_data(entry: &MftEntry) -> bool {
+    if entry.is_dir() {
+        return false;
+    }
+
+    entry
+        .iter_attributes_matching(Some(vec![MftAttributeType::DATA]))
+        .filter_map(|a| a.ok())
+        .filter(|a| !entry.is_badclus_bad(a))
+        .filter_map(|a| a.data.into_data_runs())
+        .any(|data| {
+            data.data_runs
+                .iter()
+                .any(|run| run.run_type == RunType::Standard)
+        })
+}
+
+/// The named $DATA attributes (alternate data streams) of the entry, with the size of each stream.
+///
+/// The size comes from the residential header, a non-resident stream split across several attributes
+/// only has a size in its first attribute, so the others are skipped.
+fn named_data_streams(entry: &MftEntry) -> Vec<(MftAttribute, u64)> {
+    entry
+        .iter_attributes_matching(Some(vec![MftAttributeType::DATA]))
+        .filter_map(Result::ok)
+        .filter(|attribute| !attribute.header.name.is_empty())
+        .filter_map(|attribute| {
+            let size = match &attribute.header.residential_header {
+                ResidentialHeader::Resident(resident) => u64::from(resident.data_size),
+                ResidentialHeader::NonResident(non_resident) => non_resident.logical_size()?,
+            };
+            Some((attribute, size))
+        })
+        .collect()
+}
+
+/// The MD5 of the content of the first $DATA attribute of the entry, if it is resident.
+fn resident_data_md5(entry: &MftEntry) -> Option<String> {
+    let attribute = entry
+        .iter_attributes_matching(Some(vec![MftAttributeType::DATA]))
+        .filter_map(Result::ok)
+        .next()?;
+
+    resident_attribute_md5(&attribute)
+}
+
+/// The MD5 of the content of a $DATA attribute, if it is resident.
+///
+/// Only resident data can be hashed, since non-resident data is stored in clusters outside of the MFT.
+#[cfg(feature = "hash")]
+fn resident_attribute_md5(attribute: &MftAttribute) -> Option<String> {
+    use md5::{Digest, Md5};
+
+    let data = attribute.data.clone().into_data()?;
+
+    Some(format!("{:x}", Md5::digest(data.data())))
+}
+
+#[cfg(not(feature = "hash"))]
+fn resident_attribute_md5(_attribute: &MftAttribute) -> Option<String> {
+    None
+}
+
+/// Paths longer than this (in characters) are replaced by their SHA1 in the names of extracted streams,
+/// so the names stay within the file name length limits of file systems.
+const MAX_STREAM_PATH_LENGTH: usize = 150;
+
+/// The name of an extracted resident stream - `{path}__{entry_id}_{stream_number}_{stream_name}.dontrun`.
+///
+/// The record number makes the names unique (deleted entries may share the path of another entry),
+/// without any randomness, so extracting the same MFT twice gives the same names.
+fn data_stream_file_name(
+    path: &str,
+    entry_id: u64,
+    stream_number: usize,
+    stream_name: &str,
+) -> String {
+    format!(
+        "{path}__{entry_id}_{stream_number}_{stream_name}.dontrun",
+        path = path,
+        entry_id = entry_id,
+        stream_number = stream_number,
+        stream_name = stream_name
+    )
+}
+
+/// Replaces a sanitized path longer than `MAX_STREAM_PATH_LENGTH` with the SHA1 of the path, as lowercase hex.
+///
+/// Unlike truncation, paths which only differ after the limit still get different names.
+fn shortened_path(sanitized_path: &str) -> Cow<'_, str> {
+    if sanitized_path.chars().count() <= MAX_STREAM_PATH_LENGTH {
+        return Cow::Borrowed(sanitized_path);
+    }
+
+    Cow::Owned(format!("{:x}", Sha1::digest(sanitized_path.as_bytes())))
 }
 
 // adapter from python version
@@ -447,7 +1841,32 @@ pub fn sanitized(component: &str) -> String {
     buf
 }
 
-fn main() -> Result<()> {
+/// The exit code used when the input is not a recognizable MFT, other failures (such as I/O errors) exit with 1.
+const EXIT_CODE_NOT_AN_MFT: i32 = 2;
+
+/// The context of errors which show the input is not an MFT (or one which cannot be parsed, like a WOF compressed one).
+///
+/// Only errors of creating the parser (which reads entry 0) get it,
+/// so a corrupted entry of a valid MFT is not mistaken for a wrong input file.
+#[derive(Debug)]
+struct NotAnMft;
+
+impl fmt::Display for NotAnMft {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "The input is not a supported MFT")
+    }
+}
+
+/// Maps an error to an exit code, so scripts can tell a wrong input file from a failure to read it.
+fn exit_code(error: &Error) -> i32 {
+    if error.downcast_ref::<NotAnMft>().is_some() {
+        EXIT_CODE_NOT_AN_MFT
+    } else {
+        1
+    }
+}
+
+fn main() {
     let matches = clap::Command::new("MFT Parser")
         .version(env!("CARGO_PKG_VERSION"))
         .author("Omer B. <omerbenamram@gmail.com>")
@@ -457,10 +1876,25 @@ fn main() -> Result<()> {
             Arg::new("output-format")
                 .short('o')
                 .long("output-format")
+                .visible_alias("format")
                 .action(ArgAction::Set)
-                .value_parser(clap::builder::PossibleValuesParser::new(["csv", "json", "jsonl"]))
+                .value_parser(clap::builder::PossibleValuesParser::new(["csv", "json", "jsonl", "json-array", "tree", "paths", "arrow", "bodyfile", "xml", "parquet"]))
                 .default_value("json")
-                .help("Output format."),
+                .help(indoc!("Output format, `json-array` prints a single JSON array of the entries,
+                       `tree` prints the resolved paths as an indented tree,
+                       `paths` prints only the record number and resolved path of each entry as CSV,
+                       `arrow` writes the CSV columns as an Arrow IPC stream (requires the `arrow` feature),
+                       `bodyfile` prints a line per file name in the bodyfile format, for use with `mactime`,
+                       `xml` prints an `<Mft>` document with an `<Entry>` element per entry,
+                       `parquet` writes the CSV columns as a Parquet file to `--output` (requires the `parquet` feature).")),
+        )
+        .arg(
+            Arg::new("compress")
+                .long("compress")
+                .action(ArgAction::Set)
+                .value_parser(clap::builder::PossibleValuesParser::new(["gzip", "zstd"]))
+                .help(indoc!("Compresses the output while writing it, requires `mft_dump` to be built
+                       with the `gzip` or `zstd` feature.")),
         )
         .arg(
             Arg::new("entry-range")
@@ -484,8 +1918,133 @@ fn main() -> Result<()> {
                 .short('e')
                 .action(ArgAction::Set)
                 .help(indoc!("Writes resident data streams to the given directory.
-                             Resident streams will be named like - `{path}__<random_bytes>_{stream_number}_{stream_name}.dontrun`
-                             random is added to prevent collisions.")),
+                             Resident streams will be named like - `{path}__{entry_id}_{stream_number}_{stream_name}.dontrun`
+                             paths longer than 150 characters are replaced with their SHA1.
+                             The names are the same across runs, existing files are overwritten.")),
+        )
+        .arg(
+            Arg::new("data-streams-zip-target")
+                .long("extract-resident-streams-zip")
+                .action(ArgAction::Set)
+                .help(indoc!("Writes resident data streams to the given zip archive (requires the `zip` feature).
+                             Streams will be named like - `{path}__{entry_id}_{stream_number}_{stream_name}.dontrun`
+                             where path is the full path of the entry, which is never truncated.")),
+        )
+        .arg(
+            Arg::new("manifest")
+                .long("manifest")
+                .action(ArgAction::Set)
+                .requires("data-streams-target")
+                .help(indoc!("Writes a manifest of the extracted resident streams to the given file,
+                       mapping each output file to its entry id, sequence, stream name, size and full path.
+                       Written as CSV if the file ends with `.csv`, and as JSON otherwise.")),
+        )
+        .arg(
+            Arg::new("compact")
+                .long("compact")
+                .action(ArgAction::SetTrue)
+                .help(indoc!("Writes `json` and `json-array` output without whitespace, as a single JSON array of the entries.
+                       `jsonl` output is always compact.")),
+        )
+        .arg(
+            Arg::new("summarize-content")
+                .long("summarize-content")
+                .action(ArgAction::SetTrue)
+                .help(indoc!("When set, bulky resident content (data streams, index roots and unparsed attributes)
+                       is replaced with its type and length in JSON output.")),
+        )
+        .arg(
+            Arg::new("volume-info")
+                .long("volume-info")
+                .action(ArgAction::SetTrue)
+                .help(indoc!("Prints the volume label, NTFS version and volume flags (from the `$Volume` entry) and exits.")),
+        )
+        .arg(
+            Arg::new("filetime-raw")
+                .long("filetime-raw")
+                .action(ArgAction::SetTrue)
+                .help(indoc!("When set, timestamps in JSON output are accompanied by their raw FILETIME values
+                       (under keys such as `created_filetime`), for exact comparison with other tools.")),
+        )
+        .arg(
+            Arg::new("record-number-format")
+                .long("record-number-format")
+                .action(ArgAction::Set)
+                .value_parser(clap::builder::PossibleValuesParser::new(["dec", "hex", "padded"]))
+                .default_value("dec")
+                .help(indoc!("How record numbers are written in CSV and JSON output, `padded` zero-pads them
+                       to the width of the last record number so they sort lexically.")),
+        )
+        .arg(
+            Arg::new("hash")
+                .long("hash")
+                .action(ArgAction::SetTrue)
+                .help(indoc!("Adds an `Md5` column to CSV output, with the MD5 of the first $DATA stream (requires the `hash` feature).
+                       Only resident data is hashed, since the content of non-resident streams is not stored in the MFT,
+                       the column is blank for those.")),
+        )
+        .arg(
+            Arg::new("emit-ads")
+                .long("emit-ads")
+                .action(ArgAction::SetTrue)
+                .help(indoc!("Adds a row to CSV output for each alternate data stream (named $DATA attribute) of an entry,
+                       after the row of the entry. The rows have a `StreamName` column, which is blank for the row of the entry,
+                       and the `FileSize` of a stream row is the size of the stream.")),
+        )
+        .arg(
+            Arg::new("timestamp-source")
+                .long("timestamp-source")
+                .action(ArgAction::Set)
+                .value_parser(clap::builder::PossibleValuesParser::new(["si", "fn", "both"]))
+                .default_value("si")
+                .help(indoc!("Which attribute the timestamps of bodyfile output are taken from, `both` prints
+                       the `$FILE_NAME` timestamps on separate lines, with `($FILE_NAME)` appended to the name.")),
+        )
+        .arg(
+            Arg::new("only-nonresident-data")
+                .long("only-nonresident-data")
+                .action(ArgAction::SetTrue)
+                .help(indoc!("When set, only outputs files which have a non-resident $DATA attribute
+                       with at least one non-sparse run, useful for finding candidates for carving.")),
+        )
+        .arg(
+            Arg::new("path-filter")
+                .long("path-filter")
+                .value_name("GLOB")
+                .action(ArgAction::Set)
+                .help(indoc!("Only outputs entries whose full path matches the given glob, for example `Windows/System32/*.dll`.
+                       Matching ignores case unless `--case-sensitive` is passed, entries without a resolvable path are skipped.")),
+        )
+        .arg(
+            Arg::new("case-sensitive")
+                .long("case-sensitive")
+                .action(ArgAction::SetTrue)
+                .requires("path-filter")
+                .help("Makes `--path-filter` case sensitive."),
+        )
+        .arg(
+            Arg::new("check-mirror")
+                .long("check-mirror")
+                .value_name("MFTMIRR")
+                .action(ArgAction::Set)
+                .help(indoc!("Compares the first records of the MFT with the given $MFTMirr before dumping,
+                       and reports the records which differ to stderr.")),
+        )
+        .arg(
+            Arg::new("time-entries")
+                .long("time-entries")
+                .value_name("N")
+                .action(ArgAction::Set)
+                .value_parser(clap::value_parser!(usize))
+                .help(indoc!("When set, measures how long each entry takes to parse,
+                       and prints the N slowest entries to stderr.")),
+        )
+        .arg(
+            Arg::new("fail-fast")
+                .long("fail-fast")
+                .action(ArgAction::SetTrue)
+                .help(indoc!("When set, stops at the first entry which fails to parse and exits with an error,
+                       instead of printing the error and moving on to the next entry.")),
         )
         .arg(
             Arg::new("no-confirm-overwrite")
@@ -510,8 +2069,12 @@ fn main() -> Result<()> {
                 .help("If set, a backtrace will be printed with some errors if available"))
         .get_matches();
 
-    let mut app = MftDump::from_cli_matches(&matches).context("Failed setting up the app")?;
-    app.run().context("A runtime error has occurred")?;
+    let result = MftDump::from_cli_matches(&matches)
+        .context("Failed setting up the app")
+        .and_then(|mut app| app.run().context("A runtime error has occurred"));
 
-    Ok(())
+    if let Err(e) = result {
+        eprintln!("Error: {:?}", e);
+        process::exit(exit_code(&e));
+    }
 }
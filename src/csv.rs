@@ -1,17 +1,26 @@
 use crate::attribute::header::ResidentialHeader;
-
+use crate::attribute::x30::{FileNameAttr, FileNamespace};
+use crate::attribute::xc0::ReparseData;
 use crate::attribute::{FileAttributeFlags, MftAttributeType};
-use crate::entry::EntryFlags;
+use crate::entry::{
+    best_name_attribute, data_size_consistency, is_suspicious_name, timestomp_indicators,
+    DataLayout, DataSizeConsistency, EntryFlags, TimestompIndicators,
+};
+use crate::mft::ParentRefStatus;
 use crate::{MftAttribute, MftEntry, MftParser};
 
-use serde::Serialize;
+use serde::{Deserialize, Serialize};
 
 use chrono::{DateTime, Utc};
 use std::io::{Read, Seek};
 use std::path::PathBuf;
+use winstructs::guid::Guid;
+
+/// The size of the 0x10 attribute since NTFS 3.0, older (48 bytes) attributes end before the owner id and usn fields.
+const STANDARD_INFO_V3_SIZE: u32 = 72;
 
-/// Used for CSV output
-#[derive(Serialize)]
+/// Used for CSV output, can also be used to read back previously exported CSV rows.
+#[derive(Serialize, Deserialize)]
 #[serde(rename_all = "PascalCase")]
 pub struct FlatMftEntryWithName {
     pub signature: String,
@@ -28,6 +37,12 @@ pub struct FlatMftEntryWithName {
     /// The size of the file, in bytes.
     pub used_entry_size: u32,
     pub total_entry_size: u32,
+    /// The offset of the first attribute, and the next attribute id to be assigned.
+    /// Inconsistencies with the actual attributes may indicate tampering.
+    pub first_attribute_record_offset: u16,
+    pub first_attribute_id: u16,
+    /// The $LogFile sequence number of the last change to the record, for correlating with $LogFile.
+    pub logfile_sequence_number: u64,
 
     /// The size of the file, if available, from the X80 attribute.
     /// Will be 0 if no $DATA attribute is found.
@@ -40,18 +55,54 @@ pub struct FlatMftEntryWithName {
 
     /// Indicates whether the record has alternate data streams.
     pub has_alternate_data_streams: bool,
+    /// Indicates whether the name of the record is a known obfuscation trick, see `MftEntry::has_suspicious_name`.
+    pub has_suspicious_name: bool,
 
     /// All of these fields are present for entries that have an 0x10 attribute.
     pub standard_info_flags: Option<FileAttributeFlags>,
     pub standard_info_last_modified: Option<DateTime<Utc>>,
     pub standard_info_last_access: Option<DateTime<Utc>>,
     pub standard_info_created: Option<DateTime<Utc>>,
+    /// The last update sequence number of the file in the USN journal,
+    /// 0 if there is no 0x10 attribute (or it predates NTFS 3.0).
+    pub usn: u64,
     /// All of these fields are present for entries that have an 0x30 attribute.
     pub file_name_flags: Option<FileAttributeFlags>,
     pub file_name_last_modified: Option<DateTime<Utc>>,
     pub file_name_last_access: Option<DateTime<Utc>>,
     pub file_name_created: Option<DateTime<Utc>>,
 
+    /// Signed difference (in seconds) between the 0x10 and 0x30 timestamps (SI - FN).
+    /// Present only for entries that have both attributes.
+    pub created_delta_secs: Option<i64>,
+    pub last_modified_delta_secs: Option<i64>,
+    pub last_access_delta_secs: Option<i64>,
+
+    /// The creation time embedded in the 0x40 attribute, if the object id is time-based.
+    pub object_id_timestamp: Option<DateTime<Utc>>,
+    /// The GUIDs of the 0x40 attribute, formatted like `{CC80E6BA-4C45-4742-B372-24956C937B9D}`.
+    /// The object id is used by the link tracking service to find files which were moved or renamed.
+    pub object_id: Option<String>,
+    /// These are only present in extended 0x40 attributes.
+    pub birth_volume_id: Option<String>,
+    pub birth_object_id: Option<String>,
+    pub domain_id: Option<String>,
+
+    /// The tag of the 0xC0 attribute, written in hex (such as `0xA000000C` for symbolic links).
+    #[serde(with = "hex_tag")]
+    pub reparse_tag: Option<u32>,
+    /// The target of symbolic links, junctions and mount points (the substitute name, which is what gets resolved).
+    pub reparse_target: Option<String>,
+
+    /// Whether the parent referenced by the 0x30 attribute exists, and is a directory.
+    pub parent_reference_status: Option<ParentRefStatus>,
+    /// Whether the parent referenced by the 0x30 attribute is missing, unallocated or was reused, see `MftParser::is_orphan`.
+    pub is_orphan: bool,
+    /// Whether the size of the 0x80 attribute agrees with the size recorded in the 0x30 attribute.
+    pub data_size_consistency: DataSizeConsistency,
+    /// Signs that the 0x10 timestamps were altered, present only for entries that have both 0x10 and 0x30 attributes.
+    pub timestomp_indicators: Option<TimestompIndicators>,
+
     pub full_path: PathBuf,
 }
 
@@ -65,30 +116,58 @@ impl FlatMftEntryWithName {
                 MftAttributeType::FileName,
                 MftAttributeType::StandardInformation,
                 MftAttributeType::DATA,
+                MftAttributeType::ObjectId,
+                MftAttributeType::ReparsePoint,
             ]))
             .filter_map(Result::ok)
             .collect();
 
-        let file_name = entry_attributes
+        let file_names: Vec<FileNameAttr> = entry_attributes
+            .iter()
+            .filter(|a| a.header.type_code == MftAttributeType::FileName)
+            .filter_map(|a| a.data.clone().into_file_name())
+            .collect();
+        let file_name = file_names.first().cloned();
+        let best_name = best_name_attribute(file_names, FileNamespace::Win32);
+
+        let standard_info_attribute = entry_attributes
             .iter()
-            .find(|a| a.header.type_code == MftAttributeType::FileName)
-            .and_then(|a| a.data.clone().into_file_name());
+            .find(|a| a.header.type_code == MftAttributeType::StandardInformation);
+        let standard_info =
+            standard_info_attribute.and_then(|a| a.data.clone().into_standard_info());
 
-        let standard_info = entry_attributes
+        let usn = match standard_info_attribute.map(|a| &a.header.residential_header) {
+            Some(ResidentialHeader::Resident(header))
+                if header.data_size >= STANDARD_INFO_V3_SIZE =>
+            {
+                standard_info.as_ref().map(|i| i.usn).unwrap_or(0)
+            }
+            _ => 0,
+        };
+
+        let object_id = entry_attributes
             .iter()
-            .find(|a| a.header.type_code == MftAttributeType::StandardInformation)
-            .and_then(|a| a.data.clone().into_standard_info());
+            .find(|a| a.header.type_code == MftAttributeType::ObjectId)
+            .and_then(|a| a.data.clone().into_object_id());
 
-        let data_attr = entry_attributes
+        let reparse_point = entry_attributes
             .iter()
-            .find(|a| a.header.type_code == MftAttributeType::DATA);
+            .find(|a| a.header.type_code == MftAttributeType::ReparsePoint)
+            .and_then(|a| a.data.clone().into_reparse_point());
 
-        let file_size = match data_attr {
-            Some(attr) => match &attr.header.residential_header {
-                ResidentialHeader::Resident(r) => u64::from(r.data_size),
-                ResidentialHeader::NonResident(nr) => nr.file_size,
-            },
-            _ => 0,
+        let unnamed_data = entry_attributes
+            .iter()
+            .find(|a| a.header.type_code == MftAttributeType::DATA && a.header.name.is_empty());
+
+        let (parent_reference_status, is_orphan) =
+            parser.parent_reference_status_and_orphan(entry, best_name.as_ref());
+
+        let file_size = DataLayout::from_attributes(&entry_attributes)
+            .map(|layout| layout.logical_size)
+            .unwrap_or(0);
+
+        let delta_secs = |si: Option<DateTime<Utc>>, fname: Option<DateTime<Utc>>| {
+            Some((si? - fname?).num_seconds())
         };
 
         let has_ads = entry_attributes
@@ -104,24 +183,253 @@ impl FlatMftEntryWithName {
             flags: entry.header.flags,
             used_entry_size: entry.header.used_entry_size,
             total_entry_size: entry.header.total_entry_size,
+            first_attribute_record_offset: entry.header.first_attribute_record_offset,
+            first_attribute_id: entry.header.first_attribute_id,
+            logfile_sequence_number: entry.header.metadata_transaction_journal,
             base_entry_id: entry.header.base_reference.entry,
             base_entry_sequence: entry.header.base_reference.sequence,
             is_a_directory: entry.is_dir(),
             is_deleted: !entry.header.flags.contains(EntryFlags::ALLOCATED),
             has_alternate_data_streams: has_ads,
+            has_suspicious_name: best_name
+                .as_ref()
+                .is_some_and(|file_name| is_suspicious_name(&file_name.name)),
             standard_info_flags: standard_info.as_ref().map(|i| i.file_flags),
             standard_info_last_modified: standard_info.as_ref().map(|i| i.modified),
             standard_info_last_access: standard_info.as_ref().map(|i| i.accessed),
             standard_info_created: standard_info.as_ref().map(|i| i.created),
+            usn,
             file_name_flags: file_name.as_ref().map(|i| i.flags),
             file_name_last_modified: file_name.as_ref().map(|i| i.modified),
             file_name_last_access: file_name.as_ref().map(|i| i.accessed),
             file_name_created: file_name.as_ref().map(|i| i.created),
+            created_delta_secs: delta_secs(
+                standard_info.as_ref().map(|i| i.created),
+                file_name.as_ref().map(|i| i.created),
+            ),
+            last_modified_delta_secs: delta_secs(
+                standard_info.as_ref().map(|i| i.modified),
+                file_name.as_ref().map(|i| i.modified),
+            ),
+            last_access_delta_secs: delta_secs(
+                standard_info.as_ref().map(|i| i.accessed),
+                file_name.as_ref().map(|i| i.accessed),
+            ),
+            object_id_timestamp: object_id.as_ref().and_then(|o| o.object_id_timestamp()),
+            object_id: object_id.as_ref().map(|o| braced_guid(&o.object_id)),
+            birth_volume_id: object_id
+                .as_ref()
+                .and_then(|o| o.birth_volume_id.as_ref())
+                .map(braced_guid),
+            birth_object_id: object_id
+                .as_ref()
+                .and_then(|o| o.birth_object_id.as_ref())
+                .map(braced_guid),
+            domain_id: object_id
+                .as_ref()
+                .and_then(|o| o.domain_id.as_ref())
+                .map(braced_guid),
+            reparse_tag: reparse_point.as_ref().map(|r| r.raw_tag),
+            reparse_target: reparse_point.and_then(|r| match r.data {
+                ReparseData::Link {
+                    substitute_name, ..
+                } => Some(substitute_name),
+                _ => None,
+            }),
             file_size,
+            parent_reference_status,
+            is_orphan: is_orphan.unwrap_or(false),
+            data_size_consistency: data_size_consistency(
+                entry.is_dir(),
+                best_name.as_ref(),
+                unnamed_data,
+            ),
+            timestomp_indicators: standard_info
+                .as_ref()
+                .zip(best_name.as_ref())
+                .map(|(standard_info, file_name)| timestomp_indicators(standard_info, file_name)),
             full_path: parser
-                .get_full_path_for_entry(entry)
-                .expect("I/O Err")
+                .full_path_for_best_name(entry, best_name)
                 .unwrap_or_default(),
         }
     }
 }
+
+/// (De)serializes an optional reparse tag as a hex string.
+mod hex_tag {
+    use serde::{de, Deserialize, Deserializer, Serializer};
+
+    pub fn serialize<S: Serializer>(tag: &Option<u32>, s: S) -> Result<S::Ok, S::Error> {
+        match tag {
+            Some(tag) => s.serialize_str(&format!("0x{:08X}", tag)),
+            None => s.serialize_none(),
+        }
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(d: D) -> Result<Option<u32>, D::Error> {
+        match Option::<String>::deserialize(d)? {
+            Some(tag) if !tag.is_empty() => u32::from_str_radix(tag.trim_start_matches("0x"), 16)
+                .map(Some)
+                .map_err(de::Error::custom),
+            _ => Ok(None),
+        }
+    }
+}
+
+/// Formats a GUID in its canonical braced form.
+fn braced_guid(guid: &Guid) -> String {
+    format!("{{{}}}", guid)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::FlatMftEntryWithName;
+    use crate::entry::EntryFlags;
+    use crate::tests::fixtures::{mft_sample, synthetic_entry_with_attributes};
+    use crate::{MftEntry, MftParser};
+
+    fn to_csv(rows: &[FlatMftEntryWithName]) -> String {
+        let mut writer = csv::Writer::from_writer(vec![]);
+        for row in rows {
+            writer.serialize(row).unwrap();
+        }
+
+        String::from_utf8(writer.into_inner().unwrap()).unwrap()
+    }
+
+    #[test]
+    fn test_csv_round_trip() {
+        let sample = mft_sample();
+        let mut parser = MftParser::from_path(sample).unwrap();
+
+        let entries: Vec<_> = parser
+            .iter_entries()
+            .take(100)
+            .filter_map(Result::ok)
+            .collect();
+
+        let rows: Vec<FlatMftEntryWithName> = entries
+            .iter()
+            .map(|entry| FlatMftEntryWithName::from_entry(entry, &mut parser))
+            .collect();
+
+        let exported = to_csv(&rows);
+
+        let read_back: Vec<FlatMftEntryWithName> = csv::Reader::from_reader(exported.as_bytes())
+            .deserialize()
+            .collect::<Result<_, _>>()
+            .unwrap();
+
+        assert_eq!(read_back.len(), rows.len());
+        assert_eq!(to_csv(&read_back), exported);
+    }
+
+    #[test]
+    fn test_logfile_sequence_number_and_usn() {
+        let mut parser = MftParser::from_path(mft_sample()).unwrap();
+
+        let entry = parser.get_entry(38).unwrap();
+        let row = FlatMftEntryWithName::from_entry(&entry, &mut parser);
+        assert_eq!(
+            row.logfile_sequence_number,
+            entry.header.metadata_transaction_journal
+        );
+        assert_eq!(row.usn, 0);
+
+        // `$Volume` has a 48 bytes 0x10 attribute, which has no usn.
+        let volume = parser.get_entry(3).unwrap();
+        let row = FlatMftEntryWithName::from_entry(&volume, &mut parser);
+        assert_eq!(row.logfile_sequence_number, 23_209_794);
+        assert_eq!(row.usn, 0);
+    }
+
+    #[test]
+    fn test_usn_of_standard_info_v3() {
+        let mut parser = MftParser::from_path(mft_sample()).unwrap();
+
+        let mut standard_info = vec![0_u8; 72];
+        standard_info[64..72].copy_from_slice(&8_768_215_144_u64.to_le_bytes());
+        let buffer = synthetic_entry_with_attributes(1024, 40, &[(0x10, &standard_info)]);
+        let entry = MftEntry::from_buffer(buffer, 40).unwrap();
+
+        let row = FlatMftEntryWithName::from_entry(&entry, &mut parser);
+        assert_eq!(row.usn, 8_768_215_144);
+    }
+
+    #[test]
+    fn test_object_id() {
+        let mut parser = MftParser::from_path(mft_sample()).unwrap();
+
+        let volume = parser.get_entry(3).unwrap();
+        let row = FlatMftEntryWithName::from_entry(&volume, &mut parser);
+        assert_eq!(
+            row.object_id.as_deref(),
+            Some("{CC80E6BA-4C45-4742-B372-24956C937B9D}")
+        );
+        // The object id of `$Volume` is not extended.
+        assert_eq!(row.birth_volume_id, None);
+        assert_eq!(row.domain_id, None);
+
+        let mft = parser.get_entry(0).unwrap();
+        let row = FlatMftEntryWithName::from_entry(&mft, &mut parser);
+        assert_eq!(row.object_id, None);
+    }
+
+    #[test]
+    fn test_reparse_point() {
+        let mut parser = MftParser::from_path(mft_sample()).unwrap();
+
+        let utf16 = |s: &str| -> Vec<u8> { s.encode_utf16().flat_map(u16::to_le_bytes).collect() };
+        let substitute_name = utf16("\\??\\C:\\Windows\\System32");
+        let print_name = utf16("C:\\Windows\\System32");
+
+        let mut link = vec![];
+        link.extend(0_u16.to_le_bytes());
+        link.extend((substitute_name.len() as u16).to_le_bytes());
+        link.extend((substitute_name.len() as u16).to_le_bytes());
+        link.extend((print_name.len() as u16).to_le_bytes());
+        // Flags
+        link.extend(0_u32.to_le_bytes());
+        link.extend(&substitute_name);
+        link.extend(&print_name);
+
+        let mut reparse_point = 0xA000_000C_u32.to_le_bytes().to_vec();
+        reparse_point.extend((link.len() as u16).to_le_bytes());
+        reparse_point.extend([0, 0]);
+        reparse_point.extend(link);
+
+        let buffer = synthetic_entry_with_attributes(1024, 40, &[(0xC0, &reparse_point)]);
+        let entry = MftEntry::from_buffer(buffer, 40).unwrap();
+        let row = FlatMftEntryWithName::from_entry(&entry, &mut parser);
+
+        assert_eq!(row.reparse_tag, Some(0xA000_000C));
+        assert_eq!(
+            row.reparse_target.as_deref(),
+            Some("\\??\\C:\\Windows\\System32")
+        );
+
+        let exported = to_csv(&[row]);
+        assert!(exported.contains(",0xA000000C,"));
+
+        let read_back: Vec<FlatMftEntryWithName> = csv::Reader::from_reader(exported.as_bytes())
+            .deserialize()
+            .collect::<Result<_, _>>()
+            .unwrap();
+        assert_eq!(read_back[0].reparse_tag, Some(0xA000_000C));
+    }
+
+    #[test]
+    fn test_flags_can_be_read_from_bits() {
+        let csv = "Flags\n3\nALLOCATED | INDEX_PRESENT\n";
+
+        let flags: Vec<EntryFlags> = csv::Reader::from_reader(csv.as_bytes())
+            .deserialize()
+            .map(|row: Result<(EntryFlags,), _>| row.unwrap().0)
+            .collect();
+
+        assert_eq!(
+            flags,
+            vec![EntryFlags::ALLOCATED | EntryFlags::INDEX_PRESENT; 2]
+        );
+    }
+}
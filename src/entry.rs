@@ -1,5 +1,5 @@
 use crate::err::{Error, Result};
-use crate::impl_serialize_for_bitflags;
+use crate::{impl_deserialize_for_bitflags, impl_serialize_for_bitflags};
 
 use log::{trace, warn};
 
@@ -9,22 +9,164 @@ use byteorder::{LittleEndian, ReadBytesExt};
 
 use bitflags::bitflags;
 use serde::ser::{self, SerializeStruct, Serializer};
-use serde::Serialize;
+use serde::{Deserialize, Serialize};
 
+use crate::attribute::custom::AttributeDecoders;
+use crate::attribute::data_run::{DataRun, RunType};
 use crate::attribute::header::{MftAttributeHeader, ResidentialHeader};
+use crate::attribute::non_resident_attr::combine_data_runs;
+use crate::attribute::x10::StandardInfoAttr;
 use crate::attribute::x30::{FileNameAttr, FileNamespace};
+use crate::attribute::x90::{IndexRootFlags, INDEX_RECORD_HEADER_SIZE};
+use crate::attribute::xb0::BitmapAttr;
+use crate::attribute::xc0::ReparseTag;
 use crate::attribute::{MftAttribute, MftAttributeContent, MftAttributeType};
+use crate::index::{parse_indx_record, INDX_SIGNATURE};
+use crate::utils::{apply_usa_fixups, FixupBuffer, SEQUENCE_NUMBER_STRIDE};
 
+use chrono::{SecondsFormat, Timelike};
+
+use std::borrow::Cow;
 use std::io::Read;
 use std::io::SeekFrom;
 use std::io::{Cursor, Seek};
+use std::ops::Range;
 
-const SEQUENCE_NUMBER_STRIDE: usize = 512;
+/// The size of the fields of `EntryHeader`, an attribute cannot start before this offset.
+const ENTRY_HEADER_SIZE: u16 = 42;
 
 pub const ZERO_HEADER: &[u8; 4] = b"\x00\x00\x00\x00";
 pub const BAAD_HEADER: &[u8; 4] = b"BAAD";
 pub const FILE_HEADER: &[u8; 4] = b"FILE";
 
+/// The range of record numbers reserved by Windows, see `MftEntry::is_reserved_record`.
+const RESERVED_RECORDS: Range<u64> = 12..24;
+
+/// The `$BadClus` metadata file, which maps the bad clusters of the volume in its `$Bad` stream.
+const BADCLUS_ENTRY_NUMBER: u64 = 8;
+const BADCLUS_BAD_STREAM_NAME: &str = "$Bad";
+
+/// The bidirectional formatting characters (embeddings, overrides and isolates),
+/// which can make a name display differently from how it is stored (such as `evil\u{202E}txt.exe`).
+const BIDI_CONTROL_CHARACTERS: &[char] = &[
+    '\u{200E}', '\u{200F}', '\u{202A}', '\u{202B}', '\u{202C}', '\u{202D}', '\u{202E}', '\u{2066}',
+    '\u{2067}', '\u{2068}', '\u{2069}',
+];
+
+/// The stream holding the compressed content of a file compressed by the Windows Overlay Filter.
+const WOF_COMPRESSED_DATA_STREAM_NAME: &str = "WofCompressedData";
+
+/// The name of the index of the file names in a directory (and of its `$INDEX_ALLOCATION` and `$BITMAP` attributes).
+const DIRECTORY_INDEX_NAME: &str = "$I30";
+
+/// Differences in size smaller than this (a cluster on most volumes) are not considered a mismatch.
+const SIGNIFICANT_SIZE_DIFFERENCE: u64 = 4096;
+
+/// The result of comparing the size of the $DATA attribute to the size recorded in the $FILE_NAME attribute,
+/// see `MftEntry::data_size_consistency`.
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DataSizeConsistency {
+    /// The entry is a directory, or it has no unnamed $DATA attribute or no $FILE_NAME attribute.
+    NotApplicable,
+    Consistent,
+    /// The $DATA attribute is resident and empty, but the $FILE_NAME attribute records a non-zero size.
+    EmptyResidentData,
+    /// The $DATA attribute is significantly smaller than the size recorded in the $FILE_NAME attribute.
+    SizeMismatch,
+}
+
+bitflags! {
+    /// Signs that the $STANDARD_INFORMATION timestamps were altered (timestomped), see `MftEntry::detect_timestomp`.
+    pub struct TimestompIndicators: u8 {
+        /// The $STANDARD_INFORMATION creation time is earlier than the $FILE_NAME one.
+        const SI_CREATED_BEFORE_FN  = 0x01;
+        /// The $STANDARD_INFORMATION modification time is earlier than the $FILE_NAME one.
+        const SI_MODIFIED_BEFORE_FN = 0x02;
+        /// The $STANDARD_INFORMATION creation and modification times have no fractional seconds,
+        /// which happens when they are set by tools that only take whole seconds.
+        const SI_ZEROED_SUBSECONDS  = 0x04;
+    }
+}
+
+impl_serialize_for_bitflags! {TimestompIndicators}
+impl_deserialize_for_bitflags! {TimestompIndicators}
+
+/// A summary of how the content of a file is stored, see `MftEntry::data_layout`.
+#[derive(Serialize, Debug, Clone, Copy, PartialEq, Eq)]
+pub struct DataLayout {
+    /// Whether the content is stored inside the entry itself.
+    pub is_resident: bool,
+    /// The number of runs of clusters backing the content, sparse runs are not counted.
+    /// Always 0 for resident content.
+    pub fragment_count: usize,
+    /// The size of the content, in bytes.
+    pub logical_size: u64,
+    /// The number of bytes allocated for the content.
+    /// For compressed (or sparse) streams this is the number of bytes actually allocated on disk.
+    pub allocated_size: u64,
+    pub is_compressed: bool,
+    pub is_sparse: bool,
+}
+
+impl DataLayout {
+    /// Computes the layout from the attributes of an entry, only the unnamed $DATA attributes are considered.
+    ///
+    /// Returns `None` if there is no unnamed $DATA attribute, or if the attributes are all extents of
+    /// a stream whose first extent is stored in another entry (so the sizes are unknown).
+    pub(crate) fn from_attributes<'a>(
+        attributes: impl IntoIterator<Item = &'a MftAttribute>,
+    ) -> Option<DataLayout> {
+        let mut layout: Option<DataLayout> = None;
+        let mut fragment_count = 0;
+
+        for attribute in attributes
+            .into_iter()
+            .filter(|a| a.header.type_code == MftAttributeType::DATA && a.header.name.is_empty())
+        {
+            match &attribute.header.residential_header {
+                ResidentialHeader::Resident(resident) => {
+                    return Some(DataLayout {
+                        is_resident: true,
+                        fragment_count: 0,
+                        logical_size: u64::from(resident.data_size),
+                        allocated_size: u64::from(resident.data_size),
+                        is_compressed: attribute.is_compressed(),
+                        is_sparse: attribute.is_sparse(),
+                    });
+                }
+                ResidentialHeader::NonResident(non_resident) => {
+                    if let MftAttributeContent::DataRun(content) = &attribute.data {
+                        fragment_count += content
+                            .data_runs
+                            .iter()
+                            .filter(|run| run.run_type == RunType::Standard)
+                            .count();
+                    }
+
+                    // The sizes are only valid in the first extent of the attribute.
+                    if non_resident.vnc_first == 0 {
+                        layout = Some(DataLayout {
+                            is_resident: false,
+                            fragment_count: 0,
+                            logical_size: non_resident.file_size,
+                            allocated_size: non_resident
+                                .total_allocated
+                                .unwrap_or(non_resident.allocated_length),
+                            is_compressed: attribute.is_compressed(),
+                            is_sparse: attribute.is_sparse(),
+                        });
+                    }
+                }
+            }
+        }
+
+        layout.map(|layout| DataLayout {
+            fragment_count,
+            ..layout
+        })
+    }
+}
+
 #[derive(Debug, Clone)]
 pub struct MftEntry {
     pub header: EntryHeader,
@@ -33,6 +175,20 @@ pub struct MftEntry {
     /// matched the fixup array value. It is optional because in the case of
     /// from_buffer_skip_fixup(), no fixup is even checked, thus, valid_fixup is None
     pub valid_fixup: Option<bool>,
+    /// Decoders for attributes which are otherwise left raw, see `MftParser::register_attribute_decoder`.
+    attribute_decoders: AttributeDecoders,
+}
+
+/// A borrowed counterpart of `MftEntry`, useful for scanning memory mapped (or otherwise in-memory) MFTs.
+///
+/// The entry data is only copied if applying the fixups actually changes it,
+/// otherwise it is borrowed from the input slice.
+#[derive(Debug, Clone)]
+pub struct MftEntryRef<'a> {
+    pub header: EntryHeader,
+    pub data: Cow<'a, [u8]>,
+    /// See `MftEntry::valid_fixup`.
+    pub valid_fixup: Option<bool>,
 }
 
 impl ser::Serialize for MftEntry {
@@ -92,6 +248,7 @@ bitflags! {
 }
 
 impl_serialize_for_bitflags! {EntryFlags}
+impl_deserialize_for_bitflags! {EntryFlags}
 
 impl EntryHeader {
     /// Reads an entry from a stream, will error if the entry is empty (zeroes)
@@ -109,7 +266,10 @@ impl EntryHeader {
         }
 
         if signature == *ZERO_HEADER {
-            return Ok(Self::zero());
+            return Ok(EntryHeader {
+                record_number: entry_id,
+                ..Self::zero()
+            });
         }
 
         let usa_offset = reader.read_u16::<LittleEndian>()?;
@@ -148,6 +308,12 @@ impl EntryHeader {
         self.signature == *FILE_HEADER
     }
 
+    /// Returns false if `first_attribute_record_offset` points into the header itself (for example when it is zero),
+    /// in which case the entry is treated as having no attributes.
+    pub fn has_valid_attribute_offset(&self) -> bool {
+        self.first_attribute_record_offset >= ENTRY_HEADER_SIZE
+    }
+
     pub fn zero() -> Self {
         EntryHeader {
             signature: *ZERO_HEADER,
@@ -174,22 +340,26 @@ impl MftEntry {
     /// Initializes an MFT Entry from a buffer.
     /// Since the parser is the entity responsible for knowing the entry size,
     /// we take ownership of the buffer instead of trying to read it from stream.
-    pub fn from_buffer(mut buffer: Vec<u8>, entry_number: u64) -> Result<MftEntry> {
+    pub fn from_buffer(buffer: Vec<u8>, entry_number: u64) -> Result<MftEntry> {
         let mut cursor = Cursor::new(&buffer);
         // Get Header
         let entry_header = EntryHeader::from_reader(&mut cursor, entry_number)?;
         trace!("Number of sectors: {:#?}", entry_header);
 
+        // The buffer is already owned, so the fixups are applied in place.
+        let mut data = Cow::Owned(buffer);
+
         let valid_fixup = if entry_header.is_valid() {
-            Some(Self::apply_fixups(&entry_header, &mut buffer)?)
+            Some(apply_fixups(&entry_header, &mut data)?)
         } else {
             None
         };
 
         Ok(MftEntry {
             header: entry_header,
-            data: buffer,
+            data: data.into_owned(),
             valid_fixup,
+            attribute_decoders: AttributeDecoders::default(),
         })
     }
 
@@ -212,81 +382,374 @@ impl MftEntry {
             header: entry_header,
             data: buffer,
             valid_fixup: None,
+            attribute_decoders: AttributeDecoders::default(),
         })
     }
 
+    /// Decodes otherwise raw attributes of the entry with `attribute_decoders`.
+    /// Entries read by `MftParser` already use the decoders registered with the parser.
+    pub fn with_attribute_decoders(mut self, attribute_decoders: AttributeDecoders) -> Self {
+        self.attribute_decoders = attribute_decoders;
+        self
+    }
+
+    /// Iterates over all the $FILE_NAME attributes of the entry (of all namespaces), in attribute order.
+    /// An entry has more than one name when it has hard links, or a short (DOS) name.
+    /// Attributes which fail to parse are skipped.
+    pub fn iter_file_names(&self) -> impl Iterator<Item = FileNameAttr> + '_ {
+        self.iter_attributes_matching(Some(vec![MftAttributeType::FileName]))
+            .filter_map(Result::ok)
+            .filter_map(|a| a.data.into_file_name())
+    }
+
+    /// Returns all the $FILE_NAME attributes of the entry, see `iter_file_names`.
+    pub fn file_names(&self) -> Vec<FileNameAttr> {
+        self.iter_file_names().collect()
+    }
+
+    /// Returns the $STANDARD_INFORMATION attribute and all the $FILE_NAME attributes of the entry,
+    /// which hold all of its timestamps.
+    ///
+    /// Only the attribute headers are read for other attributes (their content is never parsed),
+    /// which makes this cheaper than `iter_attributes` for building timelines.
+    /// Attributes which fail to parse are skipped.
+    pub fn timeline_attributes(&self) -> (Option<StandardInfoAttr>, Vec<FileNameAttr>) {
+        let mut standard_info = None;
+        let mut file_names = vec![];
+        let mut cursor = Cursor::new(&self.data);
+
+        for header in self.attribute_headers().filter_map(Result::ok) {
+            // Both attributes are always resident.
+            let data_offset = match &header.residential_header {
+                ResidentialHeader::Resident(resident) => u64::from(resident.data_offset),
+                ResidentialHeader::NonResident(_) => continue,
+            };
+
+            let is_standard_info = match header.type_code {
+                MftAttributeType::StandardInformation if standard_info.is_none() => true,
+                MftAttributeType::FileName => false,
+                _ => continue,
+            };
+
+            if cursor
+                .seek(SeekFrom::Start(header.start_offset + data_offset))
+                .is_err()
+            {
+                continue;
+            }
+
+            if is_standard_info {
+                standard_info = StandardInfoAttr::from_reader(&mut cursor).ok();
+            } else if let Ok(file_name) = FileNameAttr::from_stream(&mut cursor) {
+                file_names.push(file_name);
+            }
+        }
+
+        (standard_info, file_names)
+    }
+
     /// Retrieves most human-readable representation of a file path entry.
     /// Will prefer `Win32` file name attributes, and fallback to `Dos` paths.
     pub fn find_best_name_attribute(&self) -> Option<FileNameAttr> {
-        let file_name_attributes: Vec<FileNameAttr> = self
-            .iter_attributes_matching(Some(vec![MftAttributeType::FileName]))
+        self.find_best_name_attribute_with_preference(FileNamespace::Win32)
+    }
+
+    /// Like `find_best_name_attribute`, but prefers file names of the `prefer` namespace,
+    /// falling back to `Win32` names, and then to any name.
+    ///
+    /// Volumes written by non-Windows implementations may keep the real file name in the `POSIX` namespace.
+    /// A `Win32AndDos` name is considered to be both a `Win32` and a `DOS` name.
+    pub fn find_best_name_attribute_with_preference(
+        &self,
+        prefer: FileNamespace,
+    ) -> Option<FileNameAttr> {
+        best_name_attribute(self.file_names(), prefer)
+    }
+
+    /// Compares the size of the unnamed $DATA attribute with the size recorded in the $FILE_NAME attribute.
+    ///
+    /// The $FILE_NAME size is not always kept up to date by NTFS (it is often 0, or lags behind the real size),
+    /// so only a $DATA attribute which is smaller than what the $FILE_NAME attribute implies is reported,
+    /// as it may indicate tampering or an interrupted write.
+    pub fn data_size_consistency(&self) -> DataSizeConsistency {
+        let data = self
+            .iter_attributes_matching(Some(vec![MftAttributeType::DATA]))
+            .filter_map(Result::ok)
+            .find(|a| a.header.name.is_empty());
+
+        data_size_consistency(
+            self.is_dir(),
+            self.find_best_name_attribute().as_ref(),
+            data.as_ref(),
+        )
+    }
+
+    /// Summarizes how the content of the file (the unnamed $DATA attribute) is stored.
+    ///
+    /// Returns `None` if the entry has no unnamed $DATA attribute (such as directories),
+    /// or only holds extents of a stream whose sizes are recorded in another entry.
+    pub fn data_layout(&self) -> Option<DataLayout> {
+        let data_attributes: Vec<MftAttribute> = self
+            .iter_attributes_matching(Some(vec![MftAttributeType::DATA]))
             .filter_map(Result::ok)
-            .filter_map(|a| a.data.into_file_name())
             .collect();
 
-        // Try to find a human-readable filename first
-        let win32_filename = file_name_attributes
-            .iter()
-            .find(|a| [FileNamespace::Win32, FileNamespace::Win32AndDos].contains(&a.namespace));
+        DataLayout::from_attributes(&data_attributes)
+    }
+
+    /// Extracts the content of a $DATA stream, the unnamed one if `stream_name` is `None`,
+    /// or an alternate data stream otherwise.
+    ///
+    /// Resident content is returned directly, while non-resident content is read from `image` (the volume)
+    /// at the byte runs of the stream, see `NonResidentAttr::read_non_resident_data`:
+    /// sparse runs, and the tail after `valid_data_length`, read back as zeros, and compressed streams are not decompressed.
+    ///
+    /// Only the attributes of this entry are used, so streams which continue in extension entries fail to extract.
+    /// Before reading anything, the runs are checked to cover the stream and to lie within `image`,
+    /// so a corrupted entry cannot cause huge allocations or reads.
+    pub fn extract_data<R: Read + Seek>(
+        &self,
+        image: &mut R,
+        bytes_per_cluster: u64,
+        stream_name: Option<&str>,
+    ) -> Result<Vec<u8>> {
+        let stream_name = stream_name.unwrap_or("");
 
-        match win32_filename {
-            Some(filename) => Some(filename.clone()),
+        // Decoders are not applied, the content is needed as it is stored.
+        let attributes: Vec<MftAttribute> =
+            iter_attributes_matching(&self.data, &self.header, Some(vec![MftAttributeType::DATA]))
+                .filter_map(Result::ok)
+                .filter(|a| a.header.name == stream_name)
+                .collect();
+
+        match attributes.first().map(|a| &a.header.residential_header) {
+            Some(ResidentialHeader::Resident(_)) => {
+                return Ok(attributes[0]
+                    .data
+                    .clone()
+                    .into_data()
+                    .map(|data| data.data().to_vec())
+                    .unwrap_or_default())
+            }
+            Some(ResidentialHeader::NonResident(_)) => {}
             None => {
-                // Try to take anything
-                file_name_attributes.get(0).cloned()
+                return Err(Error::MissingDataStream {
+                    entry_id: self.header.record_number,
+                    stream_name: stream_name.to_string(),
+                })
+            }
+        }
+
+        // Fails unless the runs are contiguous from VCN 0, so the first record of the stream is present.
+        let content = combine_data_runs(&attributes)?;
+        let header = attributes
+            .iter()
+            .find_map(|a| match &a.header.residential_header {
+                ResidentialHeader::NonResident(header) if header.vnc_first == 0 => Some(header),
+                _ => None,
+            })
+            .expect("The runs start at VCN 0");
+        let byte_runs = content.byte_runs(bytes_per_cluster);
+
+        let runs_length: u64 = byte_runs.iter().map(|run| run.length).sum();
+        if header.file_size > runs_length {
+            return Err(Error::StreamSizeExceedsDataRuns {
+                file_size: header.file_size,
+                runs_length,
+            });
+        }
+
+        let image_size = image.seek(SeekFrom::End(0))?;
+        for run in &byte_runs {
+            if let Some(offset) = run.offset {
+                if offset.saturating_add(run.length) > image_size {
+                    return Err(Error::DataRunOutOfBounds {
+                        offset,
+                        length: run.length,
+                        image_size,
+                    });
+                }
             }
         }
+
+        content.read_non_resident_data(image, header, bytes_per_cluster)
     }
 
-    /// Applies the update sequence array fixups.
-    /// https://docs.microsoft.com/en-us/windows/desktop/devnotes/multi-sector-header
-    /// **Note**: The fixup will be written at the end of each 512-byte stride,
-    /// even if the device has more (or less) than 512 bytes per sector.
-    /// The returned result is true if all fixup blocks had the fixup array value, or
-    /// false if a block's fixup value did not match the array's value.
-    fn apply_fixups(header: &EntryHeader, buffer: &mut [u8]) -> Result<bool> {
-        let mut valid_fixup = true;
-        let number_of_fixups = u32::from(header.usa_size - 1);
-        trace!("Number of fixups: {}", number_of_fixups);
-
-        // Each fixup is a 2-byte element, and there are `usa_size` of them.
-        let fixups_start_offset = header.usa_offset as usize;
-        let fixups_end_offset = fixups_start_offset + (header.usa_size * 2) as usize;
-
-        let fixups = buffer[fixups_start_offset..fixups_end_offset].to_vec();
-        let mut fixups = fixups.chunks(2);
-
-        // There should always be bytes here, but just in case we put zeroes, so it will fail later.
-        let update_sequence = fixups.next().unwrap_or(&[0, 0]);
-
-        // We need to compare each last two bytes each 512-bytes stride with the update_sequence,
-        // And if they match, replace those bytes with the matching bytes from the fixup_sequence.
-        for (stride_number, fixup_bytes) in (0_usize..number_of_fixups as usize).zip(fixups) {
-            let sector_start_offset = stride_number * SEQUENCE_NUMBER_STRIDE;
-
-            let end_of_sector_bytes_end_offset = sector_start_offset + SEQUENCE_NUMBER_STRIDE;
-            let end_of_sector_bytes_start_offset = end_of_sector_bytes_end_offset - 2;
-
-            let end_of_sector_bytes =
-                &mut buffer[end_of_sector_bytes_start_offset..end_of_sector_bytes_end_offset];
-
-            if end_of_sector_bytes != update_sequence {
-                // An item in the block did not match the fixup array value
-                warn!(
-                    "[entry: {}] fixup bytes are not equal to update sequence value - stride_number: {}, end_of_sector_bytes: {:?}, fixup_bytes: {:?}",
-                    header.record_number,
-                    stride_number,
-                    end_of_sector_bytes.to_vec(),
-                    fixup_bytes.to_vec()
-                );
+    /// Returns a key identifying the file regardless of its record number,
+    /// for correlating the same file across snapshots (or copies) of a volume.
+    ///
+    /// The key is `<object id>|<created>|<name>`, made of the object id (empty if the file has none),
+    /// the $STANDARD_INFORMATION creation time and the best name of the file.
+    /// Returns `None` if the entry has no $STANDARD_INFORMATION or $FILE_NAME attribute.
+    pub fn identity_key(&self) -> Option<String> {
+        let (standard_info, _) = self.timeline_attributes();
+        let created = standard_info?.created;
+        let name = self.find_best_name_attribute()?.name;
+
+        let object_id = self
+            .iter_attributes_matching(Some(vec![MftAttributeType::ObjectId]))
+            .filter_map(Result::ok)
+            .find_map(|a| a.data.into_object_id())
+            .map(|object_id| object_id.object_id.to_string())
+            .unwrap_or_default();
+
+        Some(format!(
+            "{}|{}|{}",
+            object_id,
+            created.to_rfc3339_opts(SecondsFormat::Nanos, true),
+            name
+        ))
+    }
+
+    /// Returns true if the best name of the entry is a known obfuscation trick.
+    ///
+    /// These are names ending in a space or a dot (which the Win32 API strips, so they cannot be opened normally),
+    /// and names containing control characters (including NUL) or bidirectional formatting characters.
+    /// Returns false if the entry has no $FILE_NAME attribute.
+    pub fn has_suspicious_name(&self) -> bool {
+        self.find_best_name_attribute()
+            .is_some_and(|file_name| is_suspicious_name(&file_name.name))
+    }
+
+    /// Compares the $STANDARD_INFORMATION timestamps with those of the best $FILE_NAME attribute.
+    ///
+    /// The $FILE_NAME timestamps can only be set by the kernel, while the $STANDARD_INFORMATION ones
+    /// can be set by any user mode program, so a $STANDARD_INFORMATION timestamp predating its $FILE_NAME
+    /// counterpart is a common sign of tampering (though copying or extracting files can cause it too).
+    /// Returns `None` if the entry lacks either attribute, and empty indicators if nothing looks off.
+    pub fn detect_timestomp(&self) -> Option<TimestompIndicators> {
+        let standard_info = self
+            .iter_attributes_matching(Some(vec![MftAttributeType::StandardInformation]))
+            .filter_map(Result::ok)
+            .find_map(|a| a.data.into_standard_info())?;
+        let file_name = self.find_best_name_attribute()?;
 
-                valid_fixup = false;
+        Some(timestomp_indicators(&standard_info, &file_name))
+    }
+
+    /// Returns true if the file is compressed by the Windows Overlay Filter (WOF),
+    /// either by a WOF reparse point or a `WofCompressedData` stream.
+    ///
+    /// The unnamed $DATA of such files is sparse, the actual content is compressed in the `WofCompressedData` stream.
+    pub fn is_wof_compressed(&self) -> bool {
+        self.iter_attributes_matching(Some(vec![
+            MftAttributeType::DATA,
+            MftAttributeType::ReparsePoint,
+        ]))
+        .filter_map(Result::ok)
+        .any(|a| match a.header.type_code {
+            MftAttributeType::DATA => a.header.name == WOF_COMPRESSED_DATA_STREAM_NAME,
+            _ => a
+                .data
+                .into_reparse_point()
+                .is_some_and(|reparse_point| reparse_point.reparse_tag == ReparseTag::Wof),
+        })
+    }
+
+    /// Returns the children of a directory, which are the `$FILE_NAME` keys of its `$I30` index.
+    ///
+    /// Small indexes are stored entirely in the `$INDEX_ROOT` attribute.
+    /// Large indexes (with `IndexRootFlags::LARGE_INDEX`) store most of their entries in the `INDX` records
+    /// of the `$INDEX_ALLOCATION` attribute, which live outside of the MFT,
+    /// so `read_clusters(lcn, count)` must return the content of `count` clusters of the volume starting at `lcn`.
+    /// It is only called for large indexes.
+    ///
+    /// Records which the `$BITMAP` of the index marks as unused are skipped, as they may hold stale entries.
+    /// Files with a short (DOS) name have an index entry for each of their names, so they are returned more than once.
+    pub fn iter_directory_children<F>(
+        &self,
+        mut read_clusters: F,
+    ) -> Result<impl Iterator<Item = FileNameAttr>>
+    where
+        F: FnMut(u64, u64) -> Result<Vec<u8>>,
+    {
+        let mut index_root = None;
+        let mut allocation_runs = vec![];
+        let mut bitmap = None;
+
+        // Custom decoders are not applied, they are not needed for any of these attributes.
+        for attribute in iter_attributes_matching(
+            &self.data,
+            &self.header,
+            Some(vec![
+                MftAttributeType::IndexRoot,
+                MftAttributeType::IndexAllocation,
+                MftAttributeType::BITMAP,
+            ]),
+        ) {
+            let attribute = attribute?;
+            if attribute.header.name != DIRECTORY_INDEX_NAME {
+                continue;
             }
 
-            end_of_sector_bytes.copy_from_slice(fixup_bytes);
+            match attribute.data {
+                MftAttributeContent::AttrX90(root) => index_root = Some(root),
+                MftAttributeContent::DataRun(runs)
+                    if attribute.header.type_code == MftAttributeType::IndexAllocation =>
+                {
+                    allocation_runs.extend(runs.data_runs)
+                }
+                MftAttributeContent::DataRun(runs) => {
+                    bitmap = Some(BitmapAttr {
+                        data: read_runs(&runs.data_runs, &mut read_clusters)?,
+                    })
+                }
+                MftAttributeContent::AttrXB0(resident_bitmap) => bitmap = Some(resident_bitmap),
+                _ => {}
+            }
+        }
+
+        let index_root = match index_root {
+            Some(index_root) => index_root,
+            None => return Ok(Vec::new().into_iter()),
+        };
+
+        let mut children: Vec<FileNameAttr> = index_root
+            .index_entries
+            .index_entries
+            .into_iter()
+            .map(|entry| entry.fname_info)
+            .collect();
+
+        if !index_root
+            .index_root_flags
+            .contains(IndexRootFlags::LARGE_INDEX)
+        {
+            return Ok(children.into_iter());
+        }
+
+        let record_size = index_root.index_entry_size as usize;
+        // Index records are made of whole strides (for the fixups), and must hold at least a header.
+        if record_size < INDEX_RECORD_HEADER_SIZE
+            || !record_size.is_multiple_of(SEQUENCE_NUMBER_STRIDE)
+        {
+            return Err(Error::InvalidIndexRecord {
+                detail: format!(
+                    "the index root has an invalid index record size of {}",
+                    record_size
+                ),
+            });
+        }
+
+        let mut allocation = read_runs(&allocation_runs, &mut read_clusters)?;
+
+        for (record_number, record) in allocation.chunks_exact_mut(record_size).enumerate() {
+            let in_use = match bitmap {
+                Some(ref bitmap) => bitmap.is_set(record_number as u64),
+                // Without a bitmap, assume that records which were never written are all zeros.
+                None => &record[..4] == INDX_SIGNATURE,
+            };
+
+            if in_use {
+                children.extend(
+                    parse_indx_record(record)?
+                        .into_iter()
+                        .map(|entry| entry.fname_info),
+                );
+            }
         }
 
-        Ok(valid_fixup)
+        Ok(children.into_iter())
     }
 
     pub fn is_allocated(&self) -> bool {
@@ -297,97 +760,547 @@ impl MftEntry {
         self.header.flags.bits() & 0x02 != 0
     }
 
+    /// Records 12-23 are reserved by Windows for future metadata files, following the system files in records 0-11.
+    /// They are usually empty, but some versions of Windows use them for children of `$Extend` (such as `$Quota`).
+    pub fn is_reserved_record(&self) -> bool {
+        RESERVED_RECORDS.contains(&self.header.record_number)
+    }
+
+    /// Returns true if `attribute` (an attribute of this entry) is the `$BadClus:$Bad` stream.
+    ///
+    /// The stream is a sparse $DATA attribute as large as the whole volume, in which only the bad clusters are allocated,
+    /// so it should be skipped when extracting (or listing the sizes of) streams.
+    pub fn is_badclus_bad(&self, attribute: &MftAttribute) -> bool {
+        self.header.record_number == BADCLUS_ENTRY_NUMBER
+            && attribute.header.type_code == MftAttributeType::DATA
+            && attribute.header.name == BADCLUS_BAD_STREAM_NAME
+    }
+
     /// Returns an iterator over all the attributes of the entry.
     pub fn iter_attributes(&self) -> impl Iterator<Item = Result<MftAttribute>> + '_ {
         self.iter_attributes_matching(None)
     }
 
+    /// Returns an iterator over the headers of all the attributes of the entry.
+    /// The content of the attributes is not parsed, which makes this cheap for listing attributes.
+    pub fn attribute_headers(&self) -> impl Iterator<Item = Result<MftAttributeHeader>> + '_ {
+        iter_attribute_headers(&self.data, &self.header)
+    }
+
+    /// Returns the distinct types of the attributes of the entry, in the order they first appear.
+    /// Only the attribute headers are read, headers which fail to parse are skipped.
+    pub fn attribute_types(&self) -> Vec<MftAttributeType> {
+        let mut types: Vec<MftAttributeType> = vec![];
+
+        for header in self.attribute_headers().filter_map(Result::ok) {
+            if !types.contains(&header.type_code) {
+                types.push(header.type_code);
+            }
+        }
+
+        types
+    }
+
+    /// Parses only the attribute at position `index` (in attribute order).
+    /// Returns `None` if the entry has less than `index + 1` attributes.
+    ///
+    /// Together with `attribute_headers`, this allows listing attributes cheaply
+    /// and parsing the content of a specific attribute on demand.
+    pub fn attribute_at(&self, index: usize) -> Option<Result<MftAttribute>> {
+        let header = match self.attribute_headers().nth(index)? {
+            Ok(header) => header,
+            Err(e) => return Some(Err(e)),
+        };
+
+        let mut cursor = Cursor::new(&self.data);
+
+        if let Err(e) = cursor.seek(SeekFrom::Start(header.start_offset)) {
+            return Some(Err(e.into()));
+        }
+
+        // Re-reading the header leaves the cursor at the start of the attribute content.
+        let header = match MftAttributeHeader::from_stream(&mut cursor) {
+            Ok(header) => header.expect("Header was already read successfully"),
+            Err(e) => return Some(Err(e)),
+        };
+
+        Some(
+            Self::read_attribute_content(&mut cursor, &header).map(|data| {
+                self.attribute_decoders
+                    .decode(MftAttribute { header, data })
+            }),
+        )
+    }
+
     /// Returns an iterator over the attributes in the list given in `types`, skips other attributes.
     pub fn iter_attributes_matching(
         &self,
         types: Option<Vec<MftAttributeType>>,
     ) -> impl Iterator<Item = Result<MftAttribute>> + '_ {
-        let mut cursor = Cursor::new(&self.data);
-        let mut offset = u64::from(self.header.first_attribute_record_offset);
-        let mut exhausted = false;
-
-        std::iter::from_fn(move || {
-            // We use a loop here to allow skipping filtered attributes.
-            loop {
-                if exhausted {
-                    return None;
-                }
+        iter_attributes_matching(&self.data, &self.header, types)
+            .map(move |attribute| attribute.map(|a| self.attribute_decoders.decode(a)))
+    }
 
-                if let Err(e) = cursor.seek(SeekFrom::Start(offset)) {
-                    exhausted = true;
-                    return Some(Err(e.into()));
-                };
+    /// Reads the content of an attribute, the stream is expected to be positioned right after the header.
+    fn read_attribute_content<S: Read + Seek>(
+        stream: &mut S,
+        header: &MftAttributeHeader,
+    ) -> Result<MftAttributeContent> {
+        // Check if the header is resident, and if it is, read the attribute content.
+        match header.residential_header {
+            ResidentialHeader::Resident(ref resident) => {
+                // The content is aligned, so it does not always follow the name immediately.
+                stream.seek(SeekFrom::Start(
+                    header.start_offset + u64::from(resident.data_offset),
+                ))?;
+                MftAttributeContent::from_stream_resident(stream, header, resident)
+            }
+            ResidentialHeader::NonResident(ref resident) => {
+                MftAttributeContent::from_stream_non_resident(stream, header, resident)
+            }
+        }
+    }
+}
 
-                let header = MftAttributeHeader::from_stream(&mut cursor);
+impl<'a> MftEntryRef<'a> {
+    /// Initializes an MFT Entry from a slice, without taking ownership of the data.
+    /// The slice is expected to hold exactly one entry.
+    pub fn from_slice(buffer: &'a [u8], entry_number: u64) -> Result<MftEntryRef<'a>> {
+        let entry_header = EntryHeader::from_reader(&mut Cursor::new(buffer), entry_number)?;
+        trace!("Number of sectors: {:#?}", entry_header);
 
-                // Unexpected I/O error, return err and stop iterating
-                let header = match header {
-                    Ok(h) => h,
-                    Err(e) => {
-                        exhausted = true;
-                        return Some(Err(e));
-                    }
-                };
+        let mut data = Cow::Borrowed(buffer);
+
+        let valid_fixup = if entry_header.is_valid() {
+            Some(apply_fixups(&entry_header, &mut data)?)
+        } else {
+            None
+        };
+
+        Ok(MftEntryRef {
+            header: entry_header,
+            data,
+            valid_fixup,
+        })
+    }
+
+    /// Initializes an MFT Entry from a mutable slice, applying the fixups in place.
+    /// Unlike `from_slice`, the data is never copied.
+    pub fn from_mut_slice(buffer: &'a mut [u8], entry_number: u64) -> Result<MftEntryRef<'a>> {
+        let entry_header = EntryHeader::from_reader(&mut Cursor::new(&*buffer), entry_number)?;
+        trace!("Number of sectors: {:#?}", entry_header);
+
+        let valid_fixup = if entry_header.is_valid() {
+            Some(apply_fixups(&entry_header, buffer)?)
+        } else {
+            None
+        };
+
+        Ok(MftEntryRef {
+            header: entry_header,
+            data: Cow::Borrowed(buffer),
+            valid_fixup,
+        })
+    }
+
+    pub fn is_allocated(&self) -> bool {
+        self.header.flags.contains(EntryFlags::ALLOCATED)
+    }
+
+    pub fn is_dir(&self) -> bool {
+        self.header.flags.contains(EntryFlags::INDEX_PRESENT)
+    }
+
+    /// Returns an iterator over all the attributes of the entry.
+    pub fn iter_attributes(&self) -> impl Iterator<Item = Result<MftAttribute>> + '_ {
+        self.iter_attributes_matching(None)
+    }
+
+    /// Returns an iterator over the headers of all the attributes of the entry.
+    pub fn attribute_headers(&self) -> impl Iterator<Item = Result<MftAttributeHeader>> + '_ {
+        iter_attribute_headers(&self.data, &self.header)
+    }
+
+    /// Returns an iterator over the attributes in the list given in `types`, skips other attributes.
+    pub fn iter_attributes_matching(
+        &self,
+        types: Option<Vec<MftAttributeType>>,
+    ) -> impl Iterator<Item = Result<MftAttribute>> + '_ {
+        iter_attributes_matching(&self.data, &self.header, types)
+    }
+
+    /// Converts the entry to an owned `MftEntry`, copying the data if it is still borrowed.
+    pub fn into_owned(self) -> MftEntry {
+        MftEntry {
+            header: self.header,
+            data: self.data.into_owned(),
+            valid_fixup: self.valid_fixup,
+            attribute_decoders: AttributeDecoders::default(),
+        }
+    }
+}
+
+/// A base entry together with the extension entries referenced by its $ATTRIBUTE_LIST,
+/// see `MftParser::get_logical_entry`.
+#[derive(Debug, Clone)]
+pub struct LogicalMftEntry {
+    pub base: MftEntry,
+    /// The extension entries which were read, in the order they are first referenced.
+    pub extensions: Vec<MftEntry>,
+    unresolved_references: Vec<MftReference>,
+}
+
+impl LogicalMftEntry {
+    pub(crate) fn new(
+        base: MftEntry,
+        extensions: Vec<MftEntry>,
+        unresolved_references: Vec<MftReference>,
+    ) -> Self {
+        LogicalMftEntry {
+            base,
+            extensions,
+            unresolved_references,
+        }
+    }
 
-                let header = match header {
-                    Some(attribute_header) => attribute_header,
-                    // Header is 0xFFFF_FFFF, we are finished
-                    None => return None,
-                };
+    /// Returns the references of the extension entries which could not be loaded,
+    /// because they are beyond the end of the MFT (such as in a truncated acquisition), zeroed,
+    /// or were reused by another file.
+    pub fn unresolved_references(&self) -> Vec<MftReference> {
+        self.unresolved_references.clone()
+    }
+
+    /// Returns an iterator over the attributes of the base entry, followed by the attributes of the extension entries.
+    ///
+    /// Every unresolved reference yields an `Error::MissingExtensionRecord` after the attributes.
+    pub fn iter_attributes(&self) -> impl Iterator<Item = Result<MftAttribute>> + '_ {
+        self.base
+            .iter_attributes()
+            .chain(
+                self.extensions
+                    .iter()
+                    .flat_map(|extension| extension.iter_attributes()),
+            )
+            .chain(self.unresolved_references.iter().map(|reference| {
+                Err(Error::MissingExtensionRecord {
+                    entry: reference.entry,
+                    sequence: reference.sequence,
+                })
+            }))
+    }
+}
+
+/// Applies the update sequence array fixups of an entry, see `utils::apply_usa_fixups`.
+/// The returned result is true if all fixup blocks had the fixup array value, or
+/// false if a block's fixup value did not match the array's value (the fixups are applied regardless).
+fn apply_fixups<B: FixupBuffer + ?Sized>(header: &EntryHeader, buffer: &mut B) -> Result<bool> {
+    match apply_usa_fixups(buffer, header.usa_offset, header.usa_size) {
+        Ok(()) => Ok(true),
+        Err(e @ Error::FailedToApplyFixup { .. })
+        | Err(e @ Error::UpdateSequenceArrayOutOfBounds { .. }) => {
+            warn!("[entry: {}] {}", header.record_number, e);
+            Ok(false)
+        }
+        Err(e) => Err(e),
+    }
+}
+
+/// See `MftEntry::find_best_name_attribute_with_preference`.
+pub(crate) fn best_name_attribute(
+    file_name_attributes: Vec<FileNameAttr>,
+    prefer: FileNamespace,
+) -> Option<FileNameAttr> {
+    let is_in_namespace = |a: &FileNameAttr, namespace: &FileNamespace| {
+        a.namespace == *namespace
+            || (a.namespace == FileNamespace::Win32AndDos
+                && [FileNamespace::Win32, FileNamespace::DOS].contains(namespace))
+    };
+
+    let position = file_name_attributes
+        .iter()
+        .position(|a| is_in_namespace(a, &prefer))
+        // Try to find a human-readable filename
+        .or_else(|| {
+            file_name_attributes
+                .iter()
+                .position(|a| is_in_namespace(a, &FileNamespace::Win32))
+        })
+        // Try to take anything
+        .unwrap_or(0);
+
+    file_name_attributes.into_iter().nth(position)
+}
+
+/// See `MftEntry::data_size_consistency`, `data` is the unnamed $DATA attribute of the entry.
+pub(crate) fn data_size_consistency(
+    is_dir: bool,
+    best_name: Option<&FileNameAttr>,
+    data: Option<&MftAttribute>,
+) -> DataSizeConsistency {
+    if is_dir {
+        return DataSizeConsistency::NotApplicable;
+    }
+
+    // `physical_size` holds the real size of the file, `logical_size` holds the allocated size.
+    let file_name_size = match best_name {
+        Some(file_name) => file_name.physical_size,
+        None => return DataSizeConsistency::NotApplicable,
+    };
+
+    let data_size = match data.map(|a| &a.header.residential_header) {
+        Some(ResidentialHeader::Resident(resident)) => {
+            if resident.data_size == 0 && file_name_size > 0 {
+                return DataSizeConsistency::EmptyResidentData;
+            }
+            u64::from(resident.data_size)
+        }
+        // The sizes are only valid in the first extent of the attribute.
+        Some(ResidentialHeader::NonResident(non_resident)) if non_resident.vnc_first == 0 => {
+            non_resident.file_size
+        }
+        _ => return DataSizeConsistency::NotApplicable,
+    };
 
-                // Increment offset before moving header.
+    if file_name_size > data_size.saturating_add(SIGNIFICANT_SIZE_DIFFERENCE) {
+        DataSizeConsistency::SizeMismatch
+    } else {
+        DataSizeConsistency::Consistent
+    }
+}
+
+/// See `MftEntry::detect_timestomp`, `file_name` is the best $FILE_NAME attribute of the entry.
+pub(crate) fn timestomp_indicators(
+    standard_info: &StandardInfoAttr,
+    file_name: &FileNameAttr,
+) -> TimestompIndicators {
+    let mut indicators = TimestompIndicators::empty();
+    indicators.set(
+        TimestompIndicators::SI_CREATED_BEFORE_FN,
+        standard_info.created < file_name.created,
+    );
+    indicators.set(
+        TimestompIndicators::SI_MODIFIED_BEFORE_FN,
+        standard_info.modified < file_name.modified,
+    );
+    indicators.set(
+        TimestompIndicators::SI_ZEROED_SUBSECONDS,
+        standard_info.created.nanosecond() == 0 && standard_info.modified.nanosecond() == 0,
+    );
+
+    indicators
+}
+
+/// See `MftEntry::has_suspicious_name`.
+pub(crate) fn is_suspicious_name(name: &str) -> bool {
+    // The root directory is named `.`.
+    if name == "." {
+        return false;
+    }
+
+    name.ends_with([' ', '.'])
+        || name
+            .chars()
+            .any(|c| c.is_control() || BIDI_CONTROL_CHARACTERS.contains(&c))
+}
+
+/// Checks whether the attributes of the entry can be read, warns if the attribute offset is malformed.
+fn has_attributes(header: &EntryHeader) -> bool {
+    if header.is_valid() && !header.has_valid_attribute_offset() {
+        warn!(
+            "[entry: {}] first attribute offset ({}) points into the entry header, ignoring attributes",
+            header.record_number, header.first_attribute_record_offset
+        );
+    }
+
+    header.has_valid_attribute_offset()
+}
+
+fn iter_attribute_headers<'a>(
+    data: &'a [u8],
+    header: &EntryHeader,
+) -> impl Iterator<Item = Result<MftAttributeHeader>> + 'a {
+    let mut cursor = Cursor::new(data);
+    let mut offset = u64::from(header.first_attribute_record_offset);
+    let mut exhausted = !has_attributes(header);
+
+    std::iter::from_fn(move || {
+        if exhausted {
+            return None;
+        }
+
+        if let Err(e) = cursor.seek(SeekFrom::Start(offset)) {
+            exhausted = true;
+            return Some(Err(e.into()));
+        };
+
+        match MftAttributeHeader::from_stream(&mut cursor) {
+            Ok(Some(header)) => {
                 offset += u64::from(header.record_length);
+                Some(Ok(header))
+            }
+            // Header is 0xFFFF_FFFF, we are finished
+            Ok(None) => None,
+            // Unexpected I/O error, return err and stop iterating
+            Err(e) => {
+                exhausted = true;
+                Some(Err(e))
+            }
+        }
+    })
+}
 
-                // Skip attribute if filtered
-                if let Some(filter) = &types {
-                    if !filter.contains(&header.type_code) {
-                        continue;
-                    }
+/// Reads the clusters of `data_runs` (in order) with `read_clusters`.
+fn read_runs<F>(data_runs: &[DataRun], read_clusters: &mut F) -> Result<Vec<u8>>
+where
+    F: FnMut(u64, u64) -> Result<Vec<u8>>,
+{
+    let mut content = vec![];
+
+    for run in data_runs {
+        match run.run_type {
+            RunType::Standard => content.extend(read_clusters(run.lcn_offset, run.lcn_length)?),
+            RunType::Sparse => {
+                return Err(Error::InvalidIndexRecord {
+                    detail: "the index of the directory has a sparse run".to_string(),
+                })
+            }
+        }
+    }
+
+    Ok(content)
+}
+
+fn iter_attributes_matching<'a>(
+    data: &'a [u8],
+    header: &EntryHeader,
+    types: Option<Vec<MftAttributeType>>,
+) -> impl Iterator<Item = Result<MftAttribute>> + 'a {
+    let mut cursor = Cursor::new(data);
+    let mut offset = u64::from(header.first_attribute_record_offset);
+    let mut exhausted = !has_attributes(header);
+
+    std::iter::from_fn(move || {
+        // We use a loop here to allow skipping filtered attributes.
+        loop {
+            if exhausted {
+                return None;
+            }
+
+            if let Err(e) = cursor.seek(SeekFrom::Start(offset)) {
+                exhausted = true;
+                return Some(Err(e.into()));
+            };
+
+            let header = MftAttributeHeader::from_stream(&mut cursor);
+
+            // Unexpected I/O error, return err and stop iterating
+            let header = match header {
+                Ok(h) => h,
+                Err(e) => {
+                    exhausted = true;
+                    return Some(Err(e));
                 }
+            };
 
-                // Check if the header is resident, and if it is, read the attribute content.
-                let attribute_content = match header.residential_header {
-                    ResidentialHeader::Resident(ref resident) => {
-                        match MftAttributeContent::from_stream_resident(
-                            &mut cursor,
-                            &header,
-                            resident,
-                        ) {
-                            Ok(content) => content,
-                            Err(e) => return Some(Err(e)),
-                        }
-                    }
-                    ResidentialHeader::NonResident(ref resident) => {
-                        match MftAttributeContent::from_stream_non_resident(
-                            &mut cursor,
-                            &header,
-                            resident,
-                        ) {
-                            Ok(content) => content,
-                            Err(e) => return Some(Err(e)),
-                        }
-                    }
-                };
+            let header = match header {
+                Some(attribute_header) => attribute_header,
+                // Header is 0xFFFF_FFFF, we are finished
+                None => return None,
+            };
 
-                return Some(Ok(MftAttribute {
-                    header,
-                    data: attribute_content,
-                }));
+            // Increment offset before moving header.
+            offset += u64::from(header.record_length);
+
+            // Skip attribute if filtered
+            if let Some(filter) = &types {
+                if !filter.contains(&header.type_code) {
+                    continue;
+                }
             }
-        })
-    }
+
+            let attribute_content = match MftEntry::read_attribute_content(&mut cursor, &header) {
+                Ok(content) => content,
+                Err(e) => return Some(Err(e)),
+            };
+
+            return Some(Ok(MftAttribute {
+                header,
+                data: attribute_content,
+            }));
+        }
+    })
 }
 
 #[cfg(test)]
 mod tests {
-    use super::EntryHeader;
+    use super::{is_suspicious_name, EntryHeader, MftEntry, TimestompIndicators};
+    use crate::attribute::header::ResidentialHeader;
+    use crate::attribute::x30::FileNamespace;
+    use crate::attribute::MftAttributeType;
+    use crate::err::Error;
+    use crate::tests::fixtures::{indx_record, mft_sample, synthetic_entry_with_attributes};
+    use crate::MftParser;
     use std::io::Cursor;
 
+    /// 2020-01-01T00:00:00Z as a FILETIME.
+    const JAN_2020: u64 = 132_223_104_000_000_000;
+    /// 2005-01-01T00:00:00Z as a FILETIME.
+    const JAN_2005: u64 = 127_490_112_000_000_000;
+    const ONE_DAY: u64 = 864_000_000_000;
+
+    /// Builds an entry with a $STANDARD_INFORMATION attribute and a $FILE_NAME attribute,
+    /// each with the given creation and modification FILETIMEs.
+    fn entry_with_timestamps(si: (u64, u64), file_name: (u64, u64)) -> MftEntry {
+        let mut standard_info = vec![];
+        for timestamp in [si.0, si.1, si.1, si.1] {
+            standard_info.extend(timestamp.to_le_bytes());
+        }
+        // Flags, versions, class id, owner id, security id, quota and USN.
+        standard_info.extend([0; 40]);
+
+        let name: Vec<u8> = "file.txt"
+            .encode_utf16()
+            .flat_map(u16::to_le_bytes)
+            .collect();
+        let mut file_name_attribute = (5_u64 | (5 << 48)).to_le_bytes().to_vec();
+        for timestamp in [file_name.0, file_name.1, file_name.1, file_name.1] {
+            file_name_attribute.extend(timestamp.to_le_bytes());
+        }
+        // Allocated and real size, flags and reparse value.
+        file_name_attribute.extend([0; 8 + 8 + 4 + 4]);
+        file_name_attribute.push((name.len() / 2) as u8);
+        // Win32 namespace.
+        file_name_attribute.push(1);
+        file_name_attribute.extend(name);
+
+        let buffer = synthetic_entry_with_attributes(
+            1024,
+            40,
+            &[(0x10, &standard_info), (0x30, &file_name_attribute)],
+        );
+
+        MftEntry::from_buffer(buffer, 40).unwrap()
+    }
+
+    #[test]
+    fn test_iter_file_names() {
+        let mut parser = MftParser::from_path(mft_sample()).unwrap();
+        let entry = parser.get_entry(62).unwrap();
+
+        let names: Vec<(String, FileNamespace)> = entry
+            .iter_file_names()
+            .map(|file_name| (file_name.name, file_name.namespace))
+            .collect();
+
+        assert_eq!(
+            names,
+            vec![
+                ("REPOSI~1".to_string(), FileNamespace::DOS),
+                ("Repository".to_string(), FileNamespace::Win32),
+            ]
+        );
+    }
+
     #[test]
     fn mft_header_test_01() {
         let header_buffer: &[u8] = &[
@@ -414,4 +1327,131 @@ mod tests {
         assert_eq!(entry_header.first_attribute_id, 6);
         assert_eq!(entry_header.record_number, 38357);
     }
+
+    #[test]
+    fn test_is_suspicious_name() {
+        for name in [
+            "invoice.pdf ",
+            "invoice.pdf.",
+            "a\0b",
+            "tab\tname",
+            "invoice\u{202E}fdp.exe",
+        ] {
+            assert!(is_suspicious_name(name), "{:?}", name);
+        }
+
+        for name in [".", "$MFT", "invoice.pdf", "my file.txt", ".gitignore"] {
+            assert!(!is_suspicious_name(name), "{:?}", name);
+        }
+    }
+
+    #[test]
+    fn test_iter_directory_children_of_small_index() {
+        let mut parser = MftParser::from_path(mft_sample()).unwrap();
+        // `WINDOWS/system32/ras`, whose children all fit in the index root.
+        let directory = parser.get_entry(33).unwrap();
+
+        let children: Vec<_> = directory
+            .iter_directory_children(|_, _| panic!("A small index has no index allocation"))
+            .unwrap()
+            .collect();
+
+        assert_eq!(children.len(), 6);
+    }
+
+    #[test]
+    fn test_iter_directory_children_of_large_index() {
+        let mut parser = MftParser::from_path(mft_sample()).unwrap();
+        // The root directory, whose index allocation spans 2 clusters at LCN 688.
+        // Only the first record is in use according to the $I30 bitmap.
+        let directory = parser.get_entry(5).unwrap();
+
+        let names = [
+            (4, "$AttrDef"),
+            (8, "$BadClus"),
+            (6, "$Bitmap"),
+            (7, "$Boot"),
+            (11, "$Extend"),
+        ];
+
+        let children: Vec<_> = directory
+            .iter_directory_children(|lcn, count| {
+                assert_eq!((lcn, count), (688, 2));
+
+                let mut clusters = indx_record(&names);
+                // A stale record, which would fail to parse.
+                clusters.extend(vec![0xFF; 4096]);
+                Ok(clusters)
+            })
+            .unwrap()
+            .map(|file_name| file_name.name)
+            .collect();
+
+        assert_eq!(
+            children,
+            ["$AttrDef", "$BadClus", "$Bitmap", "$Boot", "$Extend"]
+        );
+    }
+
+    #[test]
+    fn test_iter_directory_children_with_invalid_record_size() {
+        let mut parser = MftParser::from_path(mft_sample()).unwrap();
+        let mut directory = parser.get_entry(5).unwrap();
+
+        let index_root = directory
+            .iter_attributes_matching(Some(vec![MftAttributeType::IndexRoot]))
+            .next()
+            .unwrap()
+            .unwrap();
+        let content_offset = match index_root.header.residential_header {
+            ResidentialHeader::Resident(ref header) => {
+                index_root.header.start_offset as usize + header.data_offset as usize
+            }
+            ResidentialHeader::NonResident(_) => panic!("The index root is always resident"),
+        };
+
+        // The index record size follows the indexed attribute type and the collation rule.
+        let record_size = content_offset + 8;
+        directory.data[record_size..record_size + 4].copy_from_slice(&2_u32.to_le_bytes());
+
+        assert!(matches!(
+            directory.iter_directory_children(|_, _| panic!("The record size is checked first")),
+            Err(Error::InvalidIndexRecord { .. })
+        ));
+    }
+
+    #[test]
+    fn test_detect_timestomp() {
+        // The $STANDARD_INFORMATION timestamps were backdated by 15 years, with whole seconds.
+        let entry = entry_with_timestamps((JAN_2005, JAN_2005), (JAN_2020 + 1234, JAN_2020 + 1234));
+
+        assert_eq!(
+            entry.detect_timestomp(),
+            Some(
+                TimestompIndicators::SI_CREATED_BEFORE_FN
+                    | TimestompIndicators::SI_MODIFIED_BEFORE_FN
+                    | TimestompIndicators::SI_ZEROED_SUBSECONDS
+            )
+        );
+    }
+
+    #[test]
+    fn test_detect_timestomp_of_untouched_entry() {
+        // A file modified a day after it was created.
+        let created = JAN_2020 + 1234;
+        let entry = entry_with_timestamps((created, created + ONE_DAY), (created, created));
+
+        assert_eq!(entry.detect_timestomp(), Some(TimestompIndicators::empty()));
+    }
+
+    #[test]
+    fn test_detect_timestomp_of_backdated_creation() {
+        // Only the creation time is backdated, but to a time with fractional seconds.
+        let entry = entry_with_timestamps((JAN_2005 + 1234, JAN_2020 + 1234), (JAN_2020, JAN_2020));
+
+        assert_eq!(
+            entry.detect_timestomp(),
+            Some(TimestompIndicators::SI_CREATED_BEFORE_FN)
+        );
+    }
 }
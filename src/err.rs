@@ -15,6 +15,18 @@ pub enum Error {
         path: PathBuf,
         source: std::io::Error,
     },
+    #[error("File is too small to be an MFT ({} bytes)", size)]
+    FileTooSmall { size: u64 },
+    #[error(
+        "The $MFT is WOF compressed, its records are stored compressed in the `WofCompressedData` stream \
+         and must be decompressed (for example by copying the file with the Windows API) before parsing"
+    )]
+    WofCompressedMft,
+    #[error(
+        "Unsupported entry size {}, expected a power of two between 1024 and 65536",
+        size
+    )]
+    UnsupportedEntrySize { size: u32 },
     #[error("Error while decoding name in filename attribute")]
     InvalidFilename,
     #[error(
@@ -22,6 +34,16 @@ pub enum Error {
         bad_sig
     )]
     InvalidEntrySignature { bad_sig: Vec<u8> },
+    #[error("Bad index record signature: {:x?}, expected b\"INDX\"", bad_sig)]
+    InvalidIndexRecordSignature { bad_sig: Vec<u8> },
+    #[error("Invalid index record: {}", detail)]
+    InvalidIndexRecord { detail: String },
+    #[error(
+        "Index entry length ({}) is shorter than its contents ({} bytes)",
+        length,
+        minimum_length
+    )]
+    InvalidIndexEntry { length: u16, minimum_length: u32 },
     #[error("Unknown `AttributeType`: {:04X}", attribute_type)]
     UnknownAttributeType { attribute_type: u32 },
     #[error("Unknown collation type {}", collation_type)]
@@ -41,6 +63,25 @@ pub enum Error {
         end_of_sector_bytes: Vec<u8>,
         fixup_bytes: Vec<u8>,
     },
+    #[error(
+        "Update sequence array (offset: {}, size: {}) is out of the bounds of the record ({} bytes)",
+        usa_offset,
+        usa_size,
+        record_size
+    )]
+    UpdateSequenceArrayOutOfBounds {
+        usa_offset: u16,
+        usa_size: u16,
+        record_size: usize,
+    },
+    #[error("Fixups of entry {} do not match its update sequence", entry_id)]
+    InvalidFixup { entry_id: u64 },
+    #[error(
+        "Extension entry {} (sequence {}) referenced by the attribute list could not be loaded",
+        entry,
+        sequence
+    )]
+    MissingExtensionRecord { entry: u64, sequence: u16 },
     #[error("Failed to read MftReference")]
     FailedToReadMftReference { source: winstructs::err::Error },
     #[error("Failed to read WindowsTime")]
@@ -49,6 +90,44 @@ pub enum Error {
     FailedToReadGuid { source: winstructs::err::Error },
     #[error("Failed to decode data runs")]
     FailedToDecodeDataRuns { bad_data_runs: Vec<u8> },
+    #[error(
+        "Data runs are not contiguous, expected VCN {} but found VCN {}",
+        expected_vcn,
+        found_vcn
+    )]
+    NonContiguousDataRuns { expected_vcn: u64, found_vcn: u64 },
+    #[error("Data runs end at VCN {}, which is not a valid VCN", vnc_last)]
+    InvalidLastVcn { vnc_last: u64 },
+    #[error("Entry {} has no $DATA stream named `{}`", entry_id, stream_name)]
+    MissingDataStream { entry_id: u64, stream_name: String },
+    #[error(
+        "Stream size ({} bytes) exceeds the size of its data runs ({} bytes)",
+        file_size,
+        runs_length
+    )]
+    StreamSizeExceedsDataRuns { file_size: u64, runs_length: u64 },
+    #[error(
+        "Data run (offset: {}, length: {}) is out of the bounds of the image ({} bytes)",
+        offset,
+        length,
+        image_size
+    )]
+    DataRunOutOfBounds {
+        offset: u64,
+        length: u64,
+        image_size: u64,
+    },
+    #[error(
+        "Data run (LCN: {}, length: {} clusters) overflows with clusters of {} bytes",
+        lcn_offset,
+        lcn_length,
+        bytes_per_cluster
+    )]
+    DataRunOverflow {
+        lcn_offset: u64,
+        lcn_length: u64,
+        bytes_per_cluster: u64,
+    },
     #[error("An unexpected error has occurred: {}", detail)]
     Any { detail: String },
 }
@@ -0,0 +1,109 @@
+//! Parsing of `INDX` records, the nodes of an `$INDEX_ALLOCATION` attribute.
+//!
+//! Index records are usually found by carving unallocated space or by reading the `$INDEX_ALLOCATION`
+//! of a directory, and each one holds `IndexEntryHeader`s like the `$INDEX_ROOT` attribute does.
+//! <https://github.com/libyal/libfsntfs/blob/main/documentation/New%20Technologies%20File%20System%20(NTFS).asciidoc#index-entry-record>
+use crate::attribute::x90::{IndexEntryHeader, IndexRecordHeader};
+use crate::err::{Error, Result};
+
+use std::io::{Cursor, Seek, SeekFrom};
+
+/// The signature of an index record.
+pub const INDX_SIGNATURE: &[u8; 4] = b"INDX";
+
+/// The offset of the index node header, which follows the record header.
+const INDEX_NODE_HEADER_OFFSET: usize = 0x18;
+
+/// Parses an `INDX` record, returning its (non terminating) index entries.
+///
+/// The signature is validated and the update sequence array fixups are applied to `buf` in place
+/// (see `IndexRecordHeader::from_buffer`), so `buf` holds the fixed up record afterwards.
+pub fn parse_indx_record(buf: &mut [u8]) -> Result<Vec<IndexEntryHeader>> {
+    let header = IndexRecordHeader::from_buffer(buf)?;
+
+    let entries_offset = header.entries_offset as usize;
+    let index_length = header.index_length as usize;
+
+    // Both are relative to the start of the index node header.
+    let entries_start = INDEX_NODE_HEADER_OFFSET + entries_offset;
+    let entries_end = (INDEX_NODE_HEADER_OFFSET + index_length).min(buf.len());
+
+    if entries_start > entries_end {
+        return Err(Error::InvalidIndexRecord {
+            detail: format!(
+                "entries offset {} is past the end of the index ({})",
+                entries_offset, index_length
+            ),
+        });
+    }
+
+    let mut cursor = Cursor::new(&buf[..entries_end]);
+    cursor.seek(SeekFrom::Start(entries_start as u64))?;
+
+    let mut entries = Vec::new();
+    while (cursor.position() as usize) < entries_end {
+        let start = cursor.position();
+
+        match IndexEntryHeader::from_stream(&mut cursor)? {
+            Some(entry) => entries.push(entry),
+            None => break,
+        }
+
+        // A zero length entry would be read forever.
+        if cursor.position() <= start {
+            break;
+        }
+    }
+
+    Ok(entries)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::parse_indx_record;
+    use crate::err::Error;
+    use crate::tests::fixtures::indx_record;
+
+    const ENTRIES: &[(u64, &str)] = &[(64, "a.txt"), (65, "b.txt")];
+
+    #[test]
+    fn test_parse_indx_record() {
+        let mut record = indx_record(ENTRIES);
+
+        let entries = parse_indx_record(&mut record).unwrap();
+
+        let names: Vec<_> = entries
+            .iter()
+            .map(|entry| (entry.mft_reference.entry, entry.fname_info.name.as_str()))
+            .collect();
+        assert_eq!(names, [(64, "a.txt"), (65, "b.txt")]);
+        assert_eq!(&record[4094..], &[0, 0]);
+    }
+
+    #[test]
+    fn test_parse_indx_record_with_bad_fixup() {
+        let mut record = indx_record(ENTRIES);
+        record[1022] = 0xFF;
+
+        let result = parse_indx_record(&mut record);
+
+        assert!(matches!(
+            result,
+            Err(Error::FailedToApplyFixup {
+                stride_number: 1,
+                ..
+            })
+        ));
+    }
+
+    #[test]
+    fn test_parse_indx_record_with_bad_signature() {
+        let mut record = indx_record(ENTRIES);
+        record[..4].copy_from_slice(b"FILE");
+
+        assert!(matches!(
+            parse_indx_record(&mut record),
+            Err(Error::InvalidIndexRecordSignature { .. })
+        ));
+    }
+}
@@ -9,14 +9,20 @@ pub use attribute::x10::StandardInfoAttr;
 pub use attribute::x30::FileNameAttr;
 pub use attribute::MftAttribute;
 
-pub use crate::mft::MftParser;
-pub use entry::{EntryHeader, MftEntry};
+pub use crate::mft::{MftParser, MftParserOptions};
+pub use entry::{EntryHeader, LogicalMftEntry, MftEntry, MftEntryRef};
 
+#[cfg(feature = "arrow")]
+pub mod arrow;
 pub mod attribute;
 pub mod csv;
 pub mod entry;
 pub mod err;
+pub mod index;
 pub mod mft;
+pub mod quota;
+pub mod sid;
+pub mod upcase;
 
 pub(crate) mod macros;
 pub(crate) mod utils;
@@ -11,3 +11,71 @@ macro_rules! impl_serialize_for_bitflags {
         }
     };
 }
+
+/// Deserializes bitflags from their `Debug` representation (`A | B`),
+/// which is what `impl_serialize_for_bitflags` produces, or from the numeric bits.
+#[macro_export]
+macro_rules! impl_deserialize_for_bitflags {
+    ($flags: ident) => {
+        impl<'de> serde::de::Deserialize<'de> for $flags {
+            fn deserialize<D>(deserializer: D) -> ::std::result::Result<Self, D::Error>
+            where
+                D: serde::de::Deserializer<'de>,
+            {
+                struct FlagsVisitor;
+
+                impl<'de> serde::de::Visitor<'de> for FlagsVisitor {
+                    type Value = $flags;
+
+                    fn expecting(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+                        write!(f, "flag names separated by `|`, or the numeric flag bits")
+                    }
+
+                    fn visit_u64<E>(self, bits: u64) -> ::std::result::Result<$flags, E>
+                    where
+                        E: serde::de::Error,
+                    {
+                        Ok($flags::from_bits_truncate(bits as _))
+                    }
+
+                    fn visit_str<E>(self, value: &str) -> ::std::result::Result<$flags, E>
+                    where
+                        E: serde::de::Error,
+                    {
+                        let value = value.trim();
+
+                        if let Ok(bits) = value.parse::<u64>() {
+                            return self.visit_u64(bits);
+                        }
+
+                        let mut flags = $flags::empty();
+
+                        if value.is_empty() || value == "(empty)" {
+                            return Ok(flags);
+                        }
+
+                        for name in value.split('|').map(str::trim) {
+                            // Unknown bits are written in hex.
+                            if let Some(hex) = name.strip_prefix("0x") {
+                                let bits = u64::from_str_radix(hex, 16).map_err(E::custom)?;
+                                flags |= $flags::from_bits_truncate(bits as _);
+                                continue;
+                            }
+
+                            let flag = (0..64)
+                                .map(|i| $flags::from_bits_truncate((1_u64 << i) as _))
+                                .find(|flag| !flag.is_empty() && format!("{:?}", flag) == name)
+                                .ok_or_else(|| E::custom(format!("unknown flag `{}`", name)))?;
+
+                            flags |= flag;
+                        }
+
+                        Ok(flags)
+                    }
+                }
+
+                deserializer.deserialize_any(FlagsVisitor)
+            }
+        }
+    };
+}
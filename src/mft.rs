@@ -1,14 +1,190 @@
-use crate::entry::MftEntry;
+use crate::attribute::custom::{AttributeDecoder, AttributeDecoders};
+use crate::attribute::data_run::{DataRun, RunType};
+use crate::attribute::header::{MftAttributeHeader, ResidentialHeader};
+use crate::attribute::non_resident_attr::combine_data_runs;
+use crate::attribute::x30::{FileNameAttr, FileNamespace};
+use crate::attribute::xb0::BitmapAttr;
+use crate::attribute::{MftAttributeContent, MftAttributeType};
+use crate::entry::{MftEntry, MftEntryRef, ZERO_HEADER};
 use crate::err::{Error, Result};
+use crate::upcase::UpCaseTable;
 
-use crate::EntryHeader;
+use crate::{EntryHeader, LogicalMftEntry, MftAttribute};
 use log::{debug, trace};
 
 use lru::LruCache;
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, HashMap, HashSet};
 use std::fs::{self, File};
-use std::io::{BufReader, Cursor, Read, Seek, SeekFrom};
+use std::io::{self, BufReader, Cursor, Read, Seek, SeekFrom};
 use std::num::NonZeroUsize;
 use std::path::{Path, PathBuf};
+use winstructs::ntfs::mft_reference::MftReference;
+
+/// The smallest entry size used by NTFS.
+const MINIMUM_ENTRY_SIZE: u64 = 1024;
+
+/// The largest entry size we support, entry sizes are powers of two.
+const MAXIMUM_ENTRY_SIZE: u64 = 65536;
+
+/// MFT entry 5 is the root directory.
+const ROOT_ENTRY_NUMBER: u64 = 5;
+
+/// The number of entries read (and then parsed in parallel) at a time by `MftParser::par_iter_entries`.
+#[cfg(feature = "rayon")]
+const PARALLEL_BATCH_SIZE: u64 = 4096;
+
+/// The default number of resolved paths kept by the parser, see `MftParser::with_cache_size`.
+const DEFAULT_CACHE_SIZE: usize = 1000;
+
+/// The number of entries (`$MFT`, `$MFTMirr`, `$LogFile` and `$Volume`) which are copied to `$MFTMirr`.
+pub const MIRRORED_ENTRIES: u64 = 4;
+
+/// The result of checking the parent reference of an entry, see `MftParser::check_parent_reference`.
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ParentRefStatus {
+    /// The parent exists, is a directory, and has a matching sequence number.
+    Valid,
+    /// The parent record number is beyond the number of records in the MFT.
+    OutOfRange,
+    /// The parent record was reused (or deleted) since the reference was written.
+    SequenceMismatch,
+    /// The parent record is not a directory.
+    NotADirectory,
+}
+
+/// A range of clusters claimed by more than one record, see `MftParser::find_cross_linked_clusters`.
+#[derive(Serialize, Debug, Clone, PartialEq, Eq)]
+pub struct ClusterConflict {
+    /// The first cluster (LCN) of the range.
+    pub lcn: u64,
+    /// The number of clusters in the range.
+    pub length: u64,
+    /// The (base) records which claim the range, in ascending order.
+    pub record_numbers: Vec<u64>,
+}
+
+/// Paths deeper than this are assumed to contain a cycle in their parent references.
+const MAXIMUM_PATH_DEPTH: usize = 1024;
+
+/// Whether `reference` still points at the record with the given sequence number and allocation state.
+///
+/// The sequence number of a record is incremented when it is freed, so a reference to a deleted directory
+/// which was not reused yet is off by one, and is still considered valid.
+fn is_reference_to(reference: MftReference, sequence: u16, is_allocated: bool) -> bool {
+    sequence == reference.sequence
+        || (!is_allocated && sequence == reference.sequence.wrapping_add(1))
+}
+
+/// The name and parent of a single record, see `PathIndex`.
+#[derive(Debug, Clone)]
+struct PathIndexNode {
+    parent: MftReference,
+    name: String,
+    is_dir: bool,
+    sequence: u16,
+    is_allocated: bool,
+}
+
+/// A resolved directory path kept by the parser, with the sequence number and allocation state of the directory,
+/// to validate the references of its children.
+#[derive(Debug, Clone)]
+struct CachedPath {
+    path: PathBuf,
+    sequence: u16,
+    is_allocated: bool,
+}
+
+/// Maps every record to its parent and name, to resolve full paths without reading the MFT again,
+/// see `MftParser::build_path_index`.
+#[derive(Debug, Clone, Default)]
+pub struct PathIndex {
+    nodes: HashMap<u64, PathIndexNode>,
+    /// Extension records, which have no name of their own, mapped to their base record.
+    base_records: HashMap<u64, u64>,
+}
+
+impl PathIndex {
+    /// Resolves the full path of a record, like `MftParser::get_full_path_for_entry`.
+    ///
+    /// Records whose parent is not a directory in the index (or was reused) resolve under `[Unknown]`,
+    /// and records with no parent (or which are part of a reference cycle) resolve under `[Orphaned]`.
+    /// Returns `None` if the record is not in the index.
+    pub fn full_path(&self, record_number: u64) -> Option<PathBuf> {
+        let record_number = self
+            .base_records
+            .get(&record_number)
+            .copied()
+            .unwrap_or(record_number);
+
+        let mut current = record_number;
+        let mut node = self.nodes.get(&current)?;
+        let mut components = vec![node.name.as_str()];
+
+        let prefix = loop {
+            let parent = node.parent.entry;
+
+            if parent == ROOT_ENTRY_NUMBER {
+                break None;
+            }
+
+            if parent == current || parent == 0 || components.len() > MAXIMUM_PATH_DEPTH {
+                break Some("[Orphaned]");
+            }
+
+            match self.nodes.get(&parent) {
+                Some(parent_node)
+                    if parent_node.is_dir
+                        && is_reference_to(
+                            node.parent,
+                            parent_node.sequence,
+                            parent_node.is_allocated,
+                        ) =>
+                {
+                    components.push(&parent_node.name);
+                    current = parent;
+                    node = parent_node;
+                }
+                _ => break Some("[Unknown]"),
+            }
+        };
+
+        let mut path = prefix.map(PathBuf::from).unwrap_or_default();
+        path.extend(components.into_iter().rev());
+
+        Some(path)
+    }
+
+    /// The number of named records in the index.
+    pub fn len(&self) -> usize {
+        self.nodes.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.nodes.is_empty()
+    }
+}
+
+/// Options for `MftParser::from_read_seek_with_options`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct MftParserOptions {
+    /// The offset of the MFT within the data, see `MftParser::from_read_seek_at`.
+    pub start_offset: u64,
+    /// The number of resolved paths to cache, see `MftParser::with_cache_size`.
+    pub cache_size: NonZeroUsize,
+    /// See `MftParser::with_fail_fast`.
+    pub fail_fast: bool,
+}
+
+impl Default for MftParserOptions {
+    fn default() -> Self {
+        MftParserOptions {
+            start_offset: 0,
+            cache_size: NonZeroUsize::new(DEFAULT_CACHE_SIZE).expect("1000 > 0"),
+            fail_fast: false,
+        }
+    }
+}
 
 pub struct MftParser<T: Read + Seek> {
     data: T,
@@ -16,7 +192,16 @@ pub struct MftParser<T: Read + Seek> {
     /// Instead this will be guessed by the entry size of the first entry.
     entry_size: u32,
     size: u64,
-    entries_cache: LruCache<u64, PathBuf>,
+    /// The offset of the MFT within `data`, non-zero when the MFT is read from inside a disk image.
+    start_offset: u64,
+    entries_cache: LruCache<u64, CachedPath>,
+    fail_fast: bool,
+    upcase_table: Option<UpCaseTable>,
+    /// Scratch buffer used by `get_entry_ref`, reused between calls.
+    entry_buffer: Vec<u8>,
+    attribute_decoders: AttributeDecoders,
+    /// The cluster size of the volume, see `with_bytes_per_cluster`.
+    bytes_per_cluster: Option<u64>,
 }
 
 impl MftParser<BufReader<File>> {
@@ -44,167 +229,2275 @@ impl MftParser<Cursor<Vec<u8>>> {
 }
 
 impl<T: Read + Seek> MftParser<T> {
-    pub fn from_read_seek(mut data: T, size: Option<u64>) -> Result<Self> {
-        // We use the first entry to guess the entry size for all the other records.
-        let first_entry = EntryHeader::from_reader(&mut data, 0)?;
+    pub fn from_read_seek(data: T, size: Option<u64>) -> Result<Self> {
+        Self::from_read_seek_at(data, size, 0)
+    }
+
+    /// Instantiates an instance of the parser from an MFT which starts at `start_offset` bytes into `data`,
+    /// such as the `$MFT` inside a raw disk (or partition) image, without having to extract it first.
+    ///
+    /// `size` is the size of the MFT in bytes. If it is not given, everything from `start_offset`
+    /// to the end of `data` is considered part of the MFT, so entries past the real end of the MFT will fail to parse.
+    /// Note that only the contiguous part of the MFT at `start_offset` is read, fragments stored elsewhere are not followed.
+    ///
+    /// The entry size is guessed from the first entry at `start_offset`, and all entry numbers are relative to it.
+    pub fn from_read_seek_at(data: T, size: Option<u64>, start_offset: u64) -> Result<Self> {
+        Self::from_read_seek_with_options(
+            data,
+            size,
+            MftParserOptions {
+                start_offset,
+                ..Default::default()
+            },
+        )
+    }
 
+    /// Instantiates an instance of the parser, configured by `options`.
+    pub fn from_read_seek_with_options(
+        mut data: T,
+        size: Option<u64>,
+        options: MftParserOptions,
+    ) -> Result<Self> {
+        let start_offset = options.start_offset;
         let size = match size {
             Some(sz) => sz,
-            None => data.seek(SeekFrom::End(0))?,
+            None => data.seek(SeekFrom::End(0))?.saturating_sub(start_offset),
         };
 
-        data.rewind()?;
+        // Anything smaller than a single entry cannot possibly be an MFT.
+        if size < MINIMUM_ENTRY_SIZE {
+            return Err(Error::FileTooSmall { size });
+        }
+
+        data.seek(SeekFrom::Start(start_offset))?;
+
+        // We use the first entry to guess the entry size for all the other records.
+        let first_entry = EntryHeader::from_reader(&mut data, 0)?;
+        let entry_size = first_entry.total_entry_size;
+
+        if !entry_size.is_power_of_two()
+            || !(MINIMUM_ENTRY_SIZE..=MAXIMUM_ENTRY_SIZE).contains(&u64::from(entry_size))
+        {
+            return Err(Error::UnsupportedEntrySize { size: entry_size });
+        }
+
+        // The records of a WOF compressed $MFT would be read as garbage, so we fail early with a clear error.
+        if size >= u64::from(entry_size) {
+            let mut first_entry_buffer = vec![0; entry_size as usize];
+            data.seek(SeekFrom::Start(start_offset))?;
+            data.read_exact(&mut first_entry_buffer)?;
+
+            if let Ok(first_entry) = MftEntry::from_buffer(first_entry_buffer, 0) {
+                if first_entry.is_wof_compressed() {
+                    return Err(Error::WofCompressedMft);
+                }
+            }
+        }
+
+        data.seek(SeekFrom::Start(start_offset))?;
 
         Ok(Self {
             data,
-            entry_size: first_entry.total_entry_size,
+            entry_size,
             size,
-            entries_cache: LruCache::new(NonZeroUsize::new(1000).expect("1000 > 0")),
+            start_offset,
+            entries_cache: LruCache::new(options.cache_size),
+            fail_fast: options.fail_fast,
+            upcase_table: None,
+            entry_buffer: vec![0; entry_size as usize],
+            attribute_decoders: AttributeDecoders::default(),
+            bytes_per_cluster: None,
         })
     }
 
+    /// When set, the entry iterators will stop after yielding the first error,
+    /// instead of yielding an error for each entry which failed to parse and moving on.
+    ///
+    /// This is useful for validating a freshly acquired image, where any error should abort the parse.
+    pub fn with_fail_fast(mut self, fail_fast: bool) -> Self {
+        self.fail_fast = fail_fast;
+        self
+    }
+
+    /// Sets the number of resolved paths (of directories, mostly) which are cached, 1000 by default.
+    ///
+    /// Resolving the path of an entry reads and parses all of its ancestors which are not in the cache,
+    /// so a larger cache trades memory for fewer re-parses during `get_full_path_for_entry`,
+    /// which helps on volumes with large directory trees. Paths which are already cached are kept, up to `size`.
+    pub fn with_cache_size(mut self, size: NonZeroUsize) -> Self {
+        self.entries_cache.resize(size);
+        self
+    }
+
+    /// Sets the cluster size of the volume, for an MFT which is read from inside a volume (or disk) image
+    /// with `from_read_seek_at`.
+    ///
+    /// This allows reading data which is stored outside of the MFT, such as the non-resident $BITMAP
+    /// used by `allocated_entries`. The start of the volume is found from `start_offset`
+    /// and the first data run of the `$MFT`.
+    pub fn with_bytes_per_cluster(mut self, bytes_per_cluster: u64) -> Self {
+        self.bytes_per_cluster = Some(bytes_per_cluster);
+        self
+    }
+
+    /// Registers a decoder for attributes of `type_code` which this crate leaves raw
+    /// (resident attributes without a built-in parser, such as vendor specific `$LOGGED_UTILITY_STREAM`s).
+    ///
+    /// Entries read by the parser afterwards yield the decoder output as `MftAttributeContent::Custom`,
+    /// which is also what they serialize to. If the decoder fails, the attribute is kept raw.
+    ///
+    /// Decoders are shared with the entries, which may be processed on other threads,
+    /// so they must be `Send + Sync` and may be called concurrently.
+    ///
+    /// ```
+    /// # use mft::MftParser;
+    /// # use mft::attribute::MftAttributeContent;
+    /// # let mut parser = MftParser::from_path("samples/MFT").unwrap();
+    /// // $LOGGED_UTILITY_STREAM, other than `$TXF_DATA`.
+    /// parser.register_attribute_decoder(0x100, Box::new(|data| {
+    ///     Ok(serde_json::json!({ "stream_length": data.len() }))
+    /// }));
+    ///
+    /// for entry in parser.iter_entries().filter_map(Result::ok) {
+    ///     for attribute in entry.iter_attributes().filter_map(Result::ok) {
+    ///         if let MftAttributeContent::Custom(value) = attribute.data {
+    ///             println!("{}: {}", entry.header.record_number, value);
+    ///         }
+    ///     }
+    /// }
+    /// ```
+    pub fn register_attribute_decoder(&mut self, type_code: u32, decoder: Box<AttributeDecoder>) {
+        self.attribute_decoders.register(type_code, decoder);
+    }
+
     pub fn get_entry_count(&self) -> u64 {
         self.size / u64::from(self.entry_size)
     }
 
+    /// The offset of an entry within the underlying data.
+    fn entry_offset(&self, entry_number: u64) -> u64 {
+        self.start_offset + entry_number * u64::from(self.entry_size)
+    }
+
+    /// Returns an iterator over the record numbers which the $BITMAP of the `$MFT` (entry 0) marks as in use,
+    /// which allows skipping the (unallocated) entries of large and mostly empty MFTs without parsing them.
+    ///
+    /// The bitmap is usually non-resident (on all but tiny volumes), and stored outside of the MFT,
+    /// so it is only read if the MFT is parsed from inside a volume image, see `with_bytes_per_cluster`.
+    /// If the bitmap is not available (or entry 0 is corrupt), all the record numbers are returned.
+    pub fn allocated_entries(&mut self) -> impl Iterator<Item = u64> {
+        let bitmap = match self.read_mft_bitmap() {
+            Ok(bitmap) => bitmap,
+            Err(e) => {
+                debug!("Failed to read the $BITMAP of the $MFT: {}", e);
+                None
+            }
+        };
+
+        if bitmap.is_none() {
+            debug!("The $BITMAP of the $MFT is not available, all the entries are considered allocated");
+        }
+
+        (0..self.get_entry_count()).filter(move |&i| match bitmap {
+            Some(ref bitmap) => bitmap.is_set(i),
+            None => true,
+        })
+    }
+
+    /// Reads the $BITMAP of the `$MFT`, `None` if it is missing,
+    /// or is non-resident and the cluster size of the volume is not known.
+    fn read_mft_bitmap(&mut self) -> Result<Option<BitmapAttr>> {
+        let bitmap = self
+            .get_entry(0)?
+            .iter_attributes_matching(Some(vec![MftAttributeType::BITMAP]))
+            .filter_map(Result::ok)
+            .find(|attribute| attribute.header.name.is_empty());
+
+        let (header, content) = match bitmap {
+            Some(MftAttribute {
+                header:
+                    MftAttributeHeader {
+                        residential_header: ResidentialHeader::NonResident(header),
+                        ..
+                    },
+                data: MftAttributeContent::DataRun(content),
+            }) => (header, content),
+            Some(attribute) => return Ok(attribute.data.into_bitmap()),
+            None => return Ok(None),
+        };
+
+        let bytes_per_cluster = match self.bytes_per_cluster {
+            Some(bytes_per_cluster) => bytes_per_cluster,
+            None => return Ok(None),
+        };
+
+        // The MFT starts at its first cluster into the volume.
+        let volume_offset = match self.mft_data_runs()?.first() {
+            Some(run) if run.run_type == RunType::Standard => run
+                .lcn_offset
+                .checked_mul(bytes_per_cluster)
+                .and_then(|mft_offset| self.start_offset.checked_sub(mft_offset)),
+            _ => None,
+        };
+        let volume_offset = volume_offset.ok_or_else(|| Error::Any {
+            detail: "The start of the volume is not part of the data".to_string(),
+        })?;
+
+        let mut volume = VolumeReader {
+            data: &mut self.data,
+            volume_offset,
+        };
+        let data = content.read_non_resident_data(&mut volume, &header, bytes_per_cluster)?;
+
+        Ok(Some(BitmapAttr { data }))
+    }
+
+    /// Sets the `$UpCase` table used for comparing file names (for example in `lookup_path`).
+    /// The table is stored outside of the MFT (in the $DATA of entry 10), so it must be read from the volume.
+    /// Without it, names are compared using the unicode uppercase rules, which may differ for non-ASCII names.
+    pub fn with_upcase_table(mut self, upcase_table: UpCaseTable) -> Self {
+        self.upcase_table = Some(upcase_table);
+        self
+    }
+
     /// Reads an entry from the MFT by entry number.
+    ///
+    /// The entry is read and parsed in the buffer used by `get_entry_ref`, and only copied once it parsed,
+    /// so entries which fail to parse are not allocated.
     pub fn get_entry(&mut self, entry_number: u64) -> Result<MftEntry> {
+        let attribute_decoders = self.attribute_decoders.clone();
+
+        self.get_entry_ref(entry_number).map(|entry| {
+            entry
+                .into_owned()
+                .with_attribute_decoders(attribute_decoders)
+        })
+    }
+
+    /// Returns the data runs of the `$MFT` itself (the unnamed $DATA attribute of entry 0), which locate all the
+    /// fragments of the MFT on the volume. This allows mapping a fragmented MFT inside a disk image,
+    /// where `from_read_seek_at` only reads the contiguous fragment at the start.
+    ///
+    /// Runs stored in extension entries (referenced by the $ATTRIBUTE_LIST of entry 0) are included,
+    /// as long as those entries are part of the data read by the parser.
+    ///
+    /// ```
+    /// # use mft::MftParser;
+    /// # use mft::attribute::data_run::RunType;
+    /// # let mut parser = MftParser::from_path("samples/MFT").unwrap();
+    /// // The cluster size is recorded in the boot sector of the volume.
+    /// let bytes_per_cluster = 2048;
+    ///
+    /// let mut vcn = 0;
+    /// for run in parser.mft_data_runs().unwrap() {
+    ///     if run.run_type == RunType::Standard {
+    ///         println!(
+    ///             "Entries from {} are stored at offset {} of the volume ({} clusters)",
+    ///             vcn * bytes_per_cluster / 1024,
+    ///             run.lcn_offset * bytes_per_cluster,
+    ///             run.lcn_length,
+    ///         );
+    ///     }
+    ///     vcn += run.lcn_length;
+    /// }
+    /// ```
+    pub fn mft_data_runs(&mut self) -> Result<Vec<DataRun>> {
+        let mft = self.get_logical_entry(0)?;
+
+        let attributes: Vec<MftAttribute> = mft
+            .iter_attributes()
+            .filter_map(Result::ok)
+            .filter(|a| a.header.type_code == MftAttributeType::DATA && a.header.name.is_empty())
+            .collect();
+
+        if attributes.is_empty() {
+            return Err(Error::MissingDataStream {
+                entry_id: 0,
+                stream_name: String::new(),
+            });
+        }
+
+        Ok(combine_data_runs(&attributes)?.data_runs)
+    }
+
+    /// Reads an entry along with the extension entries referenced by its (resident) $ATTRIBUTE_LIST,
+    /// for iterating over all the attributes of a file which doesn't fit in a single entry.
+    ///
+    /// Extension entries which can't be loaded (beyond the end of the MFT, zeroed, or belonging to another base entry
+    /// or an older sequence) don't fail the whole entry, see `LogicalMftEntry::unresolved_references`.
+    /// A non-resident $ATTRIBUTE_LIST is stored outside of the MFT, so its extension entries are not loaded.
+    pub fn get_logical_entry(&mut self, entry_number: u64) -> Result<LogicalMftEntry> {
+        let base = self.get_entry(entry_number)?;
+
+        let mut references: Vec<MftReference> = vec![];
+        for attribute_list in base
+            .iter_attributes_matching(Some(vec![MftAttributeType::AttributeList]))
+            .filter_map(Result::ok)
+            .filter_map(|a| a.data.into_attribute_list())
+        {
+            for list_entry in attribute_list.entries {
+                let reference = list_entry.segment_reference;
+
+                // Attributes which are stored in the base entry itself are also listed.
+                if reference.entry != entry_number && !references.contains(&reference) {
+                    references.push(reference);
+                }
+            }
+        }
+
+        let mut extensions = vec![];
+        let mut unresolved_references = vec![];
+
+        for reference in references {
+            match self.get_entry(reference.entry) {
+                Ok(extension)
+                    if extension.header.is_valid()
+                        && extension.header.base_reference.entry == entry_number
+                        && extension.header.sequence == reference.sequence =>
+                {
+                    extensions.push(extension)
+                }
+                Ok(_) => unresolved_references.push(reference),
+                Err(e) => {
+                    debug!("Failed to read extension entry {}: {}", reference.entry, e);
+                    unresolved_references.push(reference)
+                }
+            }
+        }
+
+        Ok(LogicalMftEntry::new(
+            base,
+            extensions,
+            unresolved_references,
+        ))
+    }
+
+    /// Reads an entry from the MFT by entry number, without allocating.
+    ///
+    /// The entry is read into a buffer owned by the parser, so it borrows the parser until it is dropped.
+    /// Use `MftEntryRef::into_owned` to keep it around.
+    pub fn get_entry_ref(&mut self, entry_number: u64) -> Result<MftEntryRef<'_>> {
         debug!("Reading entry {}", entry_number);
 
         self.data
-            .seek(SeekFrom::Start(entry_number * u64::from(self.entry_size)))?;
-        let mut entry_buffer = vec![0; self.entry_size as usize];
+            .seek(SeekFrom::Start(self.entry_offset(entry_number)))?;
+        self.data.read_exact(&mut self.entry_buffer)?;
 
-        self.data.read_exact(&mut entry_buffer)?;
-
-        MftEntry::from_buffer(entry_buffer, entry_number)
+        MftEntryRef::from_mut_slice(&mut self.entry_buffer, entry_number)
     }
 
     /// Iterates over all the entries in the MFT.
+    /// If `fail_fast` is set, iteration stops after the first error.
     pub fn iter_entries(&mut self) -> impl Iterator<Item = Result<MftEntry>> + '_ {
         let total_entries = self.get_entry_count();
+        let fail_fast = self.fail_fast;
+
+        stop_after_error(
+            (0..total_entries).map(move |i| self.get_entry(i)),
+            fail_fast,
+        )
+    }
+
+    /// Iterates over all the entries in the MFT like `iter_entries`, parsing them on the rayon thread pool.
+    ///
+    /// Entries are read from the underlying data sequentially, `PARALLEL_BATCH_SIZE` entries at a time,
+    /// and each batch is parsed in parallel. Entries are still yielded in record order.
+    /// Paths are not resolved, they can be resolved afterwards with `get_full_path_for_entry`.
+    /// If `fail_fast` is set, iteration stops after the first error.
+    #[cfg(feature = "rayon")]
+    pub fn par_iter_entries(&mut self) -> impl Iterator<Item = Result<MftEntry>> + '_ {
+        use rayon::prelude::*;
+
+        let total_entries = self.get_entry_count();
+        let fail_fast = self.fail_fast;
+        let entry_size = self.entry_size as usize;
+
+        let entries = (0..total_entries)
+            .step_by(PARALLEL_BATCH_SIZE as usize)
+            .flat_map(move |first_entry| {
+                let last_entry = (first_entry + PARALLEL_BATCH_SIZE).min(total_entries);
+
+                let mut batch = vec![0; (last_entry - first_entry) as usize * entry_size];
+                let offset = self.entry_offset(first_entry);
+                if let Err(e) = self
+                    .data
+                    .seek(SeekFrom::Start(offset))
+                    .and_then(|_| self.data.read_exact(&mut batch))
+                {
+                    return vec![Err(e.into())];
+                }
+
+                let attribute_decoders = &self.attribute_decoders;
+                batch
+                    .par_chunks_exact(entry_size)
+                    .enumerate()
+                    .map(|(i, entry_buffer)| {
+                        MftEntry::from_buffer(entry_buffer.to_vec(), first_entry + i as u64)
+                            .map(|entry| entry.with_attribute_decoders(attribute_decoders.clone()))
+                    })
+                    .collect()
+            });
+
+        stop_after_error(entries, fail_fast)
+    }
+
+    /// Iterates over all the entries in the MFT, starting from the last entry.
+    /// If `fail_fast` is set, iteration stops after the first error.
+    pub fn iter_entries_rev(&mut self) -> impl Iterator<Item = Result<MftEntry>> + '_ {
+        let total_entries = self.get_entry_count();
+        let fail_fast = self.fail_fast;
+
+        stop_after_error(
+            (0..total_entries).rev().map(move |i| self.get_entry(i)),
+            fail_fast,
+        )
+    }
+
+    /// Scans the entries in order, and returns the number of the first entry which fails to parse
+    /// (including its attributes, or a mismatched fixup) together with the error.
+    /// Returns `None` if all the entries parse, zeroed (unused) entries are considered valid.
+    ///
+    /// The scan stops at the first invalid entry. I/O errors are returned as errors, since they do not
+    /// indicate a problem with a specific entry.
+    pub fn first_invalid_entry(&mut self) -> Result<Option<(u64, Error)>> {
+        for i in 0..self.get_entry_count() {
+            let entry = match self.get_entry(i) {
+                Ok(entry) => entry,
+                Err(e @ Error::IoError { .. }) => return Err(e),
+                Err(e) => return Ok(Some((i, e))),
+            };
+
+            if entry.valid_fixup == Some(false) {
+                return Ok(Some((i, Error::InvalidFixup { entry_id: i })));
+            }
+
+            let attribute_error = entry.iter_attributes().find_map(Result::err);
+            if let Some(e) = attribute_error {
+                return Ok(Some((i, e)));
+            }
+        }
+
+        Ok(None)
+    }
+
+    /// Iterates over all the entries in the MFT, together with their full path (see `get_full_path_for_entry`).
+    /// Zeroed entries are skipped.
+    /// If `fail_fast` is set, iteration stops after the first error.
+    pub fn iter_entries_with_paths(
+        &mut self,
+    ) -> impl Iterator<Item = Result<(MftEntry, Option<PathBuf>)>> + '_ {
+        let total_entries = self.get_entry_count();
+        let fail_fast = self.fail_fast;
+
+        let entries = (0..total_entries).filter_map(move |i| {
+            let entry = match self.get_entry(i) {
+                Ok(entry) if &entry.header.signature == ZERO_HEADER => return None,
+                Ok(entry) => entry,
+                Err(e) => return Some(Err(e)),
+            };
+
+            Some(
+                self.get_full_path_for_entry(&entry)
+                    .map(|path| (entry, path)),
+            )
+        });
 
-        (0..total_entries).map(move |i| self.get_entry(i))
+        stop_after_error(entries, fail_fast)
     }
 
-    fn inner_get_entry(&mut self, parent_entry_id: u64, entry_name: Option<&str>) -> PathBuf {
-        let cached_entry = self.entries_cache.get(&parent_entry_id);
+    /// Iterates over all the attributes of all the entries in the MFT,
+    /// yielding `(record_number, attribute)` pairs.
+    /// Zeroed entries are skipped.
+    pub fn iter_all_attributes(
+        &mut self,
+    ) -> impl Iterator<Item = Result<(u64, MftAttribute)>> + '_ {
+        self.iter_entries().flat_map(|entry| {
+            let attributes: Vec<Result<(u64, MftAttribute)>> = match entry {
+                Ok(entry) if &entry.header.signature == ZERO_HEADER => vec![],
+                Ok(entry) => {
+                    let record_number = entry.header.record_number;
+                    entry
+                        .iter_attributes()
+                        .map(|attribute| attribute.map(|a| (record_number, a)))
+                        .collect()
+                }
+                Err(e) => vec![Err(e)],
+            };
+
+            attributes
+        })
+    }
 
+    /// Resolves the path of the directory `parent`, joined with `entry_name`.
+    ///
+    /// If the parent record was reused since the reference was written, the path resolves under `[Unknown]`.
+    fn inner_get_entry(&mut self, parent: MftReference, entry_name: Option<&str>) -> PathBuf {
         // If my parent path is known, then my path is parent's full path + my name.
         // Else, retrieve and cache my parent's path.
-        if let Some(cached_parent_path) = cached_entry {
-            match entry_name {
-                Some(name) => cached_parent_path.clone().join(name),
-                None => cached_parent_path.clone(),
-            }
-        } else {
-            let path = match self.get_entry(parent_entry_id).ok() {
-                Some(parent) => match self.get_full_path_for_entry(&parent) {
-                    Ok(Some(path)) if parent.is_dir() => path,
-                    Ok(Some(_)) => PathBuf::from("[Unknown]"),
-                    // I have a parent, which doesn't have a filename attribute.
-                    // Default to root.
-                    _ => PathBuf::new(),
-                },
+        if !self.entries_cache.contains(&parent.entry) {
+            let parent_entry = match self.get_entry(parent.entry) {
+                Ok(parent_entry) => parent_entry,
                 // Parent is maybe corrupted or incomplete, use a sentinel instead.
-                None => PathBuf::from("[Unknown]"),
+                Err(_) => return join_name(PathBuf::from("[Unknown]"), entry_name),
             };
 
-            self.entries_cache.put(parent_entry_id, path.clone());
-            match entry_name {
-                Some(name) => path.join(name),
-                None => path,
-            }
+            let path = match self.get_full_path_for_entry(&parent_entry) {
+                Ok(Some(path)) if parent_entry.is_dir() => path,
+                Ok(Some(_)) => PathBuf::from("[Unknown]"),
+                // I have a parent, which doesn't have a filename attribute.
+                // Default to root.
+                _ => PathBuf::new(),
+            };
+
+            self.entries_cache.put(
+                parent.entry,
+                CachedPath {
+                    path,
+                    sequence: parent_entry.header.sequence,
+                    is_allocated: parent_entry.is_allocated(),
+                },
+            );
+        }
+
+        let cached_parent = self
+            .entries_cache
+            .get(&parent.entry)
+            .expect("Parent path was just cached");
+
+        if !is_reference_to(parent, cached_parent.sequence, cached_parent.is_allocated) {
+            trace!(
+                "Parent entry ID {} was reused (sequence {}, referenced as {})",
+                parent.entry,
+                cached_parent.sequence,
+                parent.sequence
+            );
+            return join_name(PathBuf::from("[Unknown]"), entry_name);
+        }
+
+        // `join` allocates the joined path once, cloning the cached path first would allocate twice.
+        match entry_name {
+            Some(name) => cached_parent.path.join(name),
+            None => cached_parent.path.clone(),
+        }
+    }
+
+    /// Resolves the path of a single $FILE_NAME attribute of entry `entry_id`.
+    fn path_for_file_name(&mut self, entry_id: u64, filename_header: FileNameAttr) -> PathBuf {
+        let parent = filename_header.parent;
+        let parent_entry_id = parent.entry;
+
+        // MFT entry 5 is the root path.
+        if parent_entry_id == 5 {
+            return PathBuf::from(filename_header.name);
+        }
+
+        if parent_entry_id == entry_id {
+            trace!(
+                "Found self-referential file path, for entry ID {}",
+                entry_id
+            );
+            return PathBuf::from("[Orphaned]").join(filename_header.name);
+        }
+
+        if parent_entry_id > 0 {
+            self.inner_get_entry(parent, Some(&filename_header.name))
+        } else {
+            trace!("Found orphaned entry ID {}", entry_id);
+
+            PathBuf::from("[Orphaned]").join(filename_header.name)
         }
     }
 
     /// Gets the full path for an entry.
+    /// Entries whose parent directory record was reused since the reference was written resolve under `[Unknown]`.
     /// Caches computations.
     pub fn get_full_path_for_entry(&mut self, entry: &MftEntry) -> Result<Option<PathBuf>> {
+        Ok(self.full_path_for_best_name(entry, entry.find_best_name_attribute()))
+    }
+
+    /// `get_full_path_for_entry`, with the best name of the entry already looked up.
+    pub(crate) fn full_path_for_best_name(
+        &mut self,
+        entry: &MftEntry,
+        best_name: Option<FileNameAttr>,
+    ) -> Option<PathBuf> {
         let entry_id = entry.header.record_number;
-        match entry.find_best_name_attribute() {
-            Some(filename_header) => {
-                let parent_entry_id = filename_header.parent.entry;
+        match best_name {
+            Some(filename_header) => Some(self.path_for_file_name(entry_id, filename_header)),
+            None => match entry.header.base_reference.entry {
+                // I don't have a parent reference, and no X30 attribute. Though luck.
+                0 => None,
+                _ => Some(self.inner_get_entry(entry.header.base_reference, None)),
+            },
+        }
+    }
 
-                // MFT entry 5 is the root path.
-                if parent_entry_id == 5 {
-                    return Ok(Some(PathBuf::from(filename_header.name)));
-                }
+    /// Gets the full path of one of the names of an entry.
+    /// Unlike `get_full_path_for_entry`, which only resolves the best name, this can resolve every hard link of a file.
+    /// Caches computations.
+    pub fn get_full_path_for_file_name(
+        &mut self,
+        entry: &MftEntry,
+        file_name: FileNameAttr,
+    ) -> PathBuf {
+        // Names can be spread over extension entries, they all belong to the base entry.
+        let record_number = match entry.header.base_reference.entry {
+            0 => entry.header.record_number,
+            base => base,
+        };
 
-                if parent_entry_id == entry_id {
-                    trace!(
-                        "Found self-referential file path, for entry ID {}",
-                        entry_id
-                    );
-                    return Ok(Some(PathBuf::from("[Orphaned]").join(filename_header.name)));
-                }
+        self.path_for_file_name(record_number, file_name)
+    }
+
+    /// Gets the full paths of all the names of an entry, one for each hard link, in attribute order.
+    /// Short (DOS) names are skipped, since they are aliases of a long name in the same directory.
+    ///
+    /// Extension entries (which have no $FILE_NAME attribute) resolve to the paths of their base entry.
+    /// Caches computations.
+    pub fn get_all_paths_for_entry(&mut self, entry: &MftEntry) -> Result<Vec<PathBuf>> {
+        let file_names: Vec<FileNameAttr> = entry
+            .iter_file_names()
+            .filter(|file_name| file_name.namespace != FileNamespace::DOS)
+            .collect();
 
-                if parent_entry_id > 0 {
-                    Ok(Some(self.inner_get_entry(
-                        parent_entry_id,
-                        Some(&filename_header.name),
-                    )))
-                } else {
-                    trace!("Found orphaned entry ID {}", entry_id);
+        if file_names.is_empty() {
+            return match entry.header.base_reference.entry {
+                0 => Ok(vec![]),
+                base_entry_id => {
+                    let base_entry = self.get_entry(base_entry_id)?;
+                    // The base entry of an extension entry is never an extension entry itself.
+                    if base_entry.header.base_reference.entry != 0 {
+                        return Ok(vec![]);
+                    }
+                    self.get_all_paths_for_entry(&base_entry)
+                }
+            };
+        }
 
-                    let orphan = PathBuf::from("[Orphaned]").join(filename_header.name);
+        Ok(file_names
+            .into_iter()
+            .map(|file_name| self.get_full_path_for_file_name(entry, file_name))
+            .collect())
+    }
 
-                    self.entries_cache
-                        .put(entry.header.record_number, orphan.clone());
+    /// Gets the full path of the directory containing an entry, without the entry's own name.
+    /// Files in the root directory have an empty parent path.
+    ///
+    /// Extension entries (which have no $FILE_NAME attribute) resolve to the parent directory of their base entry.
+    /// Caches computations.
+    pub fn get_parent_path_for_entry(&mut self, entry: &MftEntry) -> Result<Option<PathBuf>> {
+        let entry_id = entry.header.record_number;
 
-                    Ok(Some(orphan))
-                }
-            }
+        let filename_header = match entry.find_best_name_attribute() {
+            Some(filename_header) => filename_header,
             None => match entry.header.base_reference.entry {
-                // I don't have a parent reference, and no X30 attribute. Though luck.
-                0 => Ok(None),
-                parent_entry_id => Ok(Some(self.inner_get_entry(parent_entry_id, None))),
+                0 => return Ok(None),
+                base_entry_id => match self.get_entry(base_entry_id)?.find_best_name_attribute() {
+                    Some(filename_header) => filename_header,
+                    None => return Ok(None),
+                },
             },
+        };
+
+        let parent_entry_id = filename_header.parent.entry;
+
+        // MFT entry 5 is the root path.
+        if parent_entry_id == 5 {
+            return Ok(Some(PathBuf::new()));
+        }
+
+        if parent_entry_id == entry_id || parent_entry_id == 0 {
+            return Ok(Some(PathBuf::from("[Orphaned]")));
         }
+
+        Ok(Some(self.inner_get_entry(filename_header.parent, None)))
     }
-}
 
-#[cfg(test)]
-mod tests {
-    use crate::tests::fixtures::mft_sample;
-    use crate::{MftEntry, MftParser};
+    /// Gets the references to the ancestors of an entry, root first, as recorded in the `$FILE_NAME` attributes
+    /// along the way (so the sequence numbers can be validated against the actual parents).
+    /// The entry itself is not part of the chain.
+    ///
+    /// The walk stops at the root (which is included), at a parent which cannot be read or has no name,
+    /// and at a reference cycle, in which case the chain does not reach the root.
+    /// Extension entries (which have no $FILE_NAME attribute) use the parents of their base entry.
+    pub fn get_parent_chain(&mut self, entry: &MftEntry) -> Result<Vec<MftReference>> {
+        let mut current = match entry.header.base_reference.entry {
+            0 => entry.header.record_number,
+            base_entry_id => base_entry_id,
+        };
 
-    // entrypoint for clion profiler.
-    #[test]
-    fn test_process_90_mft_entries() {
-        let sample = mft_sample();
+        let mut filename_header = match entry.find_best_name_attribute() {
+            Some(filename_header) => filename_header,
+            None if current != entry.header.record_number => {
+                match self.get_entry(current)?.find_best_name_attribute() {
+                    Some(filename_header) => filename_header,
+                    None => return Ok(vec![]),
+                }
+            }
+            None => return Ok(vec![]),
+        };
 
-        let mut parser = MftParser::from_path(sample).unwrap();
+        let mut chain = vec![];
+        let mut visited = HashSet::from([current]);
 
-        let mut count = 0;
-        for record in parser.iter_entries().take(10000).filter_map(|a| a.ok()) {
-            for _attribute in record.iter_attributes() {
-                count += 1;
+        loop {
+            let parent = filename_header.parent;
+
+            // A parent of 0 marks an orphan, see `path_for_file_name`.
+            if parent.entry == 0 || !visited.insert(parent.entry) {
+                trace!(
+                    "Parent chain of entry ID {} stopped at entry ID {}",
+                    current,
+                    parent.entry
+                );
+                break;
+            }
+
+            chain.push(parent);
+
+            if parent.entry == ROOT_ENTRY_NUMBER {
+                break;
             }
+
+            filename_header = match self
+                .get_entry(parent.entry)
+                .ok()
+                .and_then(|parent| parent.find_best_name_attribute())
+            {
+                Some(filename_header) => filename_header,
+                None => break,
+            };
+            current = parent.entry;
         }
 
-        assert!(count > 0)
+        chain.reverse();
+
+        Ok(chain)
     }
 
-    #[test]
-    fn test_get_full_path() {
-        let sample = mft_sample();
-        let mut parser = MftParser::from_path(sample).unwrap();
+    /// Scans the MFT once, collecting the parent and name of every record into a `PathIndex`,
+    /// which resolves full paths without any further I/O.
+    ///
+    /// This is much faster than `get_full_path_for_entry` when resolving the path of every entry.
+    /// Entries which fail to parse are skipped, unless `fail_fast` is set.
+    pub fn build_path_index(&mut self) -> Result<PathIndex> {
+        let mut index = PathIndex::default();
 
-        let mut paths = Vec::with_capacity(1000);
-        let entries: Vec<MftEntry> = parser
-            .iter_entries()
-            .take(1000)
-            .filter_map(Result::ok)
-            .collect();
+        for i in 0..self.get_entry_count() {
+            let entry = match self.get_entry(i) {
+                Ok(entry) => entry,
+                Err(e) if self.fail_fast => return Err(e),
+                Err(_) => continue,
+            };
 
-        for entry in entries {
-            if let Some(path) = parser.get_full_path_for_entry(&entry).unwrap() {
-                paths.push(path)
+            let record_number = entry.header.record_number;
+
+            match entry.find_best_name_attribute() {
+                Some(file_name) => {
+                    index.nodes.insert(
+                        record_number,
+                        PathIndexNode {
+                            parent: file_name.parent,
+                            name: file_name.name,
+                            is_dir: entry.is_dir(),
+                            sequence: entry.header.sequence,
+                            is_allocated: entry.is_allocated(),
+                        },
+                    );
+                }
+                None => match entry.header.base_reference.entry {
+                    0 => {}
+                    base => {
+                        index.base_records.insert(record_number, base);
+                    }
+                },
             }
         }
 
-        assert_eq!(paths.len(), 988);
+        Ok(index)
+    }
+
+    /// Groups the paths of all the entries which have more than one name (hard links),
+    /// keyed by the record number of the (base) entry.
+    ///
+    /// Short (DOS) names are not counted, since they are aliases of the long name in the same directory.
+    /// Entries which fail to parse are skipped, unless `fail_fast` is set.
+    pub fn hardlink_groups(&mut self) -> Result<HashMap<u64, Vec<PathBuf>>> {
+        let mut groups: HashMap<u64, Vec<PathBuf>> = HashMap::new();
+
+        for i in 0..self.get_entry_count() {
+            let entry = match self.get_entry(i) {
+                Ok(entry) => entry,
+                Err(e) if self.fail_fast => return Err(e),
+                Err(_) => continue,
+            };
+
+            // Names can be spread over extension entries, they all belong to the base entry.
+            let record_number = match entry.header.base_reference.entry {
+                0 => entry.header.record_number,
+                base => base,
+            };
+
+            for file_name in entry.file_names() {
+                if file_name.namespace == FileNamespace::DOS {
+                    continue;
+                }
+
+                let path = self.path_for_file_name(record_number, file_name);
+                groups.entry(record_number).or_default().push(path);
+            }
+        }
+
+        groups.retain(|_, paths| paths.len() > 1);
+
+        Ok(groups)
+    }
+
+    /// Finds ranges of clusters which are claimed by the non-resident attributes of more than one record,
+    /// which indicates cross-linked (corrupted) files.
+    ///
+    /// Only allocated entries are considered, since the clusters of deleted files may have been reused.
+    /// The extents of all the entries are collected before being compared,
+    /// so memory use is proportional to the total number of extents in the MFT.
+    /// Entries which fail to parse are skipped, unless `fail_fast` is set.
+    pub fn find_cross_linked_clusters(&mut self) -> Result<Vec<ClusterConflict>> {
+        // (first_lcn, length, record_number)
+        let mut extents = vec![];
+
+        for i in 0..self.get_entry_count() {
+            let entry = match self.get_entry(i) {
+                Ok(entry) => entry,
+                Err(e) if self.fail_fast => return Err(e),
+                Err(_) => continue,
+            };
+
+            if !entry.header.is_valid() || !entry.is_allocated() {
+                continue;
+            }
+
+            let record_number = match entry.header.base_reference.entry {
+                0 => entry.header.record_number,
+                base => base,
+            };
+
+            for data_runs in entry
+                .iter_attributes()
+                .filter_map(Result::ok)
+                .filter_map(|a| a.data.into_data_runs())
+            {
+                extents.extend(
+                    data_runs
+                        .data_runs
+                        .iter()
+                        .filter(|run| run.run_type == RunType::Standard && run.lcn_length > 0)
+                        .map(|run| (run.lcn_offset, run.lcn_length, record_number)),
+                );
+            }
+        }
+
+        Ok(find_cluster_conflicts(extents))
+    }
+
+    /// Returns the clusters occupied by the non-resident attributes of `root_record` and of every record under it,
+    /// as sorted `(first_lcn, length)` ranges, merging overlapping and contiguous ranges.
+    ///
+    /// This is useful for imaging only the data of a directory subtree.
+    /// The subtree is found by scanning the parent references of every allocated entry,
+    /// so a file with hard links is included if any of its names is in the subtree.
+    /// Directories contribute their index allocations, and sparse runs occupy no clusters.
+    /// Entries which fail to parse are skipped, unless `fail_fast` is set.
+    pub fn subtree_cluster_map(&mut self, root_record: u64) -> Result<Vec<(u64, u64)>> {
+        // Fails early if the root record can't be read.
+        self.get_entry(root_record)?;
+
+        let mut children: HashMap<u64, Vec<u64>> = HashMap::new();
+        // (first_lcn, length) extents, keyed by the base record.
+        let mut extents: HashMap<u64, Vec<(u64, u64)>> = HashMap::new();
+
+        for i in 0..self.get_entry_count() {
+            let entry = match self.get_entry(i) {
+                Ok(entry) => entry,
+                Err(e) if self.fail_fast => return Err(e),
+                Err(_) => continue,
+            };
+
+            if !entry.header.is_valid() || !entry.is_allocated() {
+                continue;
+            }
+
+            let record_number = match entry.header.base_reference.entry {
+                0 => entry.header.record_number,
+                base => base,
+            };
+
+            for attribute in entry.iter_attributes().filter_map(Result::ok) {
+                match attribute.data {
+                    // The root directory is its own parent.
+                    MftAttributeContent::AttrX30(file_name)
+                        if file_name.parent.entry != record_number =>
+                    {
+                        children
+                            .entry(file_name.parent.entry)
+                            .or_default()
+                            .push(record_number);
+                    }
+                    MftAttributeContent::DataRun(data_runs) => {
+                        extents.entry(record_number).or_default().extend(
+                            data_runs
+                                .data_runs
+                                .iter()
+                                .filter(|run| {
+                                    run.run_type == RunType::Standard && run.lcn_length > 0
+                                })
+                                .map(|run| (run.lcn_offset, run.lcn_length)),
+                        );
+                    }
+                    _ => {}
+                }
+            }
+        }
+
+        let mut subtree_extents = vec![];
+        let mut visited = HashSet::new();
+        let mut pending = vec![root_record];
+
+        while let Some(record_number) = pending.pop() {
+            // Guards against cycles in the parent references (and records listed once per name).
+            if !visited.insert(record_number) {
+                continue;
+            }
+
+            if let Some(record_extents) = extents.remove(&record_number) {
+                subtree_extents.extend(record_extents);
+            }
+
+            if let Some(record_children) = children.remove(&record_number) {
+                pending.extend(record_children);
+            }
+        }
+
+        Ok(merge_cluster_ranges(subtree_extents))
+    }
+
+    /// Returns the depth of the entry in the directory tree, which is the number of components
+    /// in its full path (the root directory has a depth of 0, `Windows` has a depth of 1).
+    ///
+    /// Returns `None` for entries which don't have a path, or whose path could not be fully resolved
+    /// (orphaned entries, or entries with an unknown parent).
+    pub fn entry_depth(&mut self, entry: &MftEntry) -> Result<Option<usize>> {
+        if entry.header.record_number == ROOT_ENTRY_NUMBER {
+            return Ok(Some(0));
+        }
+
+        let path = match self.get_full_path_for_entry(entry)? {
+            Some(path) => path,
+            None => return Ok(None),
+        };
+
+        let is_resolved = match path.components().next() {
+            Some(first) => {
+                !["[Orphaned]", "[Unknown]"].contains(&first.as_os_str().to_string_lossy().as_ref())
+            }
+            None => false,
+        };
+
+        if is_resolved {
+            Ok(Some(path.components().count()))
+        } else {
+            Ok(None)
+        }
+    }
+
+    /// Compares the entries copied to `$MFTMirr` (see `MIRRORED_ENTRIES`) with the entries in `mirror`,
+    /// and returns the numbers of the entries which differ.
+    ///
+    /// Entries are compared after applying the fixups, ignoring the update sequence array (which changes on every write).
+    /// An entry which can't be read from one of the files is considered different.
+    pub fn mismatched_mirror_entries<R: Read + Seek>(
+        &mut self,
+        mirror: &mut MftParser<R>,
+    ) -> Vec<u64> {
+        let mut mismatched = vec![];
+
+        for i in 0..MIRRORED_ENTRIES {
+            let entry = self.get_entry(i).ok();
+            let mirrored = mirror.get_entry(i).ok();
+
+            let is_match = match (entry, mirrored) {
+                (Some(entry), Some(mirrored)) => {
+                    data_without_update_sequence(&entry) == data_without_update_sequence(&mirrored)
+                }
+                _ => false,
+            };
+
+            if !is_match {
+                debug!("Entry {} differs from its mirror", i);
+                mismatched.push(i);
+            }
+        }
+
+        mismatched
+    }
+
+    /// Checks that the parent referenced by the `$FILE_NAME` attribute of the entry
+    /// (the same one used for resolving its path) exists, and is a directory.
+    ///
+    /// Entries without a `$FILE_NAME` attribute have no parent reference, and are considered `Valid`.
+    pub fn check_parent_reference(&mut self, entry: &MftEntry) -> Result<ParentRefStatus> {
+        match self.read_referenced_parent(entry.find_best_name_attribute().as_ref()) {
+            None => Ok(ParentRefStatus::Valid),
+            Some((_, None)) => Ok(ParentRefStatus::OutOfRange),
+            Some((parent, Some(parent_entry))) => Ok(parent_ref_status(parent, &parent_entry?)),
+        }
+    }
+
+    /// Checks whether the entry is an orphan, meaning the parent referenced by its `$FILE_NAME` attribute
+    /// no longer holds the directory the entry was created in.
+    ///
+    /// This is the case when the parent is missing (a null or self reference, or a record which is out of range,
+    /// empty or fails to parse), is not an allocated directory, or was reused since the reference was written
+    /// (its sequence number changed).
+    /// Entries without a `$FILE_NAME` attribute, and the root directory, are never orphans.
+    /// Only I/O errors (reading the parent record) are returned.
+    pub fn is_orphan(&mut self, entry: &MftEntry) -> Result<bool> {
+        let best_name = entry.find_best_name_attribute();
+        self.parent_reference_status_and_orphan(entry, best_name.as_ref())
+            .1
+    }
+
+    /// Both `check_parent_reference` (`None` if the parent fails to be read) and `is_orphan`,
+    /// reading the parent record only once. `best_name` is the best name of the entry.
+    pub(crate) fn parent_reference_status_and_orphan(
+        &mut self,
+        entry: &MftEntry,
+        best_name: Option<&FileNameAttr>,
+    ) -> (Option<ParentRefStatus>, Result<bool>) {
+        let record_number = entry.header.record_number;
+        let referenced_parent = self.read_referenced_parent(best_name);
+
+        let status = match &referenced_parent {
+            None => Some(ParentRefStatus::Valid),
+            Some((_, None)) => Some(ParentRefStatus::OutOfRange),
+            Some((parent, Some(Ok(parent_entry)))) => {
+                Some(parent_ref_status(*parent, parent_entry))
+            }
+            Some((_, Some(Err(_)))) => None,
+        };
+
+        let is_orphan = match referenced_parent {
+            _ if record_number == ROOT_ENTRY_NUMBER => Ok(false),
+            None => Ok(false),
+            Some((parent, _)) if parent.entry == 0 || parent.entry == record_number => Ok(true),
+            Some((_, None)) => Ok(true),
+            Some((parent, Some(Ok(parent_entry)))) => Ok(&parent_entry.header.signature
+                == ZERO_HEADER
+                || parent_entry.header.sequence != parent.sequence
+                || !parent_entry.is_allocated()
+                || !parent_entry.is_dir()),
+            Some((_, Some(Err(error @ Error::IoError { .. })))) => Err(error),
+            // A parent record which fails to parse no longer holds a directory.
+            Some((_, Some(Err(_)))) => Ok(true),
+        };
+
+        (status, is_orphan)
+    }
+
+    /// Reads the parent referenced by `best_name`, the best `$FILE_NAME` attribute of an entry.
+    ///
+    /// Returns `None` if the entry has no `$FILE_NAME` attribute,
+    /// and no parent entry if the reference is beyond the number of records in the MFT.
+    fn read_referenced_parent(
+        &mut self,
+        best_name: Option<&FileNameAttr>,
+    ) -> Option<(MftReference, Option<Result<MftEntry>>)> {
+        let parent = best_name?.parent;
+
+        if parent.entry >= self.get_entry_count() {
+            return Some((parent, None));
+        }
+
+        Some((parent, Some(self.get_entry(parent.entry))))
+    }
+
+    /// Resolves a path (such as `Windows\System32\cmd.exe`) to its entry, starting from the root directory.
+    /// Both `\` and `/` are accepted as separators, and names are compared case-insensitively
+    /// (using the `$UpCase` table, if one was set with `with_upcase_table`).
+    ///
+    /// Returns `None` if any of the path components could not be found.
+    pub fn lookup_path(&mut self, path: &str) -> Result<Option<MftEntry>> {
+        let mut current = self.get_entry(ROOT_ENTRY_NUMBER)?;
+
+        for component in path.split(['\\', '/']).filter(|c| !c.is_empty()) {
+            match self.find_child(&current, component)? {
+                Some(child) => current = child,
+                None => return Ok(None),
+            }
+        }
+
+        Ok(Some(current))
+    }
+
+    /// Finds the entry named `name` inside of the directory `parent`.
+    ///
+    /// The resident $INDEX_ROOT of the directory is searched first,
+    /// if the name is not there (the index may have spilled into $INDEX_ALLOCATION, which is not part of the MFT),
+    /// fall back to scanning the MFT for an allocated entry with a matching name and parent.
+    fn find_child(&mut self, parent: &MftEntry, name: &str) -> Result<Option<MftEntry>> {
+        let parent_entry_id = parent.header.record_number;
+
+        let indexed = parent
+            .iter_attributes_matching(Some(vec![MftAttributeType::IndexRoot]))
+            .filter_map(|attribute| attribute.ok()?.data.into_index_root())
+            .flat_map(|index_root| index_root.index_entries.index_entries)
+            .find(|index_entry| self.names_match(&index_entry.fname_info.name, name))
+            .map(|index_entry| index_entry.mft_reference);
+
+        if let Some(reference) = indexed {
+            let entry = self.get_entry(reference.entry)?;
+
+            // The index might point to a record which has since been reused.
+            if entry.header.sequence == reference.sequence {
+                return Ok(Some(entry));
+            }
+        }
+
+        for i in 0..self.get_entry_count() {
+            let entry = match self.get_entry(i) {
+                Ok(entry) if entry.is_allocated() => entry,
+                _ => continue,
+            };
+
+            let is_match = entry
+                .iter_attributes_matching(Some(vec![MftAttributeType::FileName]))
+                .filter_map(|attribute| attribute.ok()?.data.into_file_name())
+                .any(|fname| {
+                    fname.parent.entry == parent_entry_id && self.names_match(&fname.name, name)
+                });
+
+            if is_match {
+                return Ok(Some(entry));
+            }
+        }
+
+        Ok(None)
+    }
+
+    /// NTFS file names are case-insensitive, and are compared using the `$UpCase` table.
+    /// If the table was not provided, fall back to comparing with the unicode uppercase rules.
+    fn names_match(&self, a: &str, b: &str) -> bool {
+        match &self.upcase_table {
+            Some(upcase_table) => upcase_table.eq_ignore_case(a, b),
+            None => a
+                .chars()
+                .flat_map(char::to_uppercase)
+                .eq(b.chars().flat_map(char::to_uppercase)),
+        }
+    }
+}
+
+/// Sweeps over the `(first_lcn, length, record_number)` extents in order,
+/// and returns the ranges which are covered by extents of more than one record.
+fn find_cluster_conflicts(extents: Vec<(u64, u64, u64)>) -> Vec<ClusterConflict> {
+    // Each extent starts claiming clusters at its first LCN, and stops after its last one.
+    let mut events: Vec<(u64, bool, u64)> = extents
+        .into_iter()
+        .flat_map(|(lcn, length, record_number)| {
+            [
+                (lcn, true, record_number),
+                (lcn.saturating_add(length), false, record_number),
+            ]
+        })
+        .collect();
+    events.sort_unstable();
+
+    let mut conflicts: Vec<ClusterConflict> = vec![];
+    // The number of active extents of each record.
+    let mut active: BTreeMap<u64, usize> = BTreeMap::new();
+    let mut previous_lcn = 0;
+
+    for (lcn, is_start, record_number) in events {
+        if active.len() > 1 && lcn > previous_lcn {
+            let record_numbers: Vec<u64> = active.keys().copied().collect();
+
+            match conflicts.last_mut() {
+                // Merge with the previous range if it is contiguous and claimed by the same records.
+                Some(last)
+                    if last.lcn + last.length == previous_lcn
+                        && last.record_numbers == record_numbers =>
+                {
+                    last.length += lcn - previous_lcn
+                }
+                _ => conflicts.push(ClusterConflict {
+                    lcn: previous_lcn,
+                    length: lcn - previous_lcn,
+                    record_numbers,
+                }),
+            }
+        }
+
+        if is_start {
+            *active.entry(record_number).or_default() += 1;
+        } else if let Some(count) = active.get_mut(&record_number) {
+            *count -= 1;
+            if *count == 0 {
+                active.remove(&record_number);
+            }
+        }
+
+        previous_lcn = lcn;
+    }
+
+    conflicts
+}
+
+/// Sorts the `(first_lcn, length)` ranges, merging the ones which overlap or are contiguous.
+fn merge_cluster_ranges(mut ranges: Vec<(u64, u64)>) -> Vec<(u64, u64)> {
+    ranges.sort_unstable();
+
+    let mut merged: Vec<(u64, u64)> = Vec::with_capacity(ranges.len());
+
+    for (lcn, length) in ranges {
+        match merged.last_mut() {
+            Some((last_lcn, last_length)) if lcn <= last_lcn.saturating_add(*last_length) => {
+                *last_length = (*last_length).max(lcn.saturating_add(length) - *last_lcn);
+            }
+            _ => merged.push((lcn, length)),
+        }
+    }
+
+    merged
+}
+
+/// Returns the data of the entry, with the update sequence array zeroed out.
+fn data_without_update_sequence(entry: &MftEntry) -> Vec<u8> {
+    let mut data = entry.data.clone();
+
+    let usa_start = usize::from(entry.header.usa_offset).min(data.len());
+    let usa_end = (usa_start + usize::from(entry.header.usa_size) * 2).min(data.len());
+    data[usa_start..usa_end].fill(0);
+
+    data
+}
+
+/// Joins `name` to `path`, if there is one.
+fn join_name(path: PathBuf, name: Option<&str>) -> PathBuf {
+    match name {
+        Some(name) => path.join(name),
+        None => path,
+    }
+}
+
+/// A volume inside the data of the parser, with offsets relative to the start of the volume.
+struct VolumeReader<'a, T> {
+    data: &'a mut T,
+    volume_offset: u64,
+}
+
+impl<T: Read> Read for VolumeReader<'_, T> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        self.data.read(buf)
+    }
+}
+
+impl<T: Seek> Seek for VolumeReader<'_, T> {
+    fn seek(&mut self, pos: SeekFrom) -> io::Result<u64> {
+        let pos = match pos {
+            SeekFrom::Start(offset) => SeekFrom::Start(offset.saturating_add(self.volume_offset)),
+            pos => pos,
+        };
+
+        Ok(self.data.seek(pos)?.saturating_sub(self.volume_offset))
+    }
+}
+
+/// The status of a parent reference, given the parent record it points to.
+fn parent_ref_status(parent: MftReference, parent_entry: &MftEntry) -> ParentRefStatus {
+    if parent_entry.header.sequence != parent.sequence {
+        ParentRefStatus::SequenceMismatch
+    } else if !parent_entry.is_dir() {
+        ParentRefStatus::NotADirectory
+    } else {
+        ParentRefStatus::Valid
+    }
+}
+
+/// If `fail_fast` is set, ends the iteration right after the first error.
+fn stop_after_error<T>(
+    iter: impl Iterator<Item = Result<T>>,
+    fail_fast: bool,
+) -> impl Iterator<Item = Result<T>> {
+    let mut failed = false;
+
+    iter.take_while(move |item| {
+        if failed {
+            return false;
+        }
+
+        failed = fail_fast && item.is_err();
+        true
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::attribute::header::ResidentialHeader;
+    use crate::attribute::x30::FileNamespace;
+    use crate::attribute::MftAttributeType;
+    use crate::err::Error;
+    use crate::mft::{
+        find_cluster_conflicts, merge_cluster_ranges, ClusterConflict, MftParserOptions,
+        ParentRefStatus,
+    };
+    use crate::tests::fixtures::{mft_sample, synthetic_entry, synthetic_entry_with_attributes};
+    use crate::upcase::UpCaseTable;
+    use crate::{MftAttribute, MftEntry, MftParser};
+    use std::fs;
+    use std::io::{Cursor, Read, Seek, SeekFrom};
+    use std::num::NonZeroUsize;
+    use std::path::PathBuf;
+
+    // entrypoint for clion profiler.
+    #[test]
+    fn test_process_90_mft_entries() {
+        let sample = mft_sample();
+
+        let mut parser = MftParser::from_path(sample).unwrap();
+
+        let mut count = 0;
+        for record in parser.iter_entries().take(10000).filter_map(|a| a.ok()) {
+            for _attribute in record.iter_attributes() {
+                count += 1;
+            }
+        }
+
+        assert!(count > 0)
+    }
+
+    #[test]
+    fn test_iter_all_attributes() {
+        let sample = mft_sample();
+        let mut parser = MftParser::from_path(sample).unwrap();
+
+        let mut expected = 0;
+        for entry in parser.iter_entries().take(100).filter_map(Result::ok) {
+            expected += entry.iter_attributes().filter_map(Result::ok).count();
+        }
+
+        let attributes: Vec<(u64, MftAttribute)> = parser
+            .iter_all_attributes()
+            .filter_map(Result::ok)
+            .take_while(|(record_number, _)| *record_number < 100)
+            .collect();
+
+        assert_eq!(attributes.len(), expected);
+        assert_eq!(attributes[0].0, 0);
+    }
+
+    #[test]
+    fn test_get_full_path() {
+        let sample = mft_sample();
+        let mut parser = MftParser::from_path(sample).unwrap();
+
+        let mut paths = Vec::with_capacity(1000);
+        let entries: Vec<MftEntry> = parser
+            .iter_entries()
+            .take(1000)
+            .filter_map(Result::ok)
+            .collect();
+
+        for entry in entries {
+            if let Some(path) = parser.get_full_path_for_entry(&entry).unwrap() {
+                paths.push(path)
+            }
+        }
+
+        assert_eq!(paths.len(), 988);
+    }
+
+    #[test]
+    fn test_iter_entries_rev() {
+        let sample = mft_sample();
+        let mut parser = MftParser::from_path(sample).unwrap();
+
+        let last = parser.get_entry_count() - 1;
+        let expected: Vec<Vec<u8>> = (last - 2..=last)
+            .rev()
+            .map(|i| parser.get_entry(i).unwrap().data)
+            .collect();
+
+        let entries: Vec<Vec<u8>> = parser
+            .iter_entries_rev()
+            .take(3)
+            .map(|e| e.unwrap().data)
+            .collect();
+
+        assert_eq!(entries, expected);
+    }
+
+    #[test]
+    fn test_file_too_small() {
+        let result = MftParser::from_buffer(vec![0; 10]);
+
+        assert!(matches!(result, Err(Error::FileTooSmall { size: 10 })));
+    }
+
+    #[test]
+    fn test_from_read_seek_at() {
+        let mft = fs::read(mft_sample()).unwrap();
+
+        // The MFT of an image, following a partition table and a boot sector, and followed by other data.
+        let mut image = vec![0xAA; 3 * 512];
+        image.extend(&mft);
+        image.extend(vec![0xBB; 4096]);
+
+        let mut parser = MftParser::from_read_seek_at(
+            Cursor::new(image.clone()),
+            Some(mft.len() as u64),
+            3 * 512,
+        )
+        .unwrap();
+        let mut expected = MftParser::from_buffer(mft).unwrap();
+
+        assert_eq!(parser.get_entry_count(), expected.get_entry_count());
+        for i in [0, 5, 38, expected.get_entry_count() - 1] {
+            assert_eq!(
+                parser.get_entry(i).unwrap().data,
+                expected.get_entry(i).unwrap().data
+            );
+            assert_eq!(
+                parser.get_entry_ref(i).unwrap().data,
+                expected.get_entry_ref(i).unwrap().data
+            );
+        }
+
+        // Without a size, the MFT extends to the end of the image.
+        let parser = MftParser::from_read_seek_at(Cursor::new(image), None, 3 * 512).unwrap();
+        assert_eq!(parser.get_entry_count(), expected.get_entry_count() + 4);
+    }
+
+    #[test]
+    fn test_with_cache_size() {
+        let mut expected = MftParser::from_path(mft_sample()).unwrap();
+        let mut parser = MftParser::from_path(mft_sample())
+            .unwrap()
+            .with_cache_size(NonZeroUsize::new(2).unwrap());
+
+        for i in 0..1000 {
+            let entry = match parser.get_entry(i) {
+                Ok(entry) => entry,
+                Err(_) => continue,
+            };
+
+            assert_eq!(
+                parser.get_full_path_for_entry(&entry).unwrap(),
+                expected.get_full_path_for_entry(&entry).unwrap()
+            );
+        }
+
+        assert_eq!(parser.entries_cache.cap().get(), 2);
+        assert_eq!(parser.entries_cache.len(), 2);
+    }
+
+    #[test]
+    fn test_from_read_seek_with_options() {
+        let mft = fs::read(mft_sample()).unwrap();
+        let options = MftParserOptions {
+            cache_size: NonZeroUsize::new(10).unwrap(),
+            fail_fast: true,
+            ..Default::default()
+        };
+
+        let parser =
+            MftParser::from_read_seek_with_options(Cursor::new(mft), None, options).unwrap();
+
+        assert_eq!(parser.entries_cache.cap().get(), 10);
+        assert!(parser.fail_fast);
+    }
+
+    #[test]
+    fn test_mft_data_runs() {
+        let mut parser = MftParser::from_path(mft_sample()).unwrap();
+        let data_runs = parser.mft_data_runs().unwrap();
+
+        // The sample MFT is split into 4 fragments, of 2048 bytes clusters.
+        assert_eq!(data_runs.len(), 4);
+        assert_eq!(data_runs[0].lcn_offset, 205374);
+        assert_eq!(
+            data_runs
+                .iter()
+                .map(|run| run.lcn_length * 2048)
+                .sum::<u64>(),
+            13402112
+        );
+    }
+
+    #[test]
+    fn test_lookup_path() {
+        let sample = mft_sample();
+        let mut parser = MftParser::from_path(sample).unwrap();
+
+        let entries: Vec<MftEntry> = parser
+            .iter_entries()
+            .take(1000)
+            .filter_map(Result::ok)
+            .filter(|e| e.is_allocated())
+            .collect();
+
+        let mut checked = 0;
+        for entry in entries {
+            let path = match parser.get_full_path_for_entry(&entry).unwrap() {
+                Some(path) if path.components().count() > 2 => {
+                    path.to_string_lossy().to_uppercase()
+                }
+                _ => continue,
+            };
+
+            // Skip sentinels such as `[Orphaned]`, which are not real directories.
+            if path.starts_with('[') {
+                continue;
+            }
+
+            let found = parser
+                .lookup_path(&path)
+                .unwrap()
+                .expect("path should resolve");
+
+            assert_eq!(found.header.record_number, entry.header.record_number);
+
+            checked += 1;
+            if checked == 10 {
+                break;
+            }
+        }
+
+        assert_eq!(checked, 10);
+        assert!(parser
+            .lookup_path("Windows\\does-not-exist.txt")
+            .unwrap()
+            .is_none());
+    }
+
+    /// The sample has no hard links, so we turn the short name of entry 62 ("REPOSI~1") into a POSIX name,
+    /// which makes it a second name of "Repository".
+    fn mft_with_hardlink() -> Vec<u8> {
+        let mut mft = std::fs::read(mft_sample()).unwrap();
+        mft.truncate(1024 * 100);
+
+        let entry = MftParser::from_buffer(mft.clone())
+            .unwrap()
+            .get_entry(62)
+            .unwrap();
+        let short_name = entry
+            .attribute_headers()
+            .filter_map(Result::ok)
+            .find(|h| h.type_code == MftAttributeType::FileName)
+            .unwrap();
+        let data_offset = match short_name.residential_header {
+            ResidentialHeader::Resident(resident) => u64::from(resident.data_offset),
+            ResidentialHeader::NonResident(_) => unreachable!("$FILE_NAME is always resident"),
+        };
+        // The namespace is at offset 0x41 of the $FILE_NAME attribute content.
+        let namespace_offset = 62 * 1024 + short_name.start_offset + data_offset + 0x41;
+        mft[namespace_offset as usize] = FileNamespace::POSIX as u8;
+
+        mft
+    }
+
+    #[test]
+    fn test_hardlink_groups() {
+        let mut parser = MftParser::from_buffer(mft_with_hardlink()).unwrap();
+        let groups = parser.hardlink_groups().unwrap();
+
+        assert_eq!(groups.len(), 1);
+        assert_eq!(
+            groups[&62],
+            vec![
+                PathBuf::from("WINDOWS/system32/wbem/REPOSI~1"),
+                PathBuf::from("WINDOWS/system32/wbem/Repository"),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_get_all_paths_for_entry() {
+        // Without hard links, the short name is skipped.
+        let mut parser = MftParser::from_path(mft_sample()).unwrap();
+        let entry = parser.get_entry(62).unwrap();
+        assert_eq!(
+            parser.get_all_paths_for_entry(&entry).unwrap(),
+            vec![PathBuf::from("WINDOWS/system32/wbem/Repository")]
+        );
+
+        let mut parser = MftParser::from_buffer(mft_with_hardlink()).unwrap();
+        let entry = parser.get_entry(62).unwrap();
+        assert_eq!(
+            parser.get_all_paths_for_entry(&entry).unwrap(),
+            vec![
+                PathBuf::from("WINDOWS/system32/wbem/REPOSI~1"),
+                PathBuf::from("WINDOWS/system32/wbem/Repository"),
+            ]
+        );
+
+        // Extension entries resolve to the paths of their base entry.
+        let mut parser = MftParser::from_path(mft_sample()).unwrap();
+        let entry = parser.get_entry(4711).unwrap();
+        assert_eq!(entry.header.base_reference.entry, 3498);
+        assert_eq!(
+            parser.get_all_paths_for_entry(&entry).unwrap(),
+            vec![PathBuf::from("WINDOWS/system32/config/system.LOG")]
+        );
+    }
+
+    #[test]
+    fn test_mismatched_mirror_entries() {
+        let mft = std::fs::read(mft_sample()).unwrap();
+        let mut mirror = mft[..1024 * 4].to_vec();
+        // Change the update sequence number of entry 1, which should be ignored.
+        mirror[1024 + 0x30] ^= 0xFF;
+        mirror[1024 + 510] ^= 0xFF;
+        mirror[1024 + 1022] ^= 0xFF;
+        // Corrupt the content of entry 3.
+        mirror[3 * 1024 + 0x100] ^= 0xFF;
+
+        let mut parser = MftParser::from_buffer(mft).unwrap();
+        let mut mirror = MftParser::from_buffer(mirror).unwrap();
+
+        assert_eq!(parser.mismatched_mirror_entries(&mut mirror), vec![3]);
+    }
+
+    #[test]
+    fn test_find_cluster_conflicts() {
+        let conflicts = find_cluster_conflicts(vec![
+            (100, 10, 1),
+            (105, 10, 2),
+            // Overlaps within the same record are not conflicts.
+            (200, 10, 3),
+            (205, 10, 3),
+            // Adjacent extents are not conflicts.
+            (300, 10, 4),
+            (310, 10, 5),
+            // A range claimed by the same records through several extents is reported once.
+            (400, 10, 6),
+            (410, 10, 6),
+            (400, 20, 7),
+        ]);
+
+        assert_eq!(
+            conflicts,
+            vec![
+                ClusterConflict {
+                    lcn: 105,
+                    length: 5,
+                    record_numbers: vec![1, 2],
+                },
+                ClusterConflict {
+                    lcn: 400,
+                    length: 20,
+                    record_numbers: vec![6, 7],
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn test_get_logical_entry() {
+        let mut parser = MftParser::from_path(mft_sample()).unwrap();
+
+        // `system.LOG` has a $DATA extent in entry 4711.
+        let logical = parser.get_logical_entry(3498).unwrap();
+        assert_eq!(logical.extensions.len(), 1);
+        assert_eq!(logical.extensions[0].header.record_number, 4711);
+        assert!(logical.unresolved_references().is_empty());
+        assert_eq!(
+            logical.iter_attributes().filter(Result::is_ok).count(),
+            logical.base.iter_attributes().count()
+                + logical.extensions[0].iter_attributes().count()
+        );
+    }
+
+    #[test]
+    fn test_get_logical_entry_with_missing_extensions() {
+        let mft = std::fs::read(mft_sample()).unwrap();
+
+        let mut zeroed = mft.clone();
+        zeroed[4711 * 1024..4712 * 1024].fill(0);
+        let mut truncated = mft;
+        truncated.truncate(4000 * 1024);
+
+        for buffer in [zeroed, truncated] {
+            let mut parser = MftParser::from_buffer(buffer).unwrap();
+            let logical = parser.get_logical_entry(3498).unwrap();
+
+            assert!(logical.extensions.is_empty());
+            let unresolved = logical.unresolved_references();
+            assert_eq!(unresolved.len(), 1);
+            assert_eq!((unresolved[0].entry, unresolved[0].sequence), (4711, 2));
+
+            // The attributes of the base entry are still available.
+            let attributes: Vec<_> = logical.iter_attributes().collect();
+            assert_eq!(attributes.len(), logical.base.iter_attributes().count() + 1);
+            assert!(matches!(
+                attributes.last(),
+                Some(Err(Error::MissingExtensionRecord { entry: 4711, .. }))
+            ));
+        }
+    }
+
+    #[test]
+    fn test_merge_cluster_ranges() {
+        let ranges =
+            merge_cluster_ranges(vec![(300, 10), (100, 10), (105, 10), (110, 5), (120, 1)]);
+
+        assert_eq!(ranges, vec![(100, 15), (120, 1), (300, 10)]);
+    }
+
+    #[test]
+    fn test_subtree_cluster_map() {
+        let mut parser = MftParser::from_path(mft_sample()).unwrap();
+
+        // WINDOWS/system32/wbem/Repository
+        let repository = parser.subtree_cluster_map(62).unwrap();
+        assert_eq!(
+            repository,
+            vec![
+                (4919, 2),
+                (6860, 2),
+                (7060, 2),
+                (7258, 2),
+                (228090, 536),
+                (254643, 2639)
+            ]
+        );
+
+        // The subtree of the parent directory includes the clusters of `Repository`.
+        let wbem = parser.subtree_cluster_map(61).unwrap();
+        assert!(wbem.windows(2).all(|w| w[0].0 + w[0].1 < w[1].0));
+        assert!(repository.iter().all(|(lcn, length)| wbem.iter().any(
+            |(wbem_lcn, wbem_length)| wbem_lcn <= lcn && lcn + length <= wbem_lcn + wbem_length
+        )));
+    }
+
+    #[test]
+    fn test_sample_has_no_cross_linked_clusters() {
+        let mut parser = MftParser::from_path(mft_sample()).unwrap();
+
+        assert_eq!(parser.find_cross_linked_clusters().unwrap(), vec![]);
+    }
+
+    #[test]
+    fn test_iter_entries_with_paths() {
+        let mut parser = MftParser::from_path(mft_sample()).unwrap();
+
+        let paths: Vec<(u64, Option<PathBuf>)> = parser
+            .iter_entries_with_paths()
+            .take(4)
+            .map(|result| {
+                let (entry, path) = result.unwrap();
+                (entry.header.record_number, path)
+            })
+            .collect();
+
+        assert_eq!(
+            paths,
+            vec![
+                (0, Some(PathBuf::from("$MFT"))),
+                (1, Some(PathBuf::from("$MFTMirr"))),
+                (2, Some(PathBuf::from("$LogFile"))),
+                (3, Some(PathBuf::from("$Volume"))),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_path_index_matches_get_full_path_for_entry() {
+        let mut parser = MftParser::from_path(mft_sample()).unwrap();
+        let index = parser.build_path_index().unwrap();
+
+        for i in 0..parser.get_entry_count() {
+            let entry = match parser.get_entry(i) {
+                Ok(entry) if entry.find_best_name_attribute().is_some() => entry,
+                _ => continue,
+            };
+
+            assert_eq!(
+                index.full_path(i),
+                parser.get_full_path_for_entry(&entry).unwrap(),
+                "entry {}",
+                i
+            );
+        }
+
+        assert_eq!(
+            index.full_path(62),
+            Some(PathBuf::from("WINDOWS/system32/wbem/Repository"))
+        );
+        // Extension records resolve to the path of their base record.
+        assert_eq!(
+            index.full_path(4711),
+            Some(PathBuf::from("WINDOWS/system32/config/system.LOG"))
+        );
+    }
+
+    #[test]
+    fn test_get_full_path_with_reused_parent() {
+        let mut mft = std::fs::read(mft_sample()).unwrap();
+        mft.truncate(1024 * 100);
+
+        // Entry 62 (`WINDOWS/system32/wbem/Repository`) references its parent as entry 61, sequence 1.
+        // Bumping the sequence number of entry 61 makes it look like the record was reused for another directory.
+        let sequence_offset = 61 * 1024 + 0x10;
+        mft[sequence_offset..sequence_offset + 2].copy_from_slice(&2_u16.to_le_bytes());
+
+        let mut parser = MftParser::from_buffer(mft.clone()).unwrap();
+        let entry = parser.get_entry(62).unwrap();
+
+        assert_eq!(
+            parser.get_full_path_for_entry(&entry).unwrap(),
+            Some(PathBuf::from("[Unknown]/Repository"))
+        );
+        assert_eq!(
+            parser.build_path_index().unwrap().full_path(62),
+            Some(PathBuf::from("[Unknown]/Repository"))
+        );
+
+        // A freed record which was not reused yet has its sequence number incremented by one,
+        // so the path of a deleted directory is still resolved.
+        let flags_offset = 61 * 1024 + 0x16;
+        mft[flags_offset] &= !0x01;
+
+        let mut parser = MftParser::from_buffer(mft).unwrap();
+        let entry = parser.get_entry(62).unwrap();
+
+        assert_eq!(
+            parser.get_full_path_for_entry(&entry).unwrap(),
+            Some(PathBuf::from("WINDOWS/system32/wbem/Repository"))
+        );
+    }
+
+    #[test]
+    fn test_get_parent_path_for_entry() {
+        let mut parser = MftParser::from_path(mft_sample()).unwrap();
+
+        let entry = parser.get_entry(62).unwrap();
+        let parent_path = parser.get_parent_path_for_entry(&entry).unwrap().unwrap();
+        let full_path = parser.get_full_path_for_entry(&entry).unwrap().unwrap();
+
+        assert_eq!(parent_path, PathBuf::from("WINDOWS/system32/wbem"));
+        assert_eq!(full_path, parent_path.join("Repository"));
+
+        // Files in the root directory.
+        let mft = parser.get_entry(0).unwrap();
+        assert_eq!(
+            parser.get_parent_path_for_entry(&mft).unwrap(),
+            Some(PathBuf::new())
+        );
+    }
+
+    #[test]
+    fn test_get_parent_chain() {
+        let mut parser = MftParser::from_path(mft_sample()).unwrap();
+
+        // `Documents and Settings/All Users/Application Data/AOL OCP/AIM/Storage/All Users/SUDS_BBC2683C/PERSISTENT/3/1.1.1`
+        let entry = parser.get_entry(8388).unwrap();
+        let chain: Vec<(u64, u16)> = parser
+            .get_parent_chain(&entry)
+            .unwrap()
+            .into_iter()
+            .map(|reference| (reference.entry, reference.sequence))
+            .collect();
+
+        assert_eq!(
+            chain,
+            vec![
+                (5, 5),
+                (3515, 1),
+                (3517, 1),
+                (3597, 1),
+                (9744, 1),
+                (8363, 3),
+                (8370, 3),
+                (8378, 2),
+                (8379, 2),
+                (8381, 2),
+                (8382, 2),
+            ]
+        );
+
+        // The chain has an entry for every directory in the path.
+        let path = parser.get_full_path_for_entry(&entry).unwrap().unwrap();
+        assert_eq!(chain.len(), path.components().count());
+
+        let mft = parser.get_entry(0).unwrap();
+        let chain = parser.get_parent_chain(&mft).unwrap();
+        assert_eq!(chain.len(), 1);
+        assert_eq!(chain[0].entry, 5);
+    }
+
+    #[test]
+    fn test_register_attribute_decoder() {
+        // An entry with an unnamed $LOGGED_UTILITY_STREAM, which has no built-in parser.
+        let stream_type = MftAttributeType::LoggedUtilityStream as u32;
+        let mut buffer = synthetic_entry(1024, 0, &[]);
+        buffer.extend(synthetic_entry_with_attributes(
+            1024,
+            1,
+            &[(stream_type, &[0x01, 0x02, 0x03])],
+        ));
+        let mut parser = MftParser::from_buffer(buffer).unwrap();
+
+        parser.register_attribute_decoder(
+            stream_type,
+            Box::new(|data| Ok(serde_json::json!({ "stream_length": data.len() }))),
+        );
+
+        let entry = parser.get_entry(1).unwrap();
+        let contents = |entry: &MftEntry| -> Vec<serde_json::Value> {
+            let json = serde_json::to_value(entry).unwrap();
+            json["attributes"]
+                .as_array()
+                .unwrap()
+                .iter()
+                .map(|a| a["data"].clone())
+                .collect()
+        };
+
+        assert_eq!(
+            contents(&entry),
+            vec![serde_json::json!({ "stream_length": 3 })]
+        );
+
+        // Entries (and the decoders they carry) can be sent to other threads.
+        fn assert_send_sync<T: Send + Sync>(_: &T) {}
+        assert_send_sync(&entry);
+
+        // A failing decoder keeps the attribute raw.
+        parser.register_attribute_decoder(
+            stream_type,
+            Box::new(|_| {
+                Err(Error::Any {
+                    detail: "unsupported".to_string(),
+                })
+            }),
+        );
+
+        let entry = parser.get_entry(1).unwrap();
+        assert_eq!(
+            contents(&entry),
+            vec![serde_json::json!({ "attribute_type": "LoggedUtilityStream", "data": "010203" })]
+        );
+    }
+
+    #[test]
+    fn test_entry_depth() {
+        let sample = mft_sample();
+        let mut parser = MftParser::from_path(sample).unwrap();
+
+        let root = parser.get_entry(5).unwrap();
+        assert_eq!(parser.entry_depth(&root).unwrap(), Some(0));
+
+        // $MFT is in the root directory.
+        let mft = parser.get_entry(0).unwrap();
+        assert_eq!(parser.entry_depth(&mft).unwrap(), Some(1));
+
+        // The parent of this entry could not be resolved, so its path is `[Unknown]/login[5].htm`.
+        let unresolved = parser.get_entry(11613).unwrap();
+        assert_eq!(parser.entry_depth(&unresolved).unwrap(), None);
+    }
+
+    #[test]
+    fn test_lookup_path_uses_upcase_table() {
+        let sample = mft_sample();
+        // An empty table maps every character to itself, making comparisons case-sensitive.
+        let mut parser = MftParser::from_path(sample)
+            .unwrap()
+            .with_upcase_table(UpCaseTable::from_data(&[]));
+
+        assert!(parser.lookup_path("$Extend\\$Quota").unwrap().is_some());
+        assert!(parser.lookup_path("$EXTEND\\$QUOTA").unwrap().is_none());
+    }
+
+    #[test]
+    fn test_check_parent_reference() {
+        let sample = mft_sample();
+        let mut parser = MftParser::from_path(sample).unwrap();
+
+        let mft = parser.get_entry(0).unwrap();
+        assert_eq!(
+            parser.check_parent_reference(&mft).unwrap(),
+            ParentRefStatus::Valid
+        );
+
+        // The parent record of this entry was reused, so its path is unresolved (see `test_entry_depth`).
+        let entry = parser.get_entry(11613).unwrap();
+        assert_eq!(
+            parser.check_parent_reference(&entry).unwrap(),
+            ParentRefStatus::SequenceMismatch
+        );
+    }
+
+    #[test]
+    fn test_is_orphan() {
+        let mut parser = MftParser::from_path(mft_sample()).unwrap();
+
+        for record_number in [0, 5, 8388] {
+            let entry = parser.get_entry(record_number).unwrap();
+            assert!(!parser.is_orphan(&entry).unwrap(), "{}", record_number);
+        }
+
+        // The parent record of this entry was reallocated, so its sequence number no longer matches the reference.
+        let entry = parser.get_entry(11613).unwrap();
+        let parent = entry.find_best_name_attribute().unwrap().parent;
+        let reallocated_parent = parser.get_entry(parent.entry).unwrap();
+
+        assert_ne!(reallocated_parent.header.sequence, parent.sequence);
+        assert!(parser.is_orphan(&entry).unwrap());
+    }
+
+    #[test]
+    fn test_is_orphan_with_corrupted_parent() {
+        let mut buffer = std::fs::read(mft_sample()).unwrap();
+        let mut parser = MftParser::from_buffer(buffer.clone()).unwrap();
+
+        let entry = parser.get_entry(8388).unwrap();
+        let parent = entry.find_best_name_attribute().unwrap().parent;
+
+        // Corrupt the signature of the parent record, so that it fails to parse.
+        let entry_size = parser.entry_size as usize;
+        let offset = parent.entry as usize * entry_size;
+        buffer[offset..offset + 4].copy_from_slice(b"XXXX");
+
+        let mut parser = MftParser::from_buffer(buffer).unwrap();
+
+        assert!(parser.check_parent_reference(&entry).is_err());
+        assert!(parser.is_orphan(&entry).unwrap());
+    }
+
+    #[test]
+    fn test_lookup_path_root() {
+        let sample = mft_sample();
+        let mut parser = MftParser::from_path(sample).unwrap();
+
+        let root = parser.lookup_path("\\").unwrap().unwrap();
+
+        assert_eq!(root.header.record_number, 5);
+    }
+
+    #[test]
+    fn test_wof_compressed_mft_is_an_error() {
+        // A WOF reparse point (with a version 1 WOF header and a file provider header).
+        let mut reparse_point = vec![0x17, 0x00, 0x00, 0x80, 0x10, 0x00, 0x00, 0x00];
+        reparse_point.extend([1, 0, 0, 0, 2, 0, 0, 0, 1, 0, 0, 0, 0, 0, 0, 0]);
+
+        let mut buffer = synthetic_entry_with_attributes(1024, 0, &[(0xC0, &reparse_point)]);
+        buffer.extend(synthetic_entry(1024, 1, &[]));
+
+        assert!(matches!(
+            MftParser::from_buffer(buffer),
+            Err(Error::WofCompressedMft)
+        ));
+    }
+
+    #[cfg(feature = "rayon")]
+    #[test]
+    fn test_par_iter_entries() {
+        let mut parser = MftParser::from_path(mft_sample()).unwrap();
+
+        let sequential: Vec<_> = parser
+            .iter_entries()
+            .map(|entry| entry.map(|e| (e.header.record_number, e.data)).ok())
+            .collect();
+        let parallel: Vec<_> = parser
+            .par_iter_entries()
+            .map(|entry| entry.map(|e| (e.header.record_number, e.data)).ok())
+            .collect();
+
+        assert_eq!(parallel.len() as u64, parser.get_entry_count());
+        assert_eq!(parallel, sequential);
+    }
+
+    #[test]
+    fn test_allocated_entries() {
+        // Entries 0, 1 and 3 are in use, entry 2 is free (and zeroed).
+        let mut buffer = synthetic_entry_with_attributes(
+            1024,
+            0,
+            &[(MftAttributeType::BITMAP as u32, &[0b0000_1011])],
+        );
+        buffer.extend(synthetic_entry(1024, 1, &[]));
+        buffer.extend(vec![0; 1024]);
+        buffer.extend(synthetic_entry(1024, 3, &[]));
+
+        let mut parser = MftParser::from_buffer(buffer).unwrap();
+        let allocated: Vec<u64> = parser.allocated_entries().collect();
+
+        assert_eq!(allocated, vec![0, 1, 3]);
+
+        let allocated_by_header: Vec<u64> = parser
+            .iter_entries()
+            .filter_map(Result::ok)
+            .filter(|entry| entry.is_allocated())
+            .map(|entry| entry.header.record_number)
+            .collect();
+        assert_eq!(allocated, allocated_by_header);
+    }
+
+    #[test]
+    fn test_allocated_entries_without_resident_bitmap() {
+        // The $BITMAP of the sample is non-resident.
+        let mut parser = MftParser::from_path(mft_sample()).unwrap();
+
+        assert_eq!(
+            parser.allocated_entries().count() as u64,
+            parser.get_entry_count()
+        );
+    }
+
+    /// An image which only holds the data starting `base` bytes into the volume.
+    struct PartialImage {
+        data: Cursor<Vec<u8>>,
+        base: u64,
+    }
+
+    impl Read for PartialImage {
+        fn read(&mut self, buf: &mut [u8]) -> std::io::Result<usize> {
+            self.data.read(buf)
+        }
+    }
+
+    impl Seek for PartialImage {
+        fn seek(&mut self, pos: SeekFrom) -> std::io::Result<u64> {
+            let pos = match pos {
+                SeekFrom::Start(offset) => SeekFrom::Start(offset - self.base),
+                pos => pos,
+            };
+
+            Ok(self.data.seek(pos)? + self.base)
+        }
+    }
+
+    #[test]
+    fn test_allocated_entries_with_non_resident_bitmap() {
+        // In the volume of the sample, clusters are 2048 bytes, the $BITMAP of the $MFT is a single cluster
+        // at LCN 205373, and is followed by the MFT (at LCN 205374).
+        let bitmap_offset = 205_373 * 2048;
+        let mft = fs::read(mft_sample()).unwrap();
+        let mft_size = mft.len() as u64;
+
+        // Only the first 20 entries are marked as in use.
+        let mut image = vec![0xFF, 0xFF, 0x0F];
+        image.resize(2048, 0);
+        image.extend(mft);
+
+        let image = PartialImage {
+            data: Cursor::new(image),
+            base: bitmap_offset,
+        };
+        let mut parser = MftParser::from_read_seek_at(image, Some(mft_size), bitmap_offset + 2048)
+            .unwrap()
+            .with_bytes_per_cluster(2048);
+
+        assert_eq!(
+            parser.allocated_entries().collect::<Vec<u64>>(),
+            (0..20).collect::<Vec<u64>>()
+        );
+        // Entries are still read from the MFT afterwards.
+        assert_eq!(parser.get_entry(5).unwrap().header.record_number, 5);
+    }
+
+    #[test]
+    fn test_8192_byte_entries() {
+        let data: Vec<u8> = (0..6000).map(|i| (i % 251) as u8).collect();
+
+        let mut buffer = synthetic_entry(8192, 0, &data);
+        buffer.extend(synthetic_entry(8192, 1, &data));
+
+        let mut parser = MftParser::from_buffer(buffer).unwrap();
+        assert_eq!(parser.get_entry_count(), 2);
+
+        let entry = parser.get_entry(1).unwrap();
+        assert_eq!(entry.header.total_entry_size, 8192);
+        assert_eq!(entry.valid_fixup, Some(true));
+
+        let attribute = entry.iter_attributes().next().unwrap().unwrap();
+        let content = attribute.data.into_data().unwrap();
+        assert_eq!(content.data(), &data[..]);
+    }
+
+    #[test]
+    fn test_first_invalid_entry() {
+        let mut parser = MftParser::from_path(mft_sample()).unwrap();
+        assert!(parser.first_invalid_entry().unwrap().is_none());
+
+        let data = [0x41; 100];
+        let mut buffer = synthetic_entry(1024, 0, &data);
+        buffer.extend(synthetic_entry(1024, 1, &data));
+        buffer.extend(synthetic_entry(1024, 2, &data));
+        buffer.extend(synthetic_entry(1024, 3, &data));
+
+        // Break the end of the first stride of entry 2, and the attribute type of entry 3.
+        buffer[2 * 1024 + 510] ^= 0xFF;
+        buffer[3 * 1024 + 0x38] = 0x01;
+
+        let mut parser = MftParser::from_buffer(buffer.clone()).unwrap();
+        assert!(matches!(
+            parser.first_invalid_entry().unwrap(),
+            Some((2, Error::InvalidFixup { entry_id: 2 }))
+        ));
+
+        buffer[2 * 1024 + 510] ^= 0xFF;
+        let mut parser = MftParser::from_buffer(buffer).unwrap();
+        assert!(matches!(
+            parser.first_invalid_entry().unwrap(),
+            Some((3, Error::UnknownAttributeType { attribute_type: 1 }))
+        ));
+    }
+
+    #[test]
+    fn test_get_entry_ref_matches_get_entry() {
+        let mut parser = MftParser::from_path(mft_sample()).unwrap();
+
+        for i in 0..100 {
+            let owned = parser.get_entry(i).unwrap();
+            let borrowed = parser.get_entry_ref(i).unwrap();
+
+            assert_eq!(borrowed.valid_fixup, owned.valid_fixup);
+            assert_eq!(borrowed.data.as_ref(), owned.data.as_slice());
+            assert_eq!(
+                borrowed.iter_attributes().count(),
+                owned.iter_attributes().count()
+            );
+        }
+    }
+
+    #[test]
+    fn test_fail_fast() {
+        let mut buffer = vec![];
+        for record_number in 0..4 {
+            buffer.extend(synthetic_entry(1024, record_number, &[]));
+        }
+        // Corrupt the signature of the second entry.
+        buffer[1024..1028].copy_from_slice(b"XXXX");
+
+        let mut parser = MftParser::from_buffer(buffer.clone()).unwrap();
+        let results: Vec<bool> = parser.iter_entries().map(|e| e.is_ok()).collect();
+        assert_eq!(results, vec![true, false, true, true]);
+
+        let mut parser = MftParser::from_buffer(buffer).unwrap().with_fail_fast(true);
+        let results: Vec<bool> = parser.iter_entries().map(|e| e.is_ok()).collect();
+        assert_eq!(results, vec![true, false]);
+    }
+
+    #[test]
+    fn test_unsupported_entry_size() {
+        let mut buffer = synthetic_entry(2048, 0, &[]);
+        buffer[0x1C..0x20].copy_from_slice(&1500_u32.to_le_bytes());
+
+        let result = MftParser::from_buffer(buffer);
+
+        assert!(matches!(
+            result,
+            Err(Error::UnsupportedEntrySize { size: 1500 })
+        ));
     }
 
     #[test]
@@ -0,0 +1,226 @@
+//! Parsing of the `$Q` index of the `$Extend\$Quota` file.
+//!
+//! The `$Q` index maps owner ids (as found in `StandardInfoAttr::owner_id`)
+//! to the quota control entry of that owner.
+//! <https://github.com/libyal/libfsntfs/blob/main/documentation/New%20Technologies%20File%20System%20(NTFS).asciidoc#quota>
+use crate::attribute::header::ResidentialHeader;
+use crate::attribute::x90::IndexEntryFlags;
+use crate::attribute::MftAttributeType;
+use crate::err::{Error, Result};
+use crate::impl_serialize_for_bitflags;
+use crate::sid::Sid;
+use crate::MftEntry;
+
+use bitflags::bitflags;
+use byteorder::{LittleEndian, ReadBytesExt};
+use chrono::{DateTime, Utc};
+use serde::Serialize;
+use winstructs::timestamp::WinTimestamp;
+
+use std::io::{Cursor, Read, Seek, SeekFrom};
+
+/// The name of the index holding the quota control entries.
+pub const QUOTA_INDEX_NAME: &str = "$Q";
+
+/// Size of the quota control entry, without the trailing SID.
+const QUOTA_ENTRY_HEADER_SIZE: usize = 48;
+
+bitflags! {
+    pub struct QuotaFlags: u32 {
+        const DEFAULT_LIMITS      = 0x0000_0001;
+        const LIMIT_REACHED       = 0x0000_0002;
+        const ID_DELETED          = 0x0000_0004;
+        const TRACKING_ENABLED    = 0x0000_0010;
+        const ENFORCEMENT_ENABLED = 0x0000_0020;
+        const TRACKING_REQUESTED  = 0x0000_0040;
+        const LOG_THRESHOLD       = 0x0000_0080;
+        const LOG_LIMIT           = 0x0000_0100;
+        const OUT_OF_DATE         = 0x0000_0200;
+        const CORRUPT             = 0x0000_0400;
+        const PENDING_DELETES     = 0x0000_0800;
+    }
+}
+
+impl_serialize_for_bitflags! {QuotaFlags}
+
+/// A quota control entry, the value of a `$Q` index entry.
+#[derive(Serialize, Clone, Debug)]
+pub struct QuotaEntry {
+    /// The key of the index entry.
+    pub owner_id: u32,
+    pub version: u32,
+    pub flags: QuotaFlags,
+    pub bytes_used: u64,
+    pub change_time: DateTime<Utc>,
+    /// Soft quota, -1 if not limited.
+    pub threshold: i64,
+    /// Hard quota, -1 if not limited.
+    pub limit: i64,
+    /// How long the soft quota has been exceeded, in 100-nanosecond intervals.
+    pub exceeded_time: i64,
+    /// The SID of the owner.
+    /// Not present for the quota defaults entry.
+    pub sid: Option<Sid>,
+}
+
+impl QuotaEntry {
+    /// Parse the data of a `$Q` index entry, `data_size` is the length of the index entry data.
+    pub fn from_stream<S: Read>(
+        stream: &mut S,
+        owner_id: u32,
+        data_size: usize,
+    ) -> Result<QuotaEntry> {
+        let version = stream.read_u32::<LittleEndian>()?;
+        let flags = QuotaFlags::from_bits_truncate(stream.read_u32::<LittleEndian>()?);
+        let bytes_used = stream.read_u64::<LittleEndian>()?;
+        let change_time = WinTimestamp::from_reader(stream)
+            .map_err(Error::failed_to_read_windows_time)?
+            .to_datetime();
+        let threshold = stream.read_i64::<LittleEndian>()?;
+        let limit = stream.read_i64::<LittleEndian>()?;
+        let exceeded_time = stream.read_i64::<LittleEndian>()?;
+
+        let sid = if data_size > QUOTA_ENTRY_HEADER_SIZE {
+            Some(Sid::from_stream(stream)?)
+        } else {
+            None
+        };
+
+        Ok(QuotaEntry {
+            owner_id,
+            version,
+            flags,
+            bytes_used,
+            change_time,
+            threshold,
+            limit,
+            exceeded_time,
+            sid,
+        })
+    }
+}
+
+/// Reads the quota entries from the resident `$Q` $INDEX_ROOT of the given `$Quota` entry.
+///
+/// Returns an empty vector if the entry has no resident `$Q` index root.
+/// Entries which were moved to the $INDEX_ALLOCATION attribute are not available from the MFT alone.
+pub fn quota_entries(entry: &MftEntry) -> Result<Vec<QuotaEntry>> {
+    for header in entry.attribute_headers() {
+        let header = header?;
+
+        if header.type_code != MftAttributeType::IndexRoot || header.name != QUOTA_INDEX_NAME {
+            continue;
+        }
+
+        if let ResidentialHeader::Resident(resident) = &header.residential_header {
+            let start = header.start_offset as usize + resident.data_offset as usize;
+            let end = start + resident.data_size as usize;
+
+            let content = entry.data.get(start..end).ok_or_else(|| Error::Any {
+                detail: format!(
+                    "$Q index root at offset {} is out of the bounds of the entry",
+                    start
+                ),
+            })?;
+
+            return quota_entries_from_index_root(content);
+        }
+    }
+
+    Ok(vec![])
+}
+
+/// Reads the quota entries from the content of a `$Q` $INDEX_ROOT attribute.
+pub fn quota_entries_from_index_root(content: &[u8]) -> Result<Vec<QuotaEntry>> {
+    let mut stream = Cursor::new(content);
+
+    // The index node header follows the 16 bytes of the index root header.
+    let index_node_start = 0x10;
+    stream.seek(SeekFrom::Start(index_node_start))?;
+
+    let entries_offset = u64::from(stream.read_u32::<LittleEndian>()?);
+    let index_length = u64::from(stream.read_u32::<LittleEndian>()?);
+
+    let mut offset = index_node_start + entries_offset;
+    let end = index_node_start + index_length;
+
+    let mut entries = vec![];
+
+    while offset < end {
+        stream.seek(SeekFrom::Start(offset))?;
+
+        let data_offset = stream.read_u16::<LittleEndian>()?;
+        let data_length = stream.read_u16::<LittleEndian>()?;
+        let _reserved = stream.read_u32::<LittleEndian>()?;
+        let length = stream.read_u16::<LittleEndian>()?;
+        let _key_length = stream.read_u16::<LittleEndian>()?;
+        let flags =
+            IndexEntryFlags::from_bits_truncate(u32::from(stream.read_u16::<LittleEndian>()?));
+        let _reserved = stream.read_u16::<LittleEndian>()?;
+
+        if flags.contains(IndexEntryFlags::INDEX_ENTRY_END) || length == 0 {
+            break;
+        }
+
+        let owner_id = stream.read_u32::<LittleEndian>()?;
+
+        stream.seek(SeekFrom::Start(offset + u64::from(data_offset)))?;
+        entries.push(QuotaEntry::from_stream(
+            &mut stream,
+            owner_id,
+            data_length as usize,
+        )?);
+
+        offset += u64::from(length);
+    }
+
+    Ok(entries)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{quota_entries_from_index_root, QuotaFlags};
+
+    #[test]
+    fn test_parses_quota_index_root() {
+        let index_root: &[u8] = &[
+            // Index root header (view index, collation ntofs ulong)
+            0x00, 0x00, 0x00, 0x00, 0x10, 0x00, 0x00, 0x00, 0x00, 0x10, 0x00, 0x00, 0x01, 0x00,
+            0x00, 0x00, // Index node header
+            0x10, 0x00, 0x00, 0x00, 0x80, 0x00, 0x00, 0x00, 0x80, 0x00, 0x00, 0x00, 0x00, 0x00,
+            0x00, 0x00,
+            // Index entry: data offset 0x14, data length 0x4C, length 0x60, key length 4
+            0x14, 0x00, 0x4C, 0x00, 0x00, 0x00, 0x00, 0x00, 0x60, 0x00, 0x04, 0x00, 0x00, 0x00,
+            0x00, 0x00, // Key: owner id 0x100
+            0x00, 0x01, 0x00, 0x00, // Data: version 2, flags, bytes used
+            0x02, 0x00, 0x00, 0x00, 0x10, 0x00, 0x00, 0x00, 0x00, 0x10, 0x00, 0x00, 0x00, 0x00,
+            0x00, 0x00, // Change time
+            0xD5, 0x2D, 0x48, 0x58, 0x43, 0x5F, 0xCE, 0x01, // Threshold, limit (-1)
+            0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF,
+            0xFF, 0xFF, // Exceeded time
+            0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, // SID S-1-5-21-1-2-3-1000
+            0x01, 0x05, 0x00, 0x00, 0x00, 0x00, 0x00, 0x05, 0x15, 0x00, 0x00, 0x00, 0x01, 0x00,
+            0x00, 0x00, 0x02, 0x00, 0x00, 0x00, 0x03, 0x00, 0x00, 0x00, 0xE8, 0x03, 0x00, 0x00,
+            // End entry
+            0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x10, 0x00, 0x00, 0x00, 0x02, 0x00,
+            0x00, 0x00,
+        ];
+
+        let entries = quota_entries_from_index_root(index_root).unwrap();
+
+        assert_eq!(entries.len(), 1);
+
+        let entry = &entries[0];
+        assert_eq!(entry.owner_id, 0x100);
+        assert_eq!(entry.version, 2);
+        assert_eq!(entry.flags, QuotaFlags::TRACKING_ENABLED);
+        assert_eq!(entry.bytes_used, 0x1000);
+        assert_eq!(entry.change_time.timestamp(), 1370144608);
+        assert_eq!(entry.threshold, -1);
+        assert_eq!(entry.limit, -1);
+        assert_eq!(
+            entry.sid.as_ref().map(|s| s.to_string()),
+            Some("S-1-5-21-1-2-3-1000".to_string())
+        );
+    }
+}
@@ -0,0 +1,79 @@
+use crate::err::Result;
+
+use byteorder::{BigEndian, LittleEndian, ReadBytesExt};
+use serde::ser;
+
+use std::fmt;
+use std::io::Read;
+
+/// A security identifier (SID).
+/// <https://docs.microsoft.com/en-us/windows/win32/api/winnt/ns-winnt-sid>
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Sid {
+    pub revision: u8,
+    /// The 48-bit identifier authority, stored big-endian on disk.
+    pub identifier_authority: u64,
+    pub sub_authorities: Vec<u32>,
+}
+
+impl Sid {
+    /// Parse a binary SID.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use mft::sid::Sid;
+    /// # use std::io::Cursor;
+    /// let sid_buffer: &[u8] = &[
+    ///     0x01,0x02,0x00,0x00,0x00,0x00,0x00,0x05,0x20,0x00,0x00,0x00,0x20,0x02,0x00,0x00
+    /// ];
+    ///
+    /// let sid = Sid::from_stream(&mut Cursor::new(sid_buffer)).unwrap();
+    ///
+    /// assert_eq!(sid.to_string(), "S-1-5-32-544");
+    /// ```
+    pub fn from_stream<R: Read>(stream: &mut R) -> Result<Sid> {
+        let revision = stream.read_u8()?;
+        let sub_authority_count = stream.read_u8()?;
+        let identifier_authority = stream.read_u48::<BigEndian>()?;
+
+        let mut sub_authorities = Vec::with_capacity(sub_authority_count as usize);
+        for _ in 0..sub_authority_count {
+            sub_authorities.push(stream.read_u32::<LittleEndian>()?);
+        }
+
+        Ok(Sid {
+            revision,
+            identifier_authority,
+            sub_authorities,
+        })
+    }
+}
+
+impl fmt::Display for Sid {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "S-{}-", self.revision)?;
+
+        // Authorities which do not fit in 32 bits are represented in hex.
+        if self.identifier_authority > u64::from(u32::MAX) {
+            write!(f, "0x{:012X}", self.identifier_authority)?;
+        } else {
+            write!(f, "{}", self.identifier_authority)?;
+        }
+
+        for sub_authority in &self.sub_authorities {
+            write!(f, "-{}", sub_authority)?;
+        }
+
+        Ok(())
+    }
+}
+
+impl ser::Serialize for Sid {
+    fn serialize<S>(&self, serializer: S) -> ::std::result::Result<S::Ok, S::Error>
+    where
+        S: ser::Serializer,
+    {
+        serializer.serialize_str(&self.to_string())
+    }
+}
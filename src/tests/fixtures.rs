@@ -11,3 +11,129 @@ pub fn mft_sample() -> PathBuf {
         .join("samples")
         .join("MFT")
 }
+
+const INDX_RECORD_SIZE: usize = 4096;
+const INDX_USA_OFFSET: usize = 0x28;
+const INDX_UPDATE_SEQUENCE: [u8; 2] = [0x07, 0x00];
+
+/// An index entry for a `$FILE_NAME` key named `name`, of the entry `entry`.
+fn index_entry(entry: u64, name: &str) -> Vec<u8> {
+    let name: Vec<u8> = name.encode_utf16().flat_map(u16::to_le_bytes).collect();
+
+    let mut file_name = vec![];
+    // Parent reference (entry 5, sequence 5).
+    file_name.extend((5_u64 | (5 << 48)).to_le_bytes());
+    // Timestamps, allocated and real size, flags and reparse value.
+    file_name.extend([0; 4 * 8 + 8 + 8 + 4 + 4]);
+    file_name.push((name.len() / 2) as u8);
+    // Win32 namespace.
+    file_name.push(1);
+    file_name.extend(&name);
+
+    let length = (16 + file_name.len() + 7) & !7;
+
+    let mut buffer = vec![];
+    buffer.extend((entry | (1 << 48)).to_le_bytes());
+    buffer.extend((length as u16).to_le_bytes());
+    buffer.extend((file_name.len() as u16).to_le_bytes());
+    buffer.extend(0_u32.to_le_bytes());
+    buffer.extend(file_name);
+    buffer.resize(length, 0);
+    buffer
+}
+
+/// A 4096 bytes `INDX` record as stored on disk (before applying fixups), holding index entries for `entries` (record number and name).
+pub fn indx_record(entries: &[(u64, &str)]) -> Vec<u8> {
+    let mut index_entries = vec![];
+    for (entry, name) in entries {
+        index_entries.extend(index_entry(*entry, name));
+    }
+    // The terminating entry, with the `INDEX_ENTRY_END` flag.
+    index_entries.extend([0; 8]);
+    index_entries.extend(16_u16.to_le_bytes());
+    index_entries.extend(0_u16.to_le_bytes());
+    index_entries.extend(2_u32.to_le_bytes());
+
+    let entries_offset = 0x40;
+    let entries_end = 0x18 + entries_offset + index_entries.len();
+    let mut buffer = vec![0; INDX_RECORD_SIZE];
+    buffer[..4].copy_from_slice(b"INDX");
+    buffer[0x04..0x06].copy_from_slice(&(INDX_USA_OFFSET as u16).to_le_bytes());
+    buffer[0x06..0x08].copy_from_slice(&9_u16.to_le_bytes());
+    buffer[0x18..0x1C].copy_from_slice(&(entries_offset as u32).to_le_bytes());
+    buffer[0x1C..0x20]
+        .copy_from_slice(&((entries_offset + index_entries.len()) as u32).to_le_bytes());
+    buffer[0x20..0x24].copy_from_slice(&((INDX_RECORD_SIZE - 0x18) as u32).to_le_bytes());
+    buffer[0x18 + entries_offset..entries_end].copy_from_slice(&index_entries);
+
+    // Move the last two bytes of each stride to the update sequence array.
+    buffer[INDX_USA_OFFSET..INDX_USA_OFFSET + 2].copy_from_slice(&INDX_UPDATE_SEQUENCE);
+    for stride in 0..INDX_RECORD_SIZE / 512 {
+        let end = (stride + 1) * 512;
+        let fixup = INDX_USA_OFFSET + (stride + 1) * 2;
+        buffer[fixup] = buffer[end - 2];
+        buffer[fixup + 1] = buffer[end - 1];
+        buffer[end - 2..end].copy_from_slice(&INDX_UPDATE_SEQUENCE);
+    }
+
+    buffer
+}
+
+/// Builds an allocated entry of `entry_size` bytes, with a single resident $DATA attribute holding `data`,
+/// see `synthetic_entry_with_attributes`.
+pub fn synthetic_entry(entry_size: usize, record_number: u32, data: &[u8]) -> Vec<u8> {
+    synthetic_entry_with_attributes(entry_size, record_number, &[(0x80, data)])
+}
+
+/// Builds an allocated entry of `entry_size` bytes, with resident `attributes` (type code and content),
+/// and the update sequence array applied as it would be on disk.
+pub fn synthetic_entry_with_attributes(
+    entry_size: usize,
+    record_number: u32,
+    attributes: &[(u32, &[u8])],
+) -> Vec<u8> {
+    let strides = entry_size / 512;
+    let usa_offset = 0x30;
+    let first_attribute_offset = (usa_offset + (strides + 1) * 2 + 7) & !7;
+
+    let mut entry = vec![0_u8; entry_size];
+    entry[0..4].copy_from_slice(b"FILE");
+    entry[0x04..0x06].copy_from_slice(&(usa_offset as u16).to_le_bytes());
+    entry[0x06..0x08].copy_from_slice(&(strides as u16 + 1).to_le_bytes());
+    entry[0x10..0x12].copy_from_slice(&1_u16.to_le_bytes());
+    entry[0x12..0x14].copy_from_slice(&1_u16.to_le_bytes());
+    entry[0x14..0x16].copy_from_slice(&(first_attribute_offset as u16).to_le_bytes());
+    entry[0x16..0x18].copy_from_slice(&1_u16.to_le_bytes());
+    entry[0x1C..0x20].copy_from_slice(&(entry_size as u32).to_le_bytes());
+    entry[0x2C..0x30].copy_from_slice(&record_number.to_le_bytes());
+
+    let mut offset = first_attribute_offset;
+    for (id, (type_code, data)) in attributes.iter().enumerate() {
+        let attribute_length = (0x18 + data.len() + 7) & !7;
+
+        let attribute = &mut entry[offset..];
+        attribute[0x00..0x04].copy_from_slice(&type_code.to_le_bytes());
+        attribute[0x04..0x08].copy_from_slice(&(attribute_length as u32).to_le_bytes());
+        attribute[0x0E..0x10].copy_from_slice(&(id as u16).to_le_bytes());
+        attribute[0x10..0x14].copy_from_slice(&(data.len() as u32).to_le_bytes());
+        attribute[0x14..0x16].copy_from_slice(&0x18_u16.to_le_bytes());
+        attribute[0x18..0x18 + data.len()].copy_from_slice(data);
+
+        offset += attribute_length;
+    }
+    entry[offset..offset + 4].copy_from_slice(&[0xFF; 4]);
+    entry[0x18..0x1C].copy_from_slice(&(offset as u32 + 8).to_le_bytes());
+
+    // Move the last two bytes of each stride into the update sequence array.
+    let update_sequence = [0xCD, 0xAB];
+    entry[usa_offset..usa_offset + 2].copy_from_slice(&update_sequence);
+    for stride in 0..strides {
+        let end_of_stride = (stride + 1) * 512;
+        let fixup_offset = usa_offset + 2 + stride * 2;
+
+        entry.copy_within(end_of_stride - 2..end_of_stride, fixup_offset);
+        entry[end_of_stride - 2..end_of_stride].copy_from_slice(&update_sequence);
+    }
+
+    entry
+}
@@ -0,0 +1,58 @@
+//! Parsing of the `$UpCase` table, stored in the $DATA of MFT entry 10.
+//!
+//! NTFS compares file names case-insensitively by mapping every UTF-16 code unit
+//! through this table, rather than using the unicode uppercase rules.
+//! <https://github.com/libyal/libfsntfs/blob/main/documentation/New%20Technologies%20File%20System%20(NTFS).asciidoc#upcase>
+
+/// The table holds an uppercase mapping for every possible UTF-16 code unit.
+const UPCASE_TABLE_ENTRIES: usize = 0x10000;
+
+#[derive(Clone, Debug)]
+pub struct UpCaseTable {
+    table: Vec<u16>,
+}
+
+impl UpCaseTable {
+    /// Parses the content of the `$UpCase` file (a 128KiB array of little-endian UTF-16 code units).
+    /// If the data is truncated, the missing code units are mapped to themselves.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use mft::upcase::UpCaseTable;
+    ///
+    /// // Maps 'a' (0x61) to 'A' (0x41), all other characters are mapped to themselves.
+    /// let mut data: Vec<u8> = (0..=0x61_u16).flat_map(u16::to_le_bytes).collect();
+    /// data[0x61 * 2] = 0x41;
+    ///
+    /// let table = UpCaseTable::from_data(&data);
+    ///
+    /// assert_eq!(table.upcase(0x61), 0x41);
+    /// assert!(table.eq_ignore_case("abc", "Abc"));
+    /// assert!(!table.eq_ignore_case("abc", "ABC"));
+    /// ```
+    pub fn from_data(bytes: &[u8]) -> UpCaseTable {
+        let mut table: Vec<u16> = bytes
+            .chunks_exact(2)
+            .take(UPCASE_TABLE_ENTRIES)
+            .map(|c| u16::from_le_bytes([c[0], c[1]]))
+            .collect();
+
+        let parsed = table.len();
+        table.extend((parsed..UPCASE_TABLE_ENTRIES).map(|c| c as u16));
+
+        UpCaseTable { table }
+    }
+
+    /// Returns the uppercase mapping of a UTF-16 code unit.
+    pub fn upcase(&self, c: u16) -> u16 {
+        self.table[c as usize]
+    }
+
+    /// Compares two names the way NTFS does.
+    pub fn eq_ignore_case(&self, a: &str, b: &str) -> bool {
+        a.encode_utf16()
+            .map(|c| self.upcase(c))
+            .eq(b.encode_utf16().map(|c| self.upcase(c)))
+    }
+}
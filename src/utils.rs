@@ -1,8 +1,16 @@
+use crate::err::{Error, Result};
+
 use byteorder::ReadBytesExt;
+use log::trace;
+use std::borrow::Cow;
 use std::char::decode_utf16;
 use std::fmt::Write;
 use std::io::{self, Read, Seek};
 
+/// Fixups are written at the end of each 512-byte stride,
+/// even if the device has more (or less) than 512 bytes per sector.
+pub(crate) const SEQUENCE_NUMBER_STRIDE: usize = 512;
+
 pub fn to_hex_string(bytes: &[u8]) -> String {
     let len = bytes.len();
     // Each byte is represented by 2 ascii bytes.
@@ -47,3 +55,102 @@ pub fn read_utf16_string<T: Read + Seek>(stream: &mut T, len: Option<usize>) ->
         .map(|r| r.map_err(|_e| io::Error::from(io::ErrorKind::InvalidData)))
         .collect()
 }
+
+/// A buffer to which fixups can be applied.
+/// This allows borrowed buffers to only be copied if one of the fixups changes their content.
+pub trait FixupBuffer {
+    fn bytes(&self) -> &[u8];
+    fn bytes_mut(&mut self) -> &mut [u8];
+}
+
+impl FixupBuffer for [u8] {
+    fn bytes(&self) -> &[u8] {
+        self
+    }
+
+    fn bytes_mut(&mut self) -> &mut [u8] {
+        self
+    }
+}
+
+impl FixupBuffer for Cow<'_, [u8]> {
+    fn bytes(&self) -> &[u8] {
+        self
+    }
+
+    fn bytes_mut(&mut self) -> &mut [u8] {
+        self.to_mut()
+    }
+}
+
+/// Applies the update sequence array fixups of a multi-sector record (such as an MFT entry or an `INDX` record).
+/// https://docs.microsoft.com/en-us/windows/desktop/devnotes/multi-sector-header
+///
+/// The last two bytes of each 512-byte stride are expected to hold the update sequence (the first element of the array),
+/// and are replaced by the matching fixup of the array.
+/// All the fixups are applied, even if some strides do not hold the update sequence,
+/// in which case the first mismatch is returned as `Error::FailedToApplyFixup`.
+pub fn apply_usa_fixups<B: FixupBuffer + ?Sized>(
+    buffer: &mut B,
+    usa_offset: u16,
+    usa_size: u16,
+) -> Result<()> {
+    // There is one fixup per stride, but never more strides than the buffer holds.
+    let number_of_fixups =
+        usize::from(usa_size.saturating_sub(1)).min(buffer.bytes().len() / SEQUENCE_NUMBER_STRIDE);
+    trace!("Number of fixups: {}", number_of_fixups);
+
+    // Each fixup is a 2-byte element, and there are `usa_size` of them.
+    let fixups_start_offset = usize::from(usa_offset);
+    let fixups_end_offset = fixups_start_offset + usize::from(usa_size) * 2;
+
+    if fixups_end_offset > buffer.bytes().len() {
+        return Err(Error::UpdateSequenceArrayOutOfBounds {
+            usa_offset,
+            usa_size,
+            record_size: buffer.bytes().len(),
+        });
+    }
+
+    // The fixups are read one at a time (rather than copied out) to avoid allocating.
+    let fixup_at = |bytes: &[u8], index: usize| -> [u8; 2] {
+        let offset = fixups_start_offset + index * 2;
+        // There should always be bytes here, but just in case we put zeroes, so it will fail later.
+        bytes
+            .get(offset..offset + 2)
+            .map_or([0, 0], |fixup| [fixup[0], fixup[1]])
+    };
+
+    let update_sequence = fixup_at(buffer.bytes(), 0);
+    let mut first_mismatch = None;
+
+    // We need to compare each last two bytes each 512-bytes stride with the update_sequence,
+    // And if they match, replace those bytes with the matching bytes from the fixup_sequence.
+    for stride_number in 0_usize..number_of_fixups {
+        let fixup_bytes = fixup_at(buffer.bytes(), stride_number + 1);
+
+        let end_of_sector_bytes_end_offset = (stride_number + 1) * SEQUENCE_NUMBER_STRIDE;
+        let end_of_sector_bytes_start_offset = end_of_sector_bytes_end_offset - 2;
+
+        let end_of_sector_range = end_of_sector_bytes_start_offset..end_of_sector_bytes_end_offset;
+        let end_of_sector_bytes = &buffer.bytes()[end_of_sector_range.clone()];
+
+        if end_of_sector_bytes != update_sequence && first_mismatch.is_none() {
+            // An item in the block did not match the fixup array value
+            first_mismatch = Some(Error::FailedToApplyFixup {
+                stride_number,
+                end_of_sector_bytes: end_of_sector_bytes.to_vec(),
+                fixup_bytes: fixup_bytes.to_vec(),
+            });
+        }
+
+        if end_of_sector_bytes != fixup_bytes {
+            buffer.bytes_mut()[end_of_sector_range].copy_from_slice(&fixup_bytes);
+        }
+    }
+
+    match first_mismatch {
+        Some(e) => Err(e),
+        None => Ok(()),
+    }
+}
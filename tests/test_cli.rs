@@ -3,6 +3,7 @@ mod fixtures;
 use fixtures::*;
 
 use assert_cmd::prelude::*;
+use quick_xml::events::Event;
 use std::fs;
 use std::fs::File;
 use std::io::{Read, Write};
@@ -56,7 +57,59 @@ fn test_non_mft_file_is_error() {
     let mut cmd = Command::cargo_bin("mft_dump").expect("failed to find binary");
     cmd.args(&[f.to_str().unwrap()]);
 
-    cmd.assert().failure().code(1);
+    cmd.assert()
+        .failure()
+        .code(2)
+        .stderr(predicates::str::contains("File is too small to be an MFT"));
+}
+
+#[test]
+fn test_unreadable_file_exits_with_1() {
+    let d = tempdir().unwrap();
+
+    let mut cmd = Command::cargo_bin("mft_dump").expect("failed to find binary");
+    cmd.args([d.path().join("missing").to_str().unwrap()]);
+
+    cmd.assert()
+        .failure()
+        .code(1)
+        .stderr(predicates::str::contains("Failed to open file"));
+}
+
+#[test]
+fn test_file_with_bad_signature_exits_with_2() {
+    let d = tempdir().unwrap();
+
+    let f = d.as_ref().join("test.out");
+    File::create(&f).unwrap().write_all(&[0x41; 4096]).unwrap();
+
+    let mut cmd = Command::cargo_bin("mft_dump").expect("failed to find binary");
+    cmd.args(&[f.to_str().unwrap()]);
+
+    cmd.assert()
+        .failure()
+        .code(2)
+        .stderr(predicates::str::contains("Bad signature"));
+}
+
+#[test]
+fn test_corrupted_entry_with_fail_fast_exits_with_1() {
+    let d = tempdir().unwrap();
+
+    let f = d.as_ref().join("MFT");
+    let mut mft = fs::read(mft_sample()).unwrap();
+    mft.truncate(1024 * 100);
+    // Corrupt the signature of entry 30, the MFT itself is still valid.
+    mft[30 * 1024..30 * 1024 + 4].copy_from_slice(b"XXXX");
+    fs::write(&f, mft).unwrap();
+
+    let mut cmd = Command::cargo_bin("mft_dump").expect("failed to find binary");
+    cmd.args(["--fail-fast", "-o", "jsonl", f.to_str().unwrap()]);
+
+    cmd.assert()
+        .failure()
+        .code(1)
+        .stderr(predicates::str::contains("Failed to parse entry 30"));
 }
 
 #[test]
@@ -75,3 +128,804 @@ fn test_it_exports_resident_streams() {
 
     assert_eq!(fs::read_dir(d.path()).unwrap().count(), 2142)
 }
+
+#[test]
+fn test_it_writes_a_manifest_of_extracted_streams() {
+    let d = tempdir().unwrap();
+    let streams_dir = d.path().join("streams");
+    let manifest_path = d.path().join("manifest.json");
+
+    let sample = mft_sample();
+    let mut cmd = Command::cargo_bin("mft_dump").expect("failed to find binary");
+    cmd.args([
+        "-e",
+        &streams_dir.to_string_lossy(),
+        "--manifest",
+        &manifest_path.to_string_lossy(),
+        "-f",
+        &d.path().join("out.json").to_string_lossy(),
+        &sample.to_string_lossy(),
+    ]);
+
+    cmd.assert().success();
+
+    let manifest: serde_json::Value =
+        serde_json::from_reader(File::open(&manifest_path).unwrap()).unwrap();
+    let records = manifest.as_array().unwrap();
+
+    assert_eq!(records.len(), 2142);
+
+    for record in records {
+        let output_filename = record["output_filename"].as_str().unwrap();
+        let size = fs::metadata(output_filename).unwrap().len();
+
+        assert_eq!(record["size"].as_u64().unwrap(), size);
+        assert!(record["entry_id"].is_u64());
+        assert!(!record["full_path"].as_str().unwrap().is_empty());
+    }
+}
+
+#[test]
+fn test_manifest_requires_stream_extraction() {
+    let d = tempdir().unwrap();
+
+    let sample = mft_sample();
+    let mut cmd = Command::cargo_bin("mft_dump").expect("failed to find binary");
+    cmd.args([
+        "--manifest",
+        &d.path().join("manifest.csv").to_string_lossy(),
+        &sample.to_string_lossy(),
+    ]);
+
+    cmd.assert().failure();
+}
+
+#[cfg(feature = "zip")]
+#[test]
+fn test_it_exports_resident_streams_to_a_zip_archive() {
+    let d = tempdir().unwrap();
+    let archive_path = d.path().join("streams.zip");
+
+    let sample = mft_sample();
+    let mut cmd = Command::cargo_bin("mft_dump").expect("failed to find binary");
+    cmd.args([
+        "--extract-resident-streams-zip",
+        &archive_path.to_string_lossy(),
+        "-f",
+        &d.path().join("out.json").to_string_lossy(),
+        &sample.to_string_lossy(),
+    ]);
+
+    cmd.assert().success();
+
+    let archive = zip::ZipArchive::new(File::open(&archive_path).unwrap()).unwrap();
+
+    // The same streams as `test_it_exports_resident_streams`.
+    assert_eq!(archive.len(), 2142);
+    assert!(archive.file_names().all(|name| name.ends_with(".dontrun")));
+}
+
+#[cfg(not(feature = "zip"))]
+#[test]
+fn test_zip_archive_export_requires_feature() {
+    let d = tempdir().unwrap();
+
+    let sample = mft_sample();
+    let mut cmd = Command::cargo_bin("mft_dump").expect("failed to find binary");
+    cmd.args([
+        "--extract-resident-streams-zip",
+        &d.path().join("streams.zip").to_string_lossy(),
+        &sample.to_string_lossy(),
+    ]);
+
+    cmd.assert().failure().stderr(predicates::str::contains(
+        "rebuild `mft_dump` with the `zip` feature",
+    ));
+}
+
+#[test]
+fn test_csv_output_has_expected_header() {
+    let d = tempdir().unwrap();
+    let f = d.as_ref().join("test.csv");
+
+    let sample = mft_sample();
+    let mut cmd = Command::cargo_bin("mft_dump").expect("failed to find binary");
+    cmd.args([
+        "-o",
+        "csv",
+        "-f",
+        &f.to_string_lossy(),
+        sample.to_str().unwrap(),
+    ]);
+
+    cmd.assert().success();
+
+    let output = fs::read_to_string(&f).unwrap();
+    let header = output.lines().next().unwrap();
+
+    assert_eq!(
+        header,
+        "Signature,EntryId,Sequence,BaseEntryId,BaseEntrySequence,HardLinkCount,Flags,\
+         UsedEntrySize,TotalEntrySize,FirstAttributeRecordOffset,FirstAttributeId,LogfileSequenceNumber,FileSize,IsADirectory,IsDeleted,HasAlternateDataStreams,HasSuspiciousName,\
+         StandardInfoFlags,StandardInfoLastModified,StandardInfoLastAccess,StandardInfoCreated,Usn,\
+         FileNameFlags,FileNameLastModified,FileNameLastAccess,FileNameCreated,\
+         CreatedDeltaSecs,LastModifiedDeltaSecs,LastAccessDeltaSecs,ObjectIdTimestamp,\
+         ObjectId,BirthVolumeId,BirthObjectId,DomainId,ReparseTag,ReparseTarget,\
+         ParentReferenceStatus,IsOrphan,DataSizeConsistency,TimestompIndicators,FullPath"
+    );
+}
+
+#[test]
+fn test_it_summarizes_resident_content() {
+    let d = tempdir().unwrap();
+    let f = d.as_ref().join("test.jsonl");
+
+    let sample = mft_sample();
+    let mut cmd = Command::cargo_bin("mft_dump").expect("failed to find binary");
+    cmd.args([
+        "-o",
+        "jsonl",
+        "--summarize-content",
+        "-f",
+        &f.to_string_lossy(),
+        sample.to_str().unwrap(),
+    ]);
+
+    cmd.assert().success();
+
+    let output = fs::read_to_string(&f).unwrap();
+    let mut omitted = 0;
+
+    for line in output.lines() {
+        let entry: serde_json::Value = serde_json::from_str(line).unwrap();
+
+        for attribute in entry["attributes"].as_array().unwrap() {
+            if attribute["data"]["content_omitted"] == serde_json::Value::Bool(true) {
+                assert!(attribute["data"]["length"].is_u64());
+                omitted += 1;
+            }
+        }
+    }
+
+    assert!(omitted > 0);
+}
+
+#[test]
+fn test_fail_fast_stops_on_first_error() {
+    let d = tempdir().unwrap();
+    let f = d.as_ref().join("corrupted_mft");
+
+    let mut sample = vec![0; 1024 * 4];
+    File::open(mft_sample())
+        .unwrap()
+        .read_exact(&mut sample)
+        .unwrap();
+    // Corrupt the signature of the second entry.
+    sample[1024..1028].copy_from_slice(b"XXXX");
+    fs::write(&f, &sample).unwrap();
+
+    let mut cmd = Command::cargo_bin("mft_dump").expect("failed to find binary");
+    cmd.args(["-o", "jsonl", &f.to_string_lossy()]);
+    cmd.assert().success();
+
+    let mut cmd = Command::cargo_bin("mft_dump").expect("failed to find binary");
+    cmd.args(["--fail-fast", "-o", "jsonl", &f.to_string_lossy()]);
+    cmd.assert()
+        .failure()
+        .stderr(predicates::str::contains("Failed to parse entry 1"));
+}
+
+#[test]
+fn test_it_outputs_only_entries_with_nonresident_data() {
+    let sample = mft_sample();
+    let mut cmd = Command::cargo_bin("mft_dump").expect("failed to find binary");
+    cmd.args([
+        "--only-nonresident-data",
+        "-o",
+        "jsonl",
+        "-r",
+        "0-100",
+        &sample.to_string_lossy(),
+    ]);
+
+    let output = cmd.output().unwrap();
+    assert!(output.status.success());
+
+    let entries: Vec<serde_json::Value> = String::from_utf8(output.stdout)
+        .unwrap()
+        .lines()
+        .map(|line| serde_json::from_str(line).unwrap())
+        .collect();
+
+    assert!(!entries.is_empty());
+    // $MFT has non-resident data, the root directory does not.
+    assert_eq!(entries[0]["header"]["record_number"], 0);
+    assert!(entries
+        .iter()
+        .all(|entry| entry["header"]["record_number"] != 5));
+
+    for entry in entries {
+        let has_data_runs = entry["attributes"].as_array().unwrap().iter().any(|a| {
+            a["header"]["type_code"] == "DATA" && a["data"]["data_runs"].as_array().is_some()
+        });
+        assert!(has_data_runs);
+    }
+}
+
+#[test]
+fn test_it_outputs_a_json_array() {
+    let sample = mft_sample();
+    let mut cmd = Command::cargo_bin("mft_dump").expect("failed to find binary");
+    cmd.args(["-o", "json-array", "-r", "0-5", &sample.to_string_lossy()]);
+
+    let output = cmd.output().unwrap();
+    assert!(output.status.success());
+
+    let entries: serde_json::Value = serde_json::from_slice(&output.stdout).unwrap();
+    let entries = entries.as_array().unwrap();
+
+    assert_eq!(entries.len(), 6);
+    assert_eq!(entries[5]["header"]["record_number"], 5);
+}
+
+#[test]
+fn test_it_outputs_a_compact_json_array() {
+    let sample = mft_sample();
+    let mut cmd = Command::cargo_bin("mft_dump").expect("failed to find binary");
+    cmd.args([
+        "-o",
+        "json",
+        "--compact",
+        "-r",
+        "0-5",
+        &sample.to_string_lossy(),
+    ]);
+
+    let output = cmd.output().unwrap();
+    assert!(output.status.success());
+
+    // The whole array is on a single line.
+    assert_eq!(output.stdout.iter().filter(|&&b| b == b'\n').count(), 1);
+
+    let entries: serde_json::Value = serde_json::from_slice(&output.stdout).unwrap();
+    let entries = entries.as_array().unwrap();
+
+    assert_eq!(entries.len(), 6);
+    assert_eq!(entries[5]["header"]["record_number"], 5);
+}
+
+#[test]
+fn test_it_outputs_an_empty_json_array() {
+    let sample = mft_sample();
+    let mut cmd = Command::cargo_bin("mft_dump").expect("failed to find binary");
+    // Entries past the end of the MFT are skipped.
+    cmd.args(["-o", "json-array", "-r", "20000", &sample.to_string_lossy()]);
+
+    let output = cmd.output().unwrap();
+    assert!(output.status.success());
+
+    let entries: serde_json::Value = serde_json::from_slice(&output.stdout).unwrap();
+    assert_eq!(entries, serde_json::json!([]));
+}
+
+#[test]
+fn test_it_formats_record_numbers() {
+    let sample = mft_sample();
+
+    let mut cmd = Command::cargo_bin("mft_dump").expect("failed to find binary");
+    cmd.args([
+        "--record-number-format",
+        "hex",
+        "-o",
+        "jsonl",
+        "-r",
+        "31",
+        &sample.to_string_lossy(),
+    ]);
+    let output = cmd.output().unwrap();
+    assert!(output.status.success());
+
+    let entry: serde_json::Value = serde_json::from_slice(&output.stdout).unwrap();
+    assert_eq!(entry["header"]["record_number"], "0x1f");
+
+    let mut cmd = Command::cargo_bin("mft_dump").expect("failed to find binary");
+    cmd.args([
+        "--record-number-format",
+        "padded",
+        "-o",
+        "csv",
+        "-r",
+        "5",
+        &sample.to_string_lossy(),
+    ]);
+    let output = cmd.output().unwrap();
+    assert!(output.status.success());
+
+    let output = String::from_utf8(output.stdout).unwrap();
+    let mut lines = output.lines();
+    assert!(lines.next().unwrap().starts_with("Signature,EntryId,"));
+    assert!(lines.next().unwrap().starts_with("FILE,00005,"));
+    assert_eq!(lines.next(), None);
+}
+
+#[test]
+fn test_it_prints_a_tree() {
+    let sample = mft_sample();
+    let mut cmd = Command::cargo_bin("mft_dump").expect("failed to find binary");
+    cmd.args(["--format", "tree", "-r", "0-40", &sample.to_string_lossy()]);
+
+    let output = cmd.output().unwrap();
+    assert!(output.status.success());
+
+    let stdout = String::from_utf8(output.stdout).unwrap();
+    let lines: Vec<&str> = stdout.lines().collect();
+
+    assert!(lines.contains(&"$MFT"));
+    // Entries are listed right after their parent directory, and indented.
+    let extend = lines.iter().position(|l| *l == "$Extend/").unwrap();
+    assert!(lines[extend + 1..].contains(&"    $Quota"));
+    assert!(lines[extend + 1].starts_with("    "));
+}
+
+#[test]
+fn test_it_prints_volume_info() {
+    let sample = mft_sample();
+    let mut cmd = Command::cargo_bin("mft_dump").expect("failed to find binary");
+    cmd.args(["--volume-info", &sample.to_string_lossy()]);
+
+    cmd.assert()
+        .success()
+        .stdout("Volume label: \nNTFS version: 3.1\nVolume flags: 0x0000\n");
+}
+
+#[test]
+fn test_it_emits_raw_filetimes() {
+    let sample = mft_sample();
+    let mut cmd = Command::cargo_bin("mft_dump").expect("failed to find binary");
+    cmd.args([
+        "--filetime-raw",
+        "-o",
+        "jsonl",
+        "-r",
+        "0",
+        &sample.to_string_lossy(),
+    ]);
+
+    let output = cmd.output().unwrap();
+    assert!(output.status.success());
+
+    let entry: serde_json::Value = serde_json::from_slice(&output.stdout).unwrap();
+    let standard_info = &entry["attributes"][0]["data"];
+
+    assert_eq!(standard_info["created"], "2007-06-30T12:50:52.252395200Z");
+    assert_eq!(standard_info["created_filetime"], 128276814522523952_u64);
+    assert!(standard_info["accessed_filetime"].is_u64());
+
+    let file_name = &entry["attributes"][1]["data"];
+    assert_eq!(file_name["name"], "$MFT");
+    assert_eq!(file_name["created_filetime"], 128276814522523952_u64);
+}
+
+#[test]
+fn test_it_reports_slowest_entries() {
+    let sample = mft_sample();
+    let mut cmd = Command::cargo_bin("mft_dump").expect("failed to find binary");
+    cmd.args([
+        "--time-entries",
+        "3",
+        "-o",
+        "jsonl",
+        "-r",
+        "0-20",
+        &sample.to_string_lossy(),
+    ]);
+
+    let output = cmd.output().unwrap();
+    assert!(output.status.success());
+
+    let stderr = String::from_utf8(output.stderr).unwrap();
+    let mut lines = stderr.lines();
+
+    assert_eq!(lines.next(), Some("Slowest entries:"));
+    assert_eq!(lines.filter(|l| l.starts_with("    entry ")).count(), 3);
+}
+
+#[test]
+fn test_it_reports_entries_which_differ_from_the_mirror() {
+    let d = tempdir().unwrap();
+    let mirror_path = d.as_ref().join("mftmirr");
+
+    let mut mirror = vec![0; 1024 * 4];
+    File::open(mft_sample())
+        .unwrap()
+        .read_exact(&mut mirror)
+        .unwrap();
+    // Corrupt the content of the $Volume entry.
+    mirror[3 * 1024 + 0x100] ^= 0xFF;
+    fs::write(&mirror_path, &mirror).unwrap();
+
+    let sample = mft_sample();
+    let mut cmd = Command::cargo_bin("mft_dump").expect("failed to find binary");
+    cmd.args([
+        "--check-mirror",
+        &mirror_path.to_string_lossy(),
+        "-o",
+        "jsonl",
+        "-r",
+        "0",
+        &sample.to_string_lossy(),
+    ]);
+
+    cmd.assert()
+        .success()
+        .stderr("Entry 3 differs from its copy in $MFTMirr\n");
+}
+
+#[cfg(feature = "arrow")]
+#[test]
+fn test_it_writes_arrow_stream() {
+    let sample = mft_sample();
+    let mut cmd = Command::cargo_bin("mft_dump").expect("failed to find binary");
+    cmd.args(["-o", "arrow", "-r", "0-10", &sample.to_string_lossy()]);
+
+    let output = cmd.output().unwrap();
+    assert!(output.status.success());
+
+    let reader =
+        arrow_ipc::reader::StreamReader::try_new(std::io::Cursor::new(output.stdout), None)
+            .unwrap();
+    let rows: usize = reader.map(|batch| batch.unwrap().num_rows()).sum();
+
+    assert_eq!(rows, 11);
+}
+
+#[cfg(not(feature = "arrow"))]
+#[test]
+fn test_arrow_output_requires_feature() {
+    let sample = mft_sample();
+    let mut cmd = Command::cargo_bin("mft_dump").expect("failed to find binary");
+    cmd.args(["-o", "arrow", &sample.to_string_lossy()]);
+
+    cmd.assert().failure().stderr(predicates::str::contains(
+        "rebuild `mft_dump` with the `arrow` feature",
+    ));
+}
+
+#[cfg(feature = "parquet")]
+#[test]
+fn test_it_writes_parquet_file() {
+    let d = tempdir().unwrap();
+    let f = d.as_ref().join("entries.parquet");
+
+    let sample = mft_sample();
+    let mut cmd = Command::cargo_bin("mft_dump").expect("failed to find binary");
+    cmd.args([
+        "-o",
+        "parquet",
+        "-r",
+        "0-10",
+        "-f",
+        &f.to_string_lossy(),
+        &sample.to_string_lossy(),
+    ]);
+
+    cmd.assert().success();
+
+    let reader = parquet::arrow::arrow_reader::ParquetRecordBatchReader::try_new(
+        File::open(&f).unwrap(),
+        1024,
+    )
+    .unwrap();
+    let rows: usize = reader.map(|batch| batch.unwrap().num_rows()).sum();
+
+    assert_eq!(rows, 11);
+}
+
+#[cfg(feature = "parquet")]
+#[test]
+fn test_parquet_output_requires_a_file() {
+    let sample = mft_sample();
+    let mut cmd = Command::cargo_bin("mft_dump").expect("failed to find binary");
+    cmd.args(["-o", "parquet", &sample.to_string_lossy()]);
+
+    cmd.assert()
+        .failure()
+        .stderr(predicates::str::contains("pass a file with `--output`"));
+}
+
+#[cfg(not(feature = "parquet"))]
+#[test]
+fn test_parquet_output_requires_feature() {
+    let sample = mft_sample();
+    let mut cmd = Command::cargo_bin("mft_dump").expect("failed to find binary");
+    cmd.args(["-o", "parquet", &sample.to_string_lossy()]);
+
+    cmd.assert().failure().stderr(predicates::str::contains(
+        "rebuild `mft_dump` with the `parquet` feature",
+    ));
+}
+
+#[test]
+fn test_it_emits_alternate_data_streams() {
+    let sample = mft_sample();
+    let mut cmd = Command::cargo_bin("mft_dump").expect("failed to find binary");
+    cmd.args([
+        "-o",
+        "csv",
+        "--emit-ads",
+        "-r",
+        "0,12938",
+        &sample.to_string_lossy(),
+    ]);
+
+    let output = cmd.output().unwrap();
+    assert!(output.status.success());
+
+    let mut reader = csv::Reader::from_reader(output.stdout.as_slice());
+    let headers = reader.headers().unwrap().clone();
+    let column = |name: &str| headers.iter().position(|header| header == name).unwrap();
+    let (entry_id, file_size, stream_name) =
+        (column("EntryId"), column("FileSize"), column("StreamName"));
+
+    let rows: Vec<(String, String, String)> = reader
+        .records()
+        .map(|record| {
+            let record = record.unwrap();
+            (
+                record[entry_id].to_string(),
+                record[file_size].to_string(),
+                record[stream_name].to_string(),
+            )
+        })
+        .collect();
+
+    // Entry 12938 has a `Zone.Identifier` stream, which gets its own row after the row of the entry.
+    assert_eq!(rows.len(), 3);
+    assert_eq!((rows[0].0.as_str(), rows[0].2.as_str()), ("0", ""));
+    assert_eq!((rows[1].0.as_str(), rows[1].2.as_str()), ("12938", ""));
+    assert_eq!(
+        rows[2],
+        (
+            "12938".to_string(),
+            "26".to_string(),
+            "Zone.Identifier".to_string()
+        )
+    );
+}
+
+#[cfg(feature = "hash")]
+#[test]
+fn test_it_hashes_resident_data() {
+    let sample = mft_sample();
+    let mut cmd = Command::cargo_bin("mft_dump").expect("failed to find binary");
+    cmd.args([
+        "-o",
+        "csv",
+        "--hash",
+        "-r",
+        "0,12938",
+        &sample.to_string_lossy(),
+    ]);
+
+    let output = cmd.output().unwrap();
+    assert!(output.status.success());
+
+    let mut reader = csv::Reader::from_reader(output.stdout.as_slice());
+    assert_eq!(reader.headers().unwrap().iter().next_back(), Some("Md5"));
+
+    let hashes: Vec<String> = reader
+        .records()
+        .map(|record| record.unwrap().iter().next_back().unwrap().to_string())
+        .collect();
+
+    // The data of $MFT is non-resident, entry 12938 has a resident `Zone.Identifier` stream.
+    assert_eq!(hashes, vec!["", "fbccf14d504b7b2dbcb5a5bda75bd93b"]);
+}
+
+#[cfg(not(feature = "hash"))]
+#[test]
+fn test_hash_requires_feature() {
+    let sample = mft_sample();
+    let mut cmd = Command::cargo_bin("mft_dump").expect("failed to find binary");
+    cmd.args(["-o", "csv", "--hash", &sample.to_string_lossy()]);
+
+    cmd.assert().failure().stderr(predicates::str::contains(
+        "rebuild `mft_dump` with the `hash` feature",
+    ));
+}
+
+#[cfg(feature = "gzip")]
+#[test]
+fn test_it_compresses_output_with_gzip() {
+    let d = tempdir().unwrap();
+    let f = d.as_ref().join("test.jsonl.gz");
+
+    let sample = mft_sample();
+    let mut cmd = Command::cargo_bin("mft_dump").expect("failed to find binary");
+    cmd.args([
+        "--compress",
+        "gzip",
+        "-o",
+        "jsonl",
+        "-r",
+        "0-10",
+        "-f",
+        &f.to_string_lossy(),
+        &sample.to_string_lossy(),
+    ]);
+    cmd.assert().success();
+
+    let mut output = String::new();
+    flate2::read::GzDecoder::new(File::open(&f).unwrap())
+        .read_to_string(&mut output)
+        .unwrap();
+
+    assert_eq!(output.lines().count(), 11);
+    for line in output.lines() {
+        serde_json::from_str::<serde_json::Value>(line).unwrap();
+    }
+}
+
+#[cfg(feature = "zstd")]
+#[test]
+fn test_it_compresses_output_with_zstd() {
+    let d = tempdir().unwrap();
+    let f = d.as_ref().join("test.csv.zst");
+
+    let sample = mft_sample();
+    let mut cmd = Command::cargo_bin("mft_dump").expect("failed to find binary");
+    cmd.args([
+        "--compress",
+        "zstd",
+        "-o",
+        "csv",
+        "-r",
+        "0-10",
+        "-f",
+        &f.to_string_lossy(),
+        &sample.to_string_lossy(),
+    ]);
+    cmd.assert().success();
+
+    let output = String::from_utf8(zstd::decode_all(File::open(&f).unwrap()).unwrap()).unwrap();
+
+    assert!(output.starts_with("Signature,EntryId,"));
+    assert_eq!(output.lines().count(), 12);
+}
+
+#[test]
+fn test_it_prints_only_paths() {
+    let sample = mft_sample();
+    let mut cmd = Command::cargo_bin("mft_dump").expect("failed to find binary");
+    cmd.args(["--format", "paths", "-r", "0-3", &sample.to_string_lossy()]);
+
+    cmd.assert()
+        .success()
+        .stdout("RecordNumber,FullPath\n0,$MFT\n1,$MFTMirr\n2,$LogFile\n3,$Volume\n");
+}
+
+#[test]
+fn test_it_prints_bodyfile() {
+    let sample = mft_sample();
+    let mut cmd = Command::cargo_bin("mft_dump").expect("failed to find binary");
+    cmd.args(["-o", "bodyfile", "-r", "0-1,5", &sample.to_string_lossy()]);
+
+    cmd.assert().success().stdout(concat!(
+        "0|$MFT|0|r/rrwxrwxrwx|0|0|13402112|1183207852|1183207852|1183207852|1183207852\n",
+        "0|$MFTMirr|1|r/rrwxrwxrwx|0|0|4096|1183207852|1183207852|1183207852|1183207852\n",
+        "0|.|5|d/drwxrwxrwx|0|0|0|1232384001|1232338664|1232338664|1183207852\n",
+    ));
+}
+
+#[test]
+fn test_it_prints_xml() {
+    let sample = mft_sample();
+    let mut cmd = Command::cargo_bin("mft_dump").expect("failed to find binary");
+    cmd.args(["-o", "xml", "-r", "0-9", &sample.to_string_lossy()]);
+
+    let output = cmd.output().unwrap();
+    assert!(output.status.success());
+
+    let mut reader = quick_xml::Reader::from_reader(output.stdout.as_slice());
+    let mut buffer = vec![];
+    let mut depth = 0;
+    let mut elements = vec![];
+
+    // The reader fails on mismatched tags, so reaching the end means the document is well formed.
+    loop {
+        match reader.read_event_into(&mut buffer).unwrap() {
+            Event::Start(element) => {
+                if depth < 2 {
+                    elements.push(String::from_utf8(element.name().as_ref().to_vec()).unwrap());
+                }
+                depth += 1;
+            }
+            Event::End(_) => depth -= 1,
+            Event::Eof => break,
+            _ => {}
+        }
+        buffer.clear();
+    }
+
+    assert_eq!(depth, 0);
+    assert_eq!(elements[0], "Mft");
+    assert_eq!(elements.len(), 11);
+    assert!(elements[1..].iter().all(|name| name == "Entry"));
+}
+
+#[test]
+fn test_it_filters_entries_by_path() {
+    let sample = mft_sample();
+    let mut cmd = Command::cargo_bin("mft_dump").expect("failed to find binary");
+    cmd.args([
+        "-o",
+        "bodyfile",
+        "-r",
+        "0-30",
+        "--path-filter",
+        "$mft*",
+        &sample.to_string_lossy(),
+    ]);
+
+    cmd.assert().success().stdout(concat!(
+        "0|$MFT|0|r/rrwxrwxrwx|0|0|13402112|1183207852|1183207852|1183207852|1183207852\n",
+        "0|$MFTMirr|1|r/rrwxrwxrwx|0|0|4096|1183207852|1183207852|1183207852|1183207852\n",
+    ));
+}
+
+#[test]
+fn test_it_filters_paths_case_sensitively() {
+    let sample = mft_sample();
+    let mut cmd = Command::cargo_bin("mft_dump").expect("failed to find binary");
+    cmd.args([
+        "-o",
+        "paths",
+        "-r",
+        "0-30",
+        "--path-filter",
+        "$Extend\\$*",
+        "--case-sensitive",
+        &sample.to_string_lossy(),
+    ]);
+
+    cmd.assert().success().stdout(concat!(
+        "RecordNumber,FullPath\n",
+        "24,$Extend/$Quota\n",
+        "25,$Extend/$ObjId\n",
+        "26,$Extend/$Reparse\n",
+    ));
+
+    let mut cmd = Command::cargo_bin("mft_dump").expect("failed to find binary");
+    cmd.args([
+        "-o",
+        "paths",
+        "-r",
+        "0-30",
+        "--path-filter",
+        "$extend/*",
+        "--case-sensitive",
+        &sample.to_string_lossy(),
+    ]);
+
+    cmd.assert().success().stdout("");
+}
+
+#[test]
+fn test_it_prints_bodyfile_with_both_timestamp_sources() {
+    let sample = mft_sample();
+    let mut cmd = Command::cargo_bin("mft_dump").expect("failed to find binary");
+    cmd.args([
+        "-o",
+        "bodyfile",
+        "--timestamp-source",
+        "both",
+        "-r",
+        "5",
+        &sample.to_string_lossy(),
+    ]);
+
+    cmd.assert().success().stdout(concat!(
+        "0|.|5|d/drwxrwxrwx|0|0|0|1232384001|1232338664|1232338664|1183207852\n",
+        "0|. ($FILE_NAME)|5|d/drwxrwxrwx|0|0|0|1183207852|1183207852|1183207852|1183207852\n",
+    ));
+}
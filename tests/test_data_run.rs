@@ -4,6 +4,11 @@ use fixtures::*;
 use mft::mft::MftParser;
 use mft::attribute::{MftAttribute, MftAttributeType};
 use mft::attribute::data_run::{DataRun, RunType, decode_data_runs};
+use mft::attribute::header::{MftAttributeHeader, NonResidentHeader, ResidentialHeader};
+use mft::attribute::non_resident_attr::{combine_data_runs, ByteRun, NonResidentAttr};
+use mft::attribute::MftAttributeContent;
+use mft::err::Error;
+use std::io::Cursor;
 
 #[test]
 fn test_runs() {
@@ -21,7 +26,8 @@ fn test_runs() {
         Some(vec![
             DataRun {lcn_length: 0x30, lcn_offset: 0x20, run_type: RunType::Standard},
             DataRun {lcn_length: 0x60, lcn_offset: 0, run_type: RunType::Sparse},
-            DataRun {lcn_length: 0x10, lcn_offset: 0x30, run_type: RunType::Standard},
+            // Relative to the previous non-sparse run.
+            DataRun {lcn_length: 0x10, lcn_offset: 0x50, run_type: RunType::Standard},
         ])
     );
 
@@ -94,6 +100,45 @@ fn test_runs() {
     );
 }
 
+#[test]
+fn test_runs_after_sparse_runs() {
+    // Like in compressed streams, each compression unit ends with a sparse run.
+    // Offsets are relative to the last non-sparse run, the sparse runs in between don't count.
+    assert_eq!(
+        decode_data_runs(&[
+            0x21, 0x04, 0x00, 0x01, 0x01, 0x0C, 0x11, 0x04, 0x10, 0x01, 0x0C, 0x11, 0x04, 0xF0,
+            0x00
+        ]),
+        Some(vec![
+            DataRun {
+                lcn_length: 4,
+                lcn_offset: 0x100,
+                run_type: RunType::Standard
+            },
+            DataRun {
+                lcn_length: 12,
+                lcn_offset: 0,
+                run_type: RunType::Sparse
+            },
+            DataRun {
+                lcn_length: 4,
+                lcn_offset: 0x110,
+                run_type: RunType::Standard
+            },
+            DataRun {
+                lcn_length: 12,
+                lcn_offset: 0,
+                run_type: RunType::Sparse
+            },
+            DataRun {
+                lcn_length: 4,
+                lcn_offset: 0x100,
+                run_type: RunType::Standard
+            },
+        ])
+    );
+}
+
 #[test]
 // if this test fails, most likely the datarun_offset is not being respected
 fn test_data_runs_at_offset() {
@@ -113,3 +158,343 @@ fn test_data_runs_at_offset() {
         }
     }
 }
+
+#[test]
+fn test_logical_extents() {
+    let data_runs =
+        decode_data_runs(&[0x11, 0x30, 0x20, 0x01, 0x60, 0x11, 0x10, 0x30, 0x00]).unwrap();
+    let attr = NonResidentAttr { data_runs };
+
+    assert_eq!(
+        attr.logical_extents(4096),
+        vec![
+            (0, 0x30 * 4096, true),
+            (0x30 * 4096, 0x60 * 4096, false),
+            (0x90 * 4096, 0x10 * 4096, true),
+        ]
+    );
+}
+
+#[test]
+fn test_byte_runs() {
+    let data_runs =
+        decode_data_runs(&[0x11, 0x30, 0x20, 0x01, 0x60, 0x11, 0x10, 0x30, 0x00]).unwrap();
+    let attr = NonResidentAttr { data_runs };
+
+    assert_eq!(
+        attr.byte_runs(4096),
+        vec![
+            ByteRun {
+                offset: Some(0x20 * 4096),
+                length: 0x30 * 4096
+            },
+            ByteRun {
+                offset: None,
+                length: 0x60 * 4096
+            },
+            ByteRun {
+                offset: Some(0x50 * 4096),
+                length: 0x10 * 4096
+            },
+        ]
+    );
+}
+
+#[test]
+fn test_byte_runs_of_sparse_file() {
+    let sample = mft_sample_name("entry_data_run_at_offset");
+    let mut parser = MftParser::from_path(sample).unwrap();
+    let entry = parser.get_entry(0).unwrap();
+
+    let attribute = entry
+        .iter_attributes_matching(Some(vec![MftAttributeType::DATA]))
+        .filter_map(Result::ok)
+        .next()
+        .unwrap();
+
+    let header = match &attribute.header.residential_header {
+        ResidentialHeader::NonResident(header) => header.clone(),
+        ResidentialHeader::Resident(_) => panic!("The $DATA attribute should be non-resident"),
+    };
+    let byte_runs = attribute.data.into_data_runs().unwrap().byte_runs(4096);
+
+    assert_eq!(byte_runs.len(), 53);
+    assert_eq!(
+        byte_runs[0],
+        ByteRun {
+            offset: None,
+            length: 517248 * 4096
+        }
+    );
+    assert_eq!(
+        byte_runs[1],
+        ByteRun {
+            offset: Some(3961442 * 4096),
+            length: 71 * 4096
+        }
+    );
+
+    // The runs cover the whole allocation, which extends past the end of the stream.
+    let total_length: u64 = byte_runs.iter().map(|run| run.length).sum();
+    assert_eq!(total_length, header.allocated_length);
+    assert_eq!(header.logical_size(), Some(2152925272));
+}
+
+#[test]
+fn test_slack_bytes() {
+    let attr = NonResidentAttr { data_runs: vec![] };
+    let mut header = NonResidentHeader {
+        vnc_first: 0,
+        vnc_last: 2,
+        datarun_offset: 0x40,
+        unit_compression_size: 0,
+        padding: 0,
+        allocated_length: 3 * 4096,
+        file_size: 2 * 4096 + 100,
+        valid_data_length: 2 * 4096 + 100,
+        total_allocated: None,
+    };
+
+    assert_eq!(attr.slack_bytes(&header, 4096), 4096 - 100);
+
+    // Compressed streams are allocated in compression units, only the last cluster is slack.
+    header.unit_compression_size = 4;
+    header.allocated_length = 16 * 4096;
+    header.total_allocated = Some(3 * 4096);
+    assert_eq!(attr.slack_bytes(&header, 4096), 4096 - 100);
+
+    // Sizes are not valid in extension records.
+    header.vnc_first = 3;
+    assert_eq!(attr.slack_bytes(&header, 4096), 0);
+}
+
+#[test]
+fn test_read_non_resident_data_zero_fills_uninitialized_tail() {
+    // 4 clusters of 16 bytes, each filled with its LCN + 1.
+    let volume: Vec<u8> = (1..=4).flat_map(|lcn| vec![lcn as u8; 16]).collect();
+    let attr = NonResidentAttr {
+        data_runs: vec![
+            DataRun {
+                lcn_offset: 1,
+                lcn_length: 1,
+                run_type: RunType::Standard,
+            },
+            DataRun {
+                lcn_offset: 0,
+                lcn_length: 1,
+                run_type: RunType::Sparse,
+            },
+            DataRun {
+                lcn_offset: 3,
+                lcn_length: 1,
+                run_type: RunType::Standard,
+            },
+        ],
+    };
+    let mut header = NonResidentHeader {
+        vnc_first: 0,
+        vnc_last: 2,
+        datarun_offset: 0x40,
+        unit_compression_size: 0,
+        padding: 0,
+        allocated_length: 3 * 16,
+        file_size: 3 * 16 - 4,
+        valid_data_length: 3 * 16 - 4,
+        total_allocated: None,
+    };
+
+    assert!(!header.has_uninitialized_tail());
+    let data = attr
+        .read_non_resident_data(&mut Cursor::new(&volume), &header, 16)
+        .unwrap();
+    assert_eq!(data.len(), 44);
+    assert_eq!(&data[..16], &[2; 16]);
+    assert_eq!(&data[16..32], &[0; 16]);
+    assert_eq!(&data[32..], &[4; 12]);
+
+    // Only the first 4 bytes of the last cluster were written.
+    header.valid_data_length = 2 * 16 + 4;
+    assert!(header.has_uninitialized_tail());
+    let data = attr
+        .read_non_resident_data(&mut Cursor::new(&volume), &header, 16)
+        .unwrap();
+    assert_eq!(data.len(), 44);
+    assert_eq!(&data[32..36], &[4; 4]);
+    assert_eq!(&data[36..], &[0; 8]);
+
+    // Sizes are not valid in extension records.
+    header.vnc_first = 3;
+    assert!(!header.has_uninitialized_tail());
+    assert!(attr
+        .read_non_resident_data(&mut Cursor::new(&volume), &header, 16)
+        .is_err());
+}
+
+#[test]
+fn test_read_non_resident_data_rejects_corrupted_sizes() {
+    let volume = vec![0_u8; 64];
+    let attr = NonResidentAttr {
+        data_runs: vec![DataRun {
+            lcn_offset: 1,
+            lcn_length: 2,
+            run_type: RunType::Standard,
+        }],
+    };
+    let header = NonResidentHeader {
+        vnc_first: 0,
+        vnc_last: 1,
+        datarun_offset: 0x40,
+        unit_compression_size: 0,
+        padding: 0,
+        allocated_length: 2 * 16,
+        file_size: u64::MAX,
+        valid_data_length: u64::MAX,
+        total_allocated: None,
+    };
+
+    // A `file_size` past the end of the runs fails before anything is allocated.
+    assert!(matches!(
+        attr.read_non_resident_data(&mut Cursor::new(&volume), &header, 16),
+        Err(Error::StreamSizeExceedsDataRuns {
+            file_size: u64::MAX,
+            runs_length: 32
+        })
+    ));
+
+    let attr = NonResidentAttr {
+        data_runs: vec![DataRun {
+            lcn_offset: u64::MAX / 2,
+            lcn_length: 1,
+            run_type: RunType::Standard,
+        }],
+    };
+    assert!(matches!(
+        attr.read_non_resident_data(&mut Cursor::new(&volume), &header, 16),
+        Err(Error::DataRunOverflow { .. })
+    ));
+
+    // A huge run (and stream) is only read as far as the volume goes, without reserving its whole size upfront.
+    let attr = NonResidentAttr {
+        data_runs: vec![DataRun {
+            lcn_offset: 1,
+            lcn_length: 1 << 36,
+            run_type: RunType::Standard,
+        }],
+    };
+    let header = NonResidentHeader {
+        file_size: 1 << 40,
+        valid_data_length: 1 << 40,
+        ..header
+    };
+    assert!(matches!(
+        attr.read_non_resident_data(&mut Cursor::new(&volume), &header, 16),
+        Err(Error::IoError { .. })
+    ));
+}
+
+/// Builds a non-resident, unnamed $DATA attribute covering the given VCN range.
+fn non_resident_data_attribute(vnc_first: u64, vnc_last: u64, data_runs: &[u8]) -> MftAttribute {
+    let mut raw = vec![0_u8; 0x40];
+    raw[0x00..0x04].copy_from_slice(&0x80_u32.to_le_bytes());
+    raw[0x08] = 1;
+    raw[0x0A..0x0C].copy_from_slice(&0x40_u16.to_le_bytes());
+    raw[0x10..0x18].copy_from_slice(&vnc_first.to_le_bytes());
+    raw[0x18..0x20].copy_from_slice(&vnc_last.to_le_bytes());
+    raw[0x20..0x22].copy_from_slice(&0x40_u16.to_le_bytes());
+    // Valid data length, data runs are only decoded if it is non-zero.
+    raw[0x38..0x40].copy_from_slice(&1_u64.to_le_bytes());
+    raw.extend_from_slice(data_runs);
+    raw.resize((raw.len() + 7) & !7, 0);
+    let record_length = raw.len() as u32;
+    raw[0x04..0x08].copy_from_slice(&record_length.to_le_bytes());
+
+    let mut cursor = Cursor::new(raw);
+    let header = MftAttributeHeader::from_stream(&mut cursor)
+        .unwrap()
+        .unwrap();
+    let data = match &header.residential_header {
+        ResidentialHeader::NonResident(resident) => {
+            MftAttributeContent::from_stream_non_resident(&mut cursor, &header, resident).unwrap()
+        }
+        ResidentialHeader::Resident(_) => unreachable!(),
+    };
+
+    MftAttribute { header, data }
+}
+
+#[test]
+fn test_combine_data_runs() {
+    let first = non_resident_data_attribute(0, 0x2F, &[0x11, 0x30, 0x20, 0x00]);
+    let second = non_resident_data_attribute(0x30, 0x3F, &[0x11, 0x10, 0x60, 0x00]);
+
+    let combined = combine_data_runs(&[second.clone(), first.clone()]).unwrap();
+
+    assert_eq!(
+        combined.data_runs,
+        vec![
+            DataRun {
+                lcn_offset: 0x20,
+                lcn_length: 0x30,
+                run_type: RunType::Standard
+            },
+            DataRun {
+                lcn_offset: 0x60,
+                lcn_length: 0x10,
+                run_type: RunType::Standard
+            },
+        ]
+    );
+
+    let third = non_resident_data_attribute(0x50, 0x5F, &[0x11, 0x10, 0x70, 0x00]);
+    assert!(matches!(
+        combine_data_runs(&[first, third]),
+        Err(Error::NonContiguousDataRuns {
+            expected_vcn: 0x30,
+            found_vcn: 0x50
+        })
+    ));
+
+    let corrupted = non_resident_data_attribute(0, u64::MAX, &[0x11, 0x30, 0x20, 0x00]);
+    assert!(matches!(
+        combine_data_runs(&[corrupted]),
+        Err(Error::InvalidLastVcn { vnc_last: u64::MAX })
+    ));
+}
+
+#[test]
+fn test_iter_attributes_decodes_data_runs() {
+    let mut parser = MftParser::from_path(mft_sample()).unwrap();
+
+    // A fragmented file.
+    let entry = parser.get_entry(27).unwrap();
+    let data_runs = entry
+        .iter_attributes()
+        .filter_map(Result::ok)
+        .find(|a| a.header.type_code == MftAttributeType::DATA)
+        .and_then(|a| a.data.into_data_runs())
+        .unwrap();
+    assert_eq!(data_runs.data_runs.len(), 26);
+
+    // `$BadClus:$Bad` has no valid data, but its (sparse) run still covers the whole volume.
+    let entry = parser.get_entry(8).unwrap();
+    let bad = entry
+        .iter_attributes()
+        .filter_map(Result::ok)
+        .find(|a| a.header.name == "$Bad")
+        .unwrap();
+    let header = match &bad.header.residential_header {
+        ResidentialHeader::NonResident(header) => header.clone(),
+        ResidentialHeader::Resident(_) => panic!("$Bad should be non-resident"),
+    };
+    assert_eq!(header.valid_data_length, 0);
+
+    let data_runs = bad.data.into_data_runs().unwrap().data_runs;
+    assert_eq!(
+        data_runs
+            .iter()
+            .map(|run| run.lcn_length * 2048)
+            .sum::<u64>(),
+        header.allocated_length
+    );
+    assert!(data_runs.iter().all(|run| run.run_type == RunType::Sparse));
+}
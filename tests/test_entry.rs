@@ -1,14 +1,19 @@
 mod fixtures;
 
 use fixtures::*;
-use mft::entry::MftEntry;
+use mft::entry::{DataLayout, DataSizeConsistency, MftEntry, MftEntryRef};
+use std::borrow::Cow;
+use std::io::Cursor;
 use mft::mft::MftParser;
 use mft::attribute::{MftAttribute, MftAttributeType, FileAttributeFlags};
 use mft::attribute::x90::{IndexEntryHeader, IndexEntryFlags, IndexCollationRules};
 use mft::attribute::x30::{FileNameAttr, FileNamespace};
+use mft::attribute::x10::RawTimestamps;
 use winstructs::ntfs:: mft_reference::MftReference;
 use winstructs::timestamp::WinTimestamp;
 use serde_json;
+use mft::err::Error;
+use num_traits::ToPrimitive;
 
 #[test]
 fn test_entry_invalid_fixup_value() {
@@ -26,6 +31,80 @@ fn test_entry_invalid_fixup_value() {
     );
 }
 
+#[test]
+fn test_entry_ref_matches_owned_entry() {
+    let mft_entry_buffer = include_bytes!("../samples/entry_102130_fixup_issue");
+
+    let owned =
+        MftEntry::from_buffer(mft_entry_buffer.to_vec(), 102130).expect("Failed to parse entry");
+    let borrowed = MftEntryRef::from_slice(mft_entry_buffer, 102130).expect("Failed to parse entry");
+
+    assert_eq!(borrowed.valid_fixup, owned.valid_fixup);
+    assert_eq!(borrowed.data.as_ref(), owned.data.as_slice());
+    assert_eq!(
+        serde_json::to_value(borrowed.iter_attributes().filter_map(Result::ok).collect::<Vec<_>>()).unwrap(),
+        serde_json::to_value(owned.iter_attributes().filter_map(Result::ok).collect::<Vec<_>>()).unwrap()
+    );
+
+    // Once the fixups were applied, there is nothing left to change, so the data is not copied.
+    let fixed_up = MftEntryRef::from_slice(&owned.data, 102130).expect("Failed to parse entry");
+    assert!(matches!(fixed_up.data, Cow::Borrowed(_)));
+    assert_eq!(
+        fixed_up.iter_attributes().count(),
+        owned.iter_attributes().count()
+    );
+}
+
+#[test]
+fn test_entry_with_zero_attribute_offset_has_no_attributes() {
+    let mut mft_entry_buffer = include_bytes!("../samples/entry_102130_fixup_issue").to_vec();
+    // `first_attribute_record_offset` is at offset 0x14 of the header.
+    mft_entry_buffer[0x14..0x16].copy_from_slice(&[0, 0]);
+
+    let entry = MftEntry::from_buffer(mft_entry_buffer, 102130).expect("Failed to parse entry");
+
+    assert!(!entry.header.has_valid_attribute_offset());
+    assert_eq!(entry.iter_attributes().count(), 0);
+    assert_eq!(entry.attribute_headers().count(), 0);
+    assert!(entry.find_best_name_attribute().is_none());
+}
+
+#[test]
+fn test_entry_file_names() {
+    let mut parser = MftParser::from_path(mft_sample()).unwrap();
+    let entry = parser.get_entry(62).unwrap();
+
+    let names: Vec<(String, FileNamespace)> = entry
+        .file_names()
+        .into_iter()
+        .map(|f| (f.name, f.namespace))
+        .collect();
+
+    assert_eq!(
+        names,
+        vec![
+            ("REPOSI~1".to_string(), FileNamespace::DOS),
+            ("Repository".to_string(), FileNamespace::Win32),
+        ]
+    );
+    assert_eq!(entry.find_best_name_attribute().unwrap().name, "Repository");
+    assert_eq!(
+        entry
+            .find_best_name_attribute_with_preference(FileNamespace::DOS)
+            .unwrap()
+            .name,
+        "REPOSI~1"
+    );
+    // There is no POSIX name, so the Win32 name is used.
+    assert_eq!(
+        entry
+            .find_best_name_attribute_with_preference(FileNamespace::POSIX)
+            .unwrap()
+            .name,
+        "Repository"
+    );
+}
+
 #[test]
 fn test_entry_index_root() {
     let sample = mft_sample_name("entry_multiple_index_root_entries");
@@ -37,6 +116,8 @@ fn test_entry_index_root() {
             if attribute.header.type_code == MftAttributeType::IndexRoot {
                 let index_root = attribute.data.into_index_root().unwrap();
                 assert_eq!(index_root.collation_rule, IndexCollationRules::CollationFilename);
+                // The terminating entry points to the rightmost sub-node.
+                assert_eq!(index_root.index_entries.end_sub_node_vcn, Some(4));
                 let index_entries = index_root.index_entries.index_entries;
                 assert_eq!(index_entries.len(), 4);
 
@@ -60,18 +141,389 @@ fn test_entry_index_root() {
                         modified: created,
                         mft_modified: mft_modified,
                         accessed: mft_modified,
+                        raw_timestamps: RawTimestamps {
+                            created: u64::from_le_bytes([0x00, 0x00, 0xC1, 0x03, 0xDB, 0x6A, 0xC6, 0x01]),
+                            modified: u64::from_le_bytes([0x00, 0x00, 0xC1, 0x03, 0xDB, 0x6A, 0xC6, 0x01]),
+                            mft_modified: u64::from_le_bytes([0x76, 0x86, 0xF6, 0x8C, 0x04, 0x64, 0xCA, 0x01]),
+                            accessed: u64::from_le_bytes([0x76, 0x86, 0xF6, 0x8C, 0x04, 0x64, 0xCA, 0x01]),
+                        },
                         logical_size: 4096,
                         physical_size: 1484,
                         flags: FileAttributeFlags::FILE_ATTRIBUTE_ARCHIVE,
                         reparse_value: 0,
                         name_length: 22,
                         namespace: FileNamespace::Win32,
-                        name: "test_returnfuncptrs.py".to_string()
-                    }
+                        name: "test_returnfuncptrs.py".to_string(),
+                        name_had_decode_errors: false
+                    },
+                    sub_node_vcn: Some(3)
                 };                
                 let last_index_entry = &index_entries[3];
                 assert_eq!(last_index_entry, &index_entry_comp);
+
+                let sub_node_vcns: Vec<Option<u64>> = index_entries.iter().map(|e| e.sub_node_vcn).collect();
+                assert_eq!(sub_node_vcns, vec![Some(0), Some(1), Some(2), Some(3)]);
             }
         }
     }
 }
+
+#[test]
+fn test_entry_attribute_at_matches_iter_attributes() {
+    let sample = mft_sample();
+    let mut parser = MftParser::from_path(sample).unwrap();
+
+    let entry = parser.get_entry(0).unwrap();
+
+    let headers: Vec<_> = entry.attribute_headers().filter_map(Result::ok).collect();
+    let attributes: Vec<MftAttribute> = entry.iter_attributes().filter_map(Result::ok).collect();
+
+    assert_eq!(headers.len(), attributes.len());
+
+    for (i, attribute) in attributes.iter().enumerate() {
+        let lazy = entry.attribute_at(i).unwrap().unwrap();
+        assert_eq!(lazy.header.type_code, attribute.header.type_code);
+        assert_eq!(lazy.header.start_offset, attribute.header.start_offset);
+    }
+
+    assert!(entry.attribute_at(attributes.len()).is_none());
+}
+
+#[test]
+fn test_total_allocated_is_omitted_when_absent() {
+    let sample = mft_sample_name("entry_data_run_at_offset");
+    let mut parser = MftParser::from_path(sample).unwrap();
+
+    let entry = parser.get_entry(0).unwrap();
+    let json = serde_json::to_value(&entry).unwrap();
+
+    for attribute in json["attributes"].as_array().unwrap() {
+        let residential_header = &attribute["header"]["residential_header"];
+        if let Some(total_allocated) = residential_header.get("total_allocated") {
+            assert!(!total_allocated.is_null());
+        }
+    }
+}
+
+#[test]
+fn test_attributes_are_serialized_with_their_type_name() {
+    let mut parser = MftParser::from_path(mft_sample()).unwrap();
+
+    let entry = parser.get_entry(0).unwrap();
+    let json = serde_json::to_value(&entry).unwrap();
+
+    let type_names: Vec<&str> = json["attributes"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .map(|attribute| attribute["type_name"].as_str().unwrap())
+        .collect();
+
+    assert_eq!(type_names, vec!["StandardInformation", "FileName", "DATA", "BITMAP"]);
+    // The existing field is kept.
+    assert_eq!(json["attributes"][0]["header"]["type_code"], "StandardInformation");
+}
+
+#[test]
+fn test_quota_entries() {
+    let sample = mft_sample();
+    let mut parser = MftParser::from_path(sample).unwrap();
+
+    // $Extend\$Quota
+    let entry = parser.get_entry(24).unwrap();
+    let entries = mft::quota::quota_entries(&entry).unwrap();
+
+    assert_eq!(entries.len(), 2);
+    // The quota defaults entry has no SID.
+    assert!(entries[0].sid.is_none());
+    assert_eq!(entries[1].owner_id, 256);
+    assert_eq!(
+        entries[1].sid.as_ref().map(|s| s.to_string()),
+        Some("S-1-5-32-544".to_string())
+    );
+}
+
+#[test]
+fn test_attribute_data_flag_predicates() {
+    let sample = mft_sample();
+    let mut parser = MftParser::from_path(sample).unwrap();
+
+    let entry = parser.get_entry(3616).unwrap();
+    let data = entry
+        .iter_attributes_matching(Some(vec![MftAttributeType::DATA]))
+        .next()
+        .unwrap()
+        .unwrap();
+
+    assert!(data.is_compressed());
+    assert!(!data.is_sparse());
+    assert!(!data.is_encrypted());
+
+    let standard_info = entry
+        .iter_attributes_matching(Some(vec![MftAttributeType::StandardInformation]))
+        .next()
+        .unwrap()
+        .unwrap();
+
+    assert!(!standard_info.is_compressed());
+}
+
+#[test]
+fn test_data_size_consistency() {
+    let sample = mft_sample();
+    let mut parser = MftParser::from_path(sample).unwrap();
+
+    // $MFT
+    let entry = parser.get_entry(0).unwrap();
+    assert_eq!(entry.data_size_consistency(), DataSizeConsistency::Consistent);
+
+    // The root directory
+    let entry = parser.get_entry(5).unwrap();
+    assert_eq!(entry.data_size_consistency(), DataSizeConsistency::NotApplicable);
+
+    // $AttrDef records 36000 bytes in its $FILE_NAME attribute, but has only 2560 bytes of data.
+    let entry = parser.get_entry(4).unwrap();
+    assert_eq!(entry.data_size_consistency(), DataSizeConsistency::SizeMismatch);
+}
+
+#[test]
+fn test_is_reserved_record() {
+    let mut parser = MftParser::from_path(mft_sample()).unwrap();
+
+    let reserved: Vec<u64> = (0..30)
+        .map(|i| parser.get_entry(i).unwrap())
+        .filter(|entry| entry.is_reserved_record())
+        .map(|entry| entry.header.record_number)
+        .collect();
+
+    assert_eq!(reserved, (12..24).collect::<Vec<u64>>());
+}
+
+#[test]
+fn test_timeline_attributes_match_iter_attributes() {
+    let mut parser = MftParser::from_path(mft_sample()).unwrap();
+
+    for entry in parser.iter_entries().take(1000).filter_map(Result::ok) {
+        let attributes: Vec<MftAttribute> = entry.iter_attributes().filter_map(Result::ok).collect();
+        let (standard_info, file_names) = entry.timeline_attributes();
+
+        let expected_standard_info = attributes
+            .iter()
+            .find_map(|a| a.data.clone().into_standard_info());
+        let expected_file_names: Vec<FileNameAttr> = attributes
+            .iter()
+            .filter_map(|a| a.data.clone().into_file_name())
+            .collect();
+
+        assert_eq!(
+            serde_json::to_value(&standard_info).unwrap(),
+            serde_json::to_value(&expected_standard_info).unwrap()
+        );
+        assert_eq!(
+            serde_json::to_value(&file_names).unwrap(),
+            serde_json::to_value(&expected_file_names).unwrap()
+        );
+    }
+}
+
+#[test]
+fn test_is_badclus_bad() {
+    let mut parser = MftParser::from_path(mft_sample()).unwrap();
+
+    let badclus = parser.get_entry(8).unwrap();
+    let flagged: Vec<MftAttribute> = badclus
+        .iter_attributes()
+        .filter_map(Result::ok)
+        .filter(|a| badclus.is_badclus_bad(a))
+        .collect();
+
+    assert_eq!(flagged.len(), 1);
+    assert_eq!(flagged[0].header.name, "$Bad");
+    assert!(flagged[0].data.clone().into_data_runs().is_some());
+
+    // Only the stream of $BadClus is flagged.
+    let mft = parser.get_entry(0).unwrap();
+    assert!(!mft.iter_attributes().filter_map(Result::ok).any(|a| mft.is_badclus_bad(&a)));
+}
+
+#[test]
+fn test_identity_key() {
+    let mut parser = MftParser::from_path(mft_sample()).unwrap();
+
+    assert_eq!(
+        parser.get_entry(3).unwrap().identity_key().as_deref(),
+        Some("CC80E6BA-4C45-4742-B372-24956C937B9D|2007-06-30T12:50:52.252395200Z|$Volume")
+    );
+    // Files without an object id.
+    assert_eq!(
+        parser.get_entry(62).unwrap().identity_key().as_deref(),
+        Some("|2007-06-30T12:50:59.382648000Z|Repository")
+    );
+
+    // The key does not depend on the record number.
+    let volume = parser.get_entry(3).unwrap();
+    let moved = MftEntry::from_buffer(volume.data.clone(), 1000).unwrap();
+    assert_eq!(moved.identity_key(), volume.identity_key());
+
+    let zeroed = MftEntry::from_buffer(vec![0; 1024], 1000).unwrap();
+    assert_eq!(zeroed.identity_key(), None);
+}
+
+#[test]
+fn test_data_layout() {
+    let mut parser = MftParser::from_path(mft_sample()).unwrap();
+
+    // Directories have no unnamed $DATA attribute.
+    assert_eq!(parser.get_entry(5).unwrap().data_layout(), None);
+
+    assert_eq!(
+        parser.get_entry(209).unwrap().data_layout(),
+        Some(DataLayout {
+            is_resident: true,
+            fragment_count: 0,
+            logical_size: 231,
+            allocated_size: 231,
+            is_compressed: false,
+            is_sparse: false,
+        })
+    );
+
+    assert_eq!(
+        parser.get_entry(0).unwrap().data_layout(),
+        Some(DataLayout {
+            is_resident: false,
+            fragment_count: 4,
+            logical_size: 13402112,
+            allocated_size: 13402112,
+            is_compressed: false,
+            is_sparse: false,
+        })
+    );
+
+    // Compressed files only allocate part of their logical size.
+    assert_eq!(
+        parser.get_entry(3616).unwrap().data_layout(),
+        Some(DataLayout {
+            is_resident: false,
+            fragment_count: 16,
+            logical_size: 504678,
+            allocated_size: 219136,
+            is_compressed: true,
+            is_sparse: false,
+        })
+    );
+}
+
+#[test]
+fn test_object_id_timestamp() {
+    let mut parser = MftParser::from_path(mft_sample()).unwrap();
+
+    let object_id = |entry: MftEntry| {
+        entry
+            .iter_attributes_matching(Some(vec![MftAttributeType::ObjectId]))
+            .filter_map(Result::ok)
+            .find_map(|a| a.data.into_object_id())
+            .unwrap()
+    };
+
+    // A time-based (version 1) object id.
+    let time_based = object_id(parser.get_entry(462).unwrap());
+    assert_eq!(
+        time_based.object_id_timestamp().unwrap().to_rfc3339(),
+        "2007-06-30T12:58:40.500004100+00:00"
+    );
+
+    // A random (version 4) object id.
+    let random = object_id(parser.get_entry(3).unwrap());
+    assert_eq!(random.object_id_timestamp(), None);
+}
+
+#[test]
+fn test_has_suspicious_name() {
+    let mut parser = MftParser::from_path(mft_sample()).unwrap();
+
+    // The root directory is named `.`, which is not a trailing dot.
+    assert!(!parser.get_entry(5).unwrap().has_suspicious_name());
+    assert_eq!(
+        parser
+            .iter_entries()
+            .filter_map(Result::ok)
+            .filter(|entry| entry.has_suspicious_name())
+            .count(),
+        0
+    );
+}
+
+#[test]
+fn test_attribute_types() {
+    let mut parser = MftParser::from_path(mft_sample()).unwrap();
+
+    assert_eq!(
+        parser.get_entry(0).unwrap().attribute_types(),
+        vec![
+            MftAttributeType::StandardInformation,
+            MftAttributeType::FileName,
+            MftAttributeType::DATA,
+            MftAttributeType::BITMAP,
+        ]
+    );
+
+    // Repository has both a long and a short $FILE_NAME, which are listed once.
+    let types = parser.get_entry(62).unwrap().attribute_types();
+    assert_eq!(
+        types
+            .iter()
+            .filter(|t| **t == MftAttributeType::FileName)
+            .count(),
+        1
+    );
+}
+
+#[test]
+fn test_extract_data() {
+    let mut parser = MftParser::from_path(mft_sample()).unwrap();
+
+    // $Boot is stored in the first 4 clusters (of 2048 bytes) of the volume.
+    let boot = parser.get_entry(7).unwrap();
+    let image: Vec<u8> = (0..5 * 2048).map(|i| (i / 2048) as u8).collect();
+
+    let data = boot
+        .extract_data(&mut Cursor::new(&image), 2048, None)
+        .unwrap();
+    assert_eq!(data, image[..4 * 2048]);
+
+    // The runs of $Boot do not fit in a single cluster.
+    assert!(matches!(
+        boot.extract_data(&mut Cursor::new(&image[..2048]), 2048, None),
+        Err(Error::DataRunOutOfBounds { offset: 0, length: 8192, image_size: 2048 })
+    ));
+
+    assert!(matches!(
+        boot.extract_data(&mut Cursor::new(&image), 2048, Some("Zone.Identifier")),
+        Err(Error::MissingDataStream { entry_id: 7, .. })
+    ));
+}
+
+#[test]
+fn test_extract_resident_alternate_data_stream() {
+    let mut parser = MftParser::from_path(mft_sample()).unwrap();
+    let entry = parser.get_entry(12938).unwrap();
+
+    // Resident streams are not read from the image.
+    let zone_identifier = entry
+        .extract_data(&mut Cursor::new(&[]), 2048, Some("Zone.Identifier"))
+        .unwrap();
+
+    assert_eq!(zone_identifier, b"[ZoneTransfer]\r\nZoneId=3\r\n");
+}
+
+#[test]
+fn test_attribute_type_names() {
+    assert_eq!(MftAttributeType::DATA.as_str(), "DATA");
+    assert_eq!(MftAttributeType::StandardInformation.to_string(), "StandardInformation");
+    assert_eq!(
+        serde_json::to_value(MftAttributeType::FileName).unwrap(),
+        serde_json::Value::from("FileName")
+    );
+    assert_eq!(MftAttributeType::FileName.to_u32(), Some(0x30));
+}